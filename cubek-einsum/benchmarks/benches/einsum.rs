@@ -10,7 +10,7 @@ use cubecl::{
     prelude::*,
     std::tensor::TensorHandle,
 };
-use cubek_einsum::{einsum, EinsumConfig, ContractionStrategy};
+use cubek_einsum::{einsum, einsum_output_shape, EinsumConfig, ContractionStrategy};
 use cubek::random::random_uniform;
 use half::f16;
 
@@ -41,8 +41,8 @@ impl<R: Runtime, E: CubePrimitive + Numeric> Benchmark for EinsumBench<R, E> {
             tensor
         }).collect();
 
-        // Compute output shape based on notation
-        let output_shape = compute_output_shape(&self.notation, &self.shapes);
+        let shapes: Vec<&[usize]> = self.shapes.iter().map(|s| s.as_slice()).collect();
+        let output_shape = einsum_output_shape(self.notation, &shapes).unwrap();
         let output = TensorHandle::empty(&client, output_shape, dtype);
 
         (inputs, output)
@@ -51,10 +51,12 @@ impl<R: Runtime, E: CubePrimitive + Numeric> Benchmark for EinsumBench<R, E> {
     fn execute(&self, (inputs, mut output): Self::Input) -> Result<Self::Output, String> {
         let input_refs: Vec<&TensorHandle<R>> = inputs.iter().collect();
         let config = EinsumConfig {
-            strategy: self.strategy,
+            strategy: self.strategy.clone(),
             use_tensor_cores: true,
             autotune: false,
             validate_shapes: false,
+            allow_output_view: false,
+            plan_cache: false,
         };
 
         einsum::<R, E>(
@@ -94,46 +96,6 @@ impl<R: Runtime, E: CubePrimitive + Numeric> Benchmark for EinsumBench<R, E> {
     }
 }
 
-/// Computes output shape from einsum notation and input shapes.
-fn compute_output_shape(notation: &str, shapes: &[Vec<usize>]) -> Vec<usize> {
-    use std::collections::HashMap;
-
-    // Parse notation
-    let parts: Vec<&str> = notation.split("->").collect();
-    if parts.len() != 2 {
-        // If no '->' provided, fall back to scalar (our benchmarks always use explicit ->).
-        return vec![1];
-    }
-
-    let inputs_str = parts[0];
-    let output_str = parts[1];
-
-    // Build dimension map
-    let mut dim_map: HashMap<char, usize> = HashMap::new();
-    let input_subscripts: Vec<&str> = inputs_str.split(',').collect();
-
-    for (i, subscript) in input_subscripts.iter().enumerate() {
-        if i >= shapes.len() {
-            break;
-        }
-        for (j, c) in subscript.chars().enumerate() {
-            if j < shapes[i].len() && c.is_alphabetic() {
-                dim_map.insert(c, shapes[i][j]);
-            }
-        }
-    }
-
-    // Build output shape
-    if output_str.is_empty() {
-        vec![1] // Scalar
-    } else {
-        output_str.chars()
-            .filter(|c| c.is_alphabetic())
-            .filter_map(|c| dim_map.get(&c).copied())
-            .collect()
-    }
-}
-
 /// Estimates FLOPs for an einsum operation.
 ///
 /// For binary operations (2 inputs), uses the standard matmul formula: 2*M*K*N.
@@ -0,0 +1,431 @@
+//! Offline contraction path planner.
+//!
+//! Plans an einsum expression for the given shapes and prints the path,
+//! without a GPU. Run with `--help` for the syntax.
+
+use std::io::{self, BufRead, Write};
+use std::process::ExitCode;
+use std::time::Instant;
+
+use cubek_einsum::notation::{parse_einsum, validate_notation, validation::validate_shapes};
+use cubek_einsum::optimization::{
+    create_plan_with_cost_model, explain_plan, ContractionStrategy, CostModel, Objective, PlanReport,
+};
+use serde::Serialize;
+
+const USAGE: &str = "\
+Usage: cubek-einsum-plan [OPTIONS] [NOTATION OPERAND...]
+
+Plans an einsum expression and prints its contraction path.
+
+Operands are either one shape per input (`2x30`, `scalar` for a 0-d
+input) or a size dictionary (`i=2,j=30,k=40`, possibly split over several
+arguments). Without NOTATION, expressions are read from stdin, one per
+line, in the same syntax.
+
+Options:
+  -s, --strategy <NAME>     auto, greedy, random-greedy, partition, optimal,
+                            connected, branch-bound or all [default: auto]
+  -c, --cost-model <NAME>   gpu, cpu or a memory penalty factor [default: gpu]
+  -m, --memory-limit <N>    largest intermediate allowed, in elements
+  -o, --objective <NAME>    combined, flops, size or write [default: combined]
+  -f, --format <NAME>       table or json, an array with one report per
+                            strategy [default: table]
+  -h, --help                Print this help
+
+Example:
+  cubek-einsum-plan -s all ij,jk,kl->il 2x30 30x40 40x5";
+
+const RANDOM_GREEDY: ContractionStrategy = ContractionStrategy::RandomGreedy {
+    trials: 128,
+    temperature: 1.0,
+    seed: 0,
+};
+
+const PARTITION: ContractionStrategy = ContractionStrategy::Partition { trials: 8, seed: 0 };
+
+const CONNECTED: ContractionStrategy = ContractionStrategy::ConnectedOptimal { outer_products: false };
+
+const BRANCH_BOUND: ContractionStrategy = ContractionStrategy::branch_bound();
+
+const ALL_STRATEGIES: [ContractionStrategy; 7] = [
+    ContractionStrategy::Greedy,
+    RANDOM_GREEDY,
+    PARTITION,
+    ContractionStrategy::Optimal,
+    CONNECTED,
+    BRANCH_BOUND,
+    ContractionStrategy::Auto,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Table,
+    Json,
+}
+
+#[derive(Debug)]
+struct Options {
+    strategies: Vec<ContractionStrategy>,
+    cost_model: CostModel,
+    format: Format,
+    expression: Vec<String>,
+}
+
+/// A planned expression under one strategy.
+#[derive(Serialize)]
+struct StrategyReport {
+    strategy: ContractionStrategy,
+    planning_time_us: u128,
+    report: PlanReport,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+
+    let options = match parse_options(&args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let expressions: Vec<Vec<String>> = if options.expression.is_empty() {
+        io::stdin()
+            .lock()
+            .lines()
+            .map_while(Result::ok)
+            .filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .map(|line| line.split_whitespace().map(String::from).collect())
+            .collect()
+    } else {
+        vec![options.expression.clone()]
+    };
+
+    let mut stdout = io::stdout().lock();
+    let mut status = ExitCode::SUCCESS;
+    for expression in &expressions {
+        let result = plan_expression(expression, &options)
+            .and_then(|reports| print_reports(&mut stdout, &reports, options.format));
+        if let Err(message) = result {
+            eprintln!("error: {}: {message}", expression.join(" "));
+            status = ExitCode::FAILURE;
+        }
+    }
+
+    status
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        strategies: vec![ContractionStrategy::Auto],
+        cost_model: CostModel::gpu(),
+        format: Format::Table,
+        expression: Vec::new(),
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .cloned()
+                .ok_or_else(|| format!("missing value for {name}"))
+        };
+
+        match arg.as_str() {
+            "-s" | "--strategy" => options.strategies = parse_strategy(&value(arg)?)?,
+            "-c" | "--cost-model" => {
+                options.cost_model = CostModel {
+                    memory_limit: options.cost_model.memory_limit,
+                    objective: options.cost_model.objective,
+                    ..parse_cost_model(&value(arg)?)?
+                }
+            }
+            "-o" | "--objective" => options.cost_model.objective = parse_objective(&value(arg)?)?,
+            "-m" | "--memory-limit" => {
+                let limit = value(arg)?;
+                let limit = limit
+                    .parse()
+                    .map_err(|_| format!("invalid memory limit '{limit}'"))?;
+                options.cost_model.memory_limit = Some(limit);
+            }
+            "-f" | "--format" => {
+                options.format = match value(arg)?.as_str() {
+                    "table" => Format::Table,
+                    "json" => Format::Json,
+                    other => return Err(format!("unknown format '{other}'")),
+                }
+            }
+            flag if flag.starts_with('-') => return Err(format!("unknown option '{flag}'")),
+            _ => options.expression.push(arg.clone()),
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_strategy(name: &str) -> Result<Vec<ContractionStrategy>, String> {
+    let strategy = match name {
+        "auto" => ContractionStrategy::Auto,
+        "greedy" => ContractionStrategy::Greedy,
+        "random-greedy" | "rg" => RANDOM_GREEDY,
+        "partition" => PARTITION,
+        "optimal" | "dp" => ContractionStrategy::Optimal,
+        "connected" | "dpccp" => CONNECTED,
+        "branch-bound" | "bb" => BRANCH_BOUND,
+        "all" => return Ok(ALL_STRATEGIES.to_vec()),
+        other => return Err(format!("unknown strategy '{other}'")),
+    };
+
+    Ok(vec![strategy])
+}
+
+fn parse_cost_model(name: &str) -> Result<CostModel, String> {
+    match name {
+        "gpu" => Ok(CostModel::gpu()),
+        "cpu" => Ok(CostModel::cpu()),
+        alpha => alpha
+            .parse()
+            .map(|alpha| CostModel { alpha, ..CostModel::default() })
+            .map_err(|_| format!("unknown cost model '{alpha}'")),
+    }
+}
+
+fn parse_objective(name: &str) -> Result<Objective, String> {
+    match name {
+        "combined" => Ok(Objective::Combined),
+        "flops" => Ok(Objective::Flops),
+        "size" => Ok(Objective::Size),
+        "write" => Ok(Objective::Write),
+        other => Err(format!("unknown objective '{other}'")),
+    }
+}
+
+/// Resolves the operand arguments of an expression into input shapes.
+fn parse_shapes(notation: &str, operands: &[String]) -> Result<Vec<Vec<usize>>, String> {
+    let parse_dim = |dim: &str| {
+        dim.trim()
+            .parse::<usize>()
+            .map_err(|_| format!("invalid dimension '{dim}'"))
+    };
+
+    if !operands.iter().any(|op| op.contains('=')) {
+        return operands
+            .iter()
+            .map(|op| match op.as_str() {
+                "scalar" => Ok(Vec::new()),
+                op => op.split('x').map(parse_dim).collect(),
+            })
+            .collect();
+    }
+
+    let mut sizes = std::collections::HashMap::new();
+    for entry in operands.iter().flat_map(|op| op.split(',')) {
+        let (index, size) = entry
+            .split_once('=')
+            .ok_or_else(|| format!("expected 'index=size', got '{entry}'"))?;
+        let mut chars = index.trim().chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => sizes.insert(c, parse_dim(size)?),
+            _ => return Err(format!("invalid index '{index}'")),
+        };
+    }
+
+    let notation = parse_einsum(notation).map_err(|e| e.to_string())?;
+    if notation.has_ellipsis() {
+        return Err("a size dictionary cannot describe ellipsis dimensions, pass shapes".into());
+    }
+
+    notation
+        .inputs()
+        .iter()
+        .map(|input| {
+            input
+                .named_indices()
+                .map(|c| sizes.get(&c).copied().ok_or_else(|| format!("no size for index '{c}'")))
+                .collect()
+        })
+        .collect()
+}
+
+fn plan_expression(expression: &[String], options: &Options) -> Result<Vec<StrategyReport>, String> {
+    let (notation_str, operands) = expression
+        .split_first()
+        .ok_or_else(|| String::from("missing notation"))?;
+
+    let shapes = parse_shapes(notation_str, operands)?;
+    let shapes: Vec<&[usize]> = shapes.iter().map(|s| s.as_slice()).collect();
+
+    let notation = parse_einsum(notation_str).map_err(|e| e.to_string())?;
+    validate_notation(&notation).map_err(|e| e.to_string())?;
+    validate_shapes(&notation, &shapes).map_err(|e| e.to_string())?;
+
+    options
+        .strategies
+        .iter()
+        .map(|strategy| {
+            let start = Instant::now();
+            let plan = create_plan_with_cost_model(&notation, &shapes, strategy.clone(), &options.cost_model)
+                .map_err(|e| e.to_string())?;
+            let planning_time_us = start.elapsed().as_micros();
+            let report = explain_plan(&notation, &shapes, &plan).map_err(|e| e.to_string())?;
+
+            Ok(StrategyReport {
+                strategy: strategy.clone(),
+                planning_time_us,
+                report,
+            })
+        })
+        .collect()
+}
+
+fn print_reports(out: &mut impl Write, reports: &[StrategyReport], format: Format) -> Result<(), String> {
+    let result = match (format, reports) {
+        (Format::Json, reports) => serde_json::to_writer_pretty(&mut *out, reports)
+            .map_err(io::Error::from)
+            .and_then(|_| writeln!(out)),
+        (Format::Table, [single]) => write!(out, "{}", single.report),
+        (Format::Table, reports) => write_comparison(out, reports),
+    };
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Prints one line per strategy.
+fn write_comparison(out: &mut impl Write, reports: &[StrategyReport]) -> io::Result<()> {
+    if let Some(first) = reports.first() {
+        writeln!(out, "  Complete contraction:  {}", first.report.notation)?;
+        writeln!(out, "             Fast path:  {}", first.report.fast_path)?;
+        writeln!(out, "      Naive FLOP count:  {}", first.report.naive_flops)?;
+    }
+
+    writeln!(out, "{}", "-".repeat(108))?;
+    writeln!(
+        out,
+        "{:<12}  {:>5}  {:>14}  {:>14}  {:>12}  {:>14}  {:>9}  {:>6}  {:>8}",
+        "strategy", "steps", "flops", "memory", "largest", "written", "speedup", "proven", "time"
+    )?;
+    writeln!(out, "{}", "-".repeat(108))?;
+
+    for entry in reports {
+        let report = &entry.report;
+        let name = match &entry.strategy {
+            ContractionStrategy::RandomGreedy { .. } => String::from("RandomGreedy"),
+            ContractionStrategy::Partition { .. } => String::from("Partition"),
+            ContractionStrategy::ConnectedOptimal { .. } => String::from("Connected"),
+            ContractionStrategy::BranchBound { .. } => String::from("BranchBound"),
+            strategy => format!("{:?}", strategy),
+        };
+        let memory: u64 = report.steps.iter().map(|s| s.memory).sum();
+        writeln!(
+            out,
+            "{:<12}  {:>5}  {:>14}  {:>14}  {:>12}  {:>14}  {:>9.3}  {:>6}  {:>6}us",
+            name,
+            report.steps.len(),
+            report.optimized_flops,
+            memory,
+            report.largest_intermediate,
+            report.total_write,
+            report.speedup(),
+            if report.proven_optimal { "yes" } else { "no" },
+            entry.planning_time_us,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let options = parse_options(&args(&["-s", "all", "--cost-model", "16", "-f", "json", "ij->ji", "2x3"])).unwrap();
+
+        assert_eq!(options.strategies, ALL_STRATEGIES);
+        assert_eq!(options.cost_model.alpha, 16);
+        assert_eq!(options.format, Format::Json);
+        assert_eq!(options.expression, ["ij->ji", "2x3"]);
+
+        let options = parse_options(&args(&["-m", "1000", "-o", "size", "-c", "cpu"])).unwrap();
+        assert_eq!(
+            options.cost_model,
+            CostModel::cpu().with_memory_limit(1000).with_objective(Objective::Size)
+        );
+        assert!(parse_options(&args(&["--objective", "time"])).is_err());
+        assert!(parse_options(&args(&["--memory-limit", "1e3"])).is_err());
+
+        assert!(parse_options(&args(&["--strategy"])).is_err());
+        assert!(parse_options(&args(&["--strategy", "fastest"])).is_err());
+        assert!(parse_options(&args(&["--verbose"])).is_err());
+        assert!(parse_options(&args(&["-v", "ij->ji", "2x3"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_shapes() {
+        let shapes = parse_shapes("ij,j->i", &args(&["2x3", "3"])).unwrap();
+        assert_eq!(shapes, [vec![2, 3], vec![3]]);
+
+        let shapes = parse_shapes("ij,->i", &args(&["2x3", "scalar"])).unwrap();
+        assert_eq!(shapes, [vec![2, 3], vec![]]);
+
+        assert!(parse_shapes("ij->i", &args(&["2xa"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_size_dictionary() {
+        let shapes = parse_shapes("ij,jk->ik", &args(&["i=2,j=3", "k=4"])).unwrap();
+        assert_eq!(shapes, [vec![2, 3], vec![3, 4]]);
+
+        assert!(parse_shapes("ij,jk->ik", &args(&["i=2,j=3"])).is_err());
+        assert!(parse_shapes("...ij->...i", &args(&["i=2,j=3"])).is_err());
+    }
+
+    #[test]
+    fn test_plan_expression() {
+        let options = parse_options(&args(&["-s", "all"])).unwrap();
+        let reports = plan_expression(&args(&["ij,jk,kl->il", "2x30", "30x40", "40x5"]), &options).unwrap();
+
+        assert_eq!(reports.len(), ALL_STRATEGIES.len());
+        for entry in &reports {
+            assert_eq!(entry.report.output_shape, [2, 5]);
+            assert_eq!(entry.report.steps.len(), 2);
+        }
+
+        let mut out = Vec::new();
+        print_reports(&mut out, &reports, Format::Table).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("BranchBound"));
+        assert!(text.contains("RandomGreedy  "));
+        assert!(text.contains("proven"));
+
+        let mut out = Vec::new();
+        print_reports(&mut out, &reports, Format::Json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), ALL_STRATEGIES.len());
+
+        // A single strategy is still printed as an array
+        let mut out = Vec::new();
+        print_reports(&mut out, &reports[..1], Format::Json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_plan_expression_invalid() {
+        let options = parse_options(&[]).unwrap();
+
+        assert!(plan_expression(&args(&["ij,jk->ik", "2x3", "4x5"]), &options).is_err());
+        assert!(plan_expression(&args(&["ij,jk->ik", "2x3"]), &options).is_err());
+    }
+}
@@ -95,18 +95,18 @@ fn materialize_and_multiply<R: Runtime, E: CubePrimitive + Numeric>(
     rhs: &TensorHandle<R>,
     output: &mut TensorHandle<R>,
 ) -> EinsumResult<()> {
-    // Materialize lhs if it has broadcast dimensions (stride=0)
-    let lhs_contiguous = if lhs.strides.iter().any(|&s| s == 0) {
-        materialize_tensor::<R, E>(client, lhs, &output.shape)?
-    } else {
+    // Materialize lhs if it has broadcast (stride=0) or permuted dimensions
+    let lhs_contiguous = if is_row_major(lhs) {
         lhs.clone()
+    } else {
+        materialize_tensor::<R, E>(client, lhs, &output.shape)?
     };
 
-    // Materialize rhs if it has broadcast dimensions (stride=0)
-    let rhs_contiguous = if rhs.strides.iter().any(|&s| s == 0) {
-        materialize_tensor::<R, E>(client, rhs, &output.shape)?
-    } else {
+    // Materialize rhs if it has broadcast (stride=0) or permuted dimensions
+    let rhs_contiguous = if is_row_major(rhs) {
         rhs.clone()
+    } else {
+        materialize_tensor::<R, E>(client, rhs, &output.shape)?
     };
 
     // Now both are contiguous with matching shapes, use hadamard
@@ -174,11 +174,11 @@ fn materialize_tensor<R: Runtime, E: CubePrimitive + Numeric>(
                 ScalarArg::new(input.strides[2] as u32),
                 E::as_type_native_unchecked(),
             ),
+            // For rank 4+: generic strided copy, which also reads stride-0 dimensions
             _ => {
-                return Err(EinsumError::launch(alloc::format!(
-                    "broadcast multiply: rank {} exceeds max supported (3)",
-                    rank
-                )));
+                let mut result = result;
+                super::copy_reshape::<R, E>(client, input, &mut result)?;
+                return Ok(result);
             }
         }.map_err(|e| EinsumError::launch(alloc::format!("copy broadcast kernel failed: {:?}", e)))?;
     }
@@ -186,6 +186,18 @@ fn materialize_tensor<R: Runtime, E: CubePrimitive + Numeric>(
     Ok(result)
 }
 
+/// Checks if a tensor is laid out contiguously in row-major order.
+fn is_row_major<R: Runtime>(tensor: &TensorHandle<R>) -> bool {
+    let mut expected = 1;
+    for (&size, &stride) in tensor.shape.iter().zip(&tensor.strides).rev() {
+        if size != 1 && stride != expected {
+            return false;
+        }
+        expected *= size;
+    }
+    true
+}
+
 /// 1D broadcast multiply kernel
 #[cube(launch_unchecked)]
 fn broadcast_multiply_1d<E: Numeric>(
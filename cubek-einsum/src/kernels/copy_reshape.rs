@@ -3,6 +3,9 @@
 //! When a tensor is permuted via stride manipulation and then needs to be
 //! reshaped (merging dimensions), we must copy the data to make it contiguous
 //! in the permuted order before the reshape can work correctly.
+//!
+//! Both sides are addressed through their own shape and strides, so the same
+//! kernel also writes permutations into caller-owned buffers.
 
 use cubecl::prelude::*;
 use cubecl::Runtime;
@@ -14,10 +17,11 @@ use crate::error::{EinsumError, EinsumResult};
 /// Block size for copy kernel.
 const BLOCK_SIZE: u32 = 256;
 
-/// Copies data from a potentially non-contiguous source to a contiguous destination.
+/// Copies data from a potentially non-contiguous source to the destination.
 ///
 /// The source tensor may have non-standard strides (from permutation), and this
-/// function materializes it into a contiguous tensor with the destination's shape.
+/// function materializes it in row-major order into the destination's shape.
+/// The destination's strides are honored as well.
 pub fn copy_reshape<R: Runtime, E: CubePrimitive + Numeric>(
     client: &ComputeClient<R>,
     input: &TensorHandle<R>,
@@ -55,7 +59,42 @@ pub fn copy_reshape<R: Runtime, E: CubePrimitive + Numeric>(
     }
 }
 
-/// Copy kernel using ABSOLUTE_POS for coalesced access.
+/// Writes `input` permuted by `permutation` into `output`.
+///
+/// `output.shape[d]` must equal `input.shape[permutation[d]]`. The output may
+/// have any strides, so results land in the buffer the caller allocated.
+pub fn launch_permute<R: Runtime, E: CubePrimitive + Numeric>(
+    client: &ComputeClient<R>,
+    input: &TensorHandle<R>,
+    output: &mut TensorHandle<R>,
+    permutation: &[usize],
+) -> EinsumResult<()> {
+    if permutation.len() != input.shape.len() {
+        return Err(EinsumError::launch(alloc::format!(
+            "permute: permutation of length {} for rank {} input",
+            permutation.len(),
+            input.shape.len()
+        )));
+    }
+
+    let mut permuted = input.clone();
+    permuted.shape = permutation.iter().map(|&p| input.shape[p]).collect();
+    permuted.strides = permutation.iter().map(|&p| input.strides[p]).collect();
+
+    if permuted.shape != output.shape {
+        return Err(EinsumError::shape(alloc::format!(
+            "permute: output shape {:?} does not match permuted shape {:?}",
+            output.shape, permuted.shape
+        )));
+    }
+
+    copy_reshape::<R, E>(client, &permuted, output)
+}
+
+/// Strided copy kernel.
+///
+/// Each unit handles one row-major position, which is decomposed separately
+/// against the input and output shapes to find both memory offsets.
 #[cube(launch_unchecked)]
 fn copy_kernel<E: Numeric>(
     input: &Tensor<Line<E>>,
@@ -64,6 +103,26 @@ fn copy_kernel<E: Numeric>(
     #[define(E)] _dtype: StorageType,
 ) {
     if ABSOLUTE_POS < num_elements {
-        output[ABSOLUTE_POS] = input[ABSOLUTE_POS];
+        let input_rank = input.rank();
+        let mut input_offset = 0u32;
+        let mut remainder = ABSOLUTE_POS;
+        for i in 0..input_rank {
+            let dim = input_rank - 1 - i;
+            let size = input.shape(dim);
+            input_offset += (remainder % size) * input.stride(dim);
+            remainder /= size;
+        }
+
+        let output_rank = output.rank();
+        let mut output_offset = 0u32;
+        let mut remainder = ABSOLUTE_POS;
+        for i in 0..output_rank {
+            let dim = output_rank - 1 - i;
+            let size = output.shape(dim);
+            output_offset += (remainder % size) * output.stride(dim);
+            remainder /= size;
+        }
+
+        output[output_offset] = input[input_offset];
     }
 }
@@ -5,6 +5,7 @@
 //! - Reduction operations (dot product, trace)
 //! - Diagonal operations (extraction)
 //! - Copy/reshape operations (for materializing permuted tensors)
+//! - Slice copy and accumulation (for sliced plans)
 
 mod hadamard;
 mod outer_product;
@@ -13,6 +14,7 @@ mod dot_product;
 mod trace;
 mod diagonal;
 mod copy_reshape;
+mod slice;
 
 pub use hadamard::launch_hadamard;
 pub use outer_product::launch_outer_product;
@@ -20,4 +22,5 @@ pub use broadcast_multiply::launch_broadcast_multiply;
 pub use dot_product::launch_dot_product;
 pub use trace::launch_trace;
 pub use diagonal::launch_diagonal;
-pub use copy_reshape::copy_reshape;
+pub use copy_reshape::{copy_reshape, launch_permute};
+pub use slice::{launch_accumulate, launch_copy_slice};
@@ -0,0 +1,163 @@
+//! Kernels for sliced execution.
+//!
+//! Sliced plans run their steps on one slice of the inputs at a time: the
+//! slice is copied out of each input holding a sliced index, and the result
+//! of every slice is added to the output.
+
+use cubecl::prelude::*;
+use cubecl::Runtime;
+use cubecl::client::ComputeClient;
+use cubecl::std::tensor::TensorHandle;
+
+use crate::error::{EinsumError, EinsumResult};
+
+/// Block size for slice kernels.
+const BLOCK_SIZE: u32 = 256;
+
+/// Copies one slice of `input` into `output`.
+///
+/// `output` has the rank of `input`, with size 1 along sliced dimensions
+/// and the size of `input` along the others. `offset` is the element offset
+/// of the slice in `input`, the sum of each sliced value times its stride.
+pub fn launch_copy_slice<R: Runtime, E: CubePrimitive + Numeric>(
+    client: &ComputeClient<R>,
+    input: &TensorHandle<R>,
+    output: &mut TensorHandle<R>,
+    offset: usize,
+) -> EinsumResult<()> {
+    let matches = input.shape.len() == output.shape.len()
+        && input.shape.iter().zip(&output.shape).all(|(&i, &o)| o == i || o == 1);
+    if !matches {
+        return Err(EinsumError::shape(alloc::format!(
+            "copy_slice: output shape {:?} is not a slice of input shape {:?}",
+            output.shape, input.shape
+        )));
+    }
+
+    let num_elements: usize = output.shape.iter().product();
+    if num_elements == 0 {
+        return Ok(());
+    }
+
+    // The kernel indexes with u32
+    let (offset, num_elements) = match (u32::try_from(offset), u32::try_from(num_elements)) {
+        (Ok(offset), Ok(num_elements)) => (offset, num_elements),
+        _ => {
+            return Err(EinsumError::launch(alloc::format!(
+                "copy_slice: offset {} or {} elements exceed 32-bit indexing",
+                offset, num_elements
+            )));
+        }
+    };
+
+    let num_cubes = num_elements.div_ceil(BLOCK_SIZE);
+    let cube_dim = CubeDim { x: BLOCK_SIZE, y: 1, z: 1 };
+    let cube_count = CubeCount::Static(num_cubes, 1, 1);
+
+    unsafe {
+        copy_slice_kernel::launch_unchecked::<R>(
+            client,
+            cube_count,
+            cube_dim,
+            input.as_arg(1),
+            output.as_arg(1),
+            ScalarArg::new(offset),
+            ScalarArg::new(num_elements),
+            E::as_type_native_unchecked(),
+        ).map_err(|e| EinsumError::launch(alloc::format!("copy_slice kernel failed: {:?}", e)))
+    }
+}
+
+/// Adds `input` to `output` element-wise.
+///
+/// Both tensors must have the same shape; their strides may differ.
+pub fn launch_accumulate<R: Runtime, E: CubePrimitive + Numeric>(
+    client: &ComputeClient<R>,
+    input: &TensorHandle<R>,
+    output: &mut TensorHandle<R>,
+) -> EinsumResult<()> {
+    if input.shape != output.shape {
+        return Err(EinsumError::shape(alloc::format!(
+            "accumulate: input shape {:?} does not match output shape {:?}",
+            input.shape, output.shape
+        )));
+    }
+
+    let num_elements: usize = output.shape.iter().product();
+    if num_elements == 0 {
+        return Ok(());
+    }
+
+    let num_cubes = ((num_elements as u32) + BLOCK_SIZE - 1) / BLOCK_SIZE;
+    let cube_dim = CubeDim { x: BLOCK_SIZE, y: 1, z: 1 };
+    let cube_count = CubeCount::Static(num_cubes, 1, 1);
+
+    unsafe {
+        accumulate_kernel::launch_unchecked::<R>(
+            client,
+            cube_count,
+            cube_dim,
+            input.as_arg(1),
+            output.as_arg(1),
+            ScalarArg::new(num_elements as u32),
+            E::as_type_native_unchecked(),
+        ).map_err(|e| EinsumError::launch(alloc::format!("accumulate kernel failed: {:?}", e)))
+    }
+}
+
+/// Slice copy kernel.
+///
+/// Each unit handles one row-major position of the output. Sliced
+/// dimensions have size 1 there, so they contribute nothing besides
+/// `offset` to the input offset.
+#[cube(launch_unchecked)]
+fn copy_slice_kernel<E: Numeric>(
+    input: &Tensor<Line<E>>,
+    output: &mut Tensor<Line<E>>,
+    offset: u32,
+    num_elements: u32,
+    #[define(E)] _dtype: StorageType,
+) {
+    if ABSOLUTE_POS < num_elements {
+        let rank = output.rank();
+        let mut input_offset = offset;
+        let mut output_offset = 0u32;
+        let mut remainder = ABSOLUTE_POS;
+        for i in 0..rank {
+            let dim = rank - 1 - i;
+            let size = output.shape(dim);
+            let coordinate = remainder % size;
+            input_offset += coordinate * input.stride(dim);
+            output_offset += coordinate * output.stride(dim);
+            remainder /= size;
+        }
+
+        output[output_offset] = input[input_offset];
+    }
+}
+
+/// Strided accumulation kernel.
+#[cube(launch_unchecked)]
+fn accumulate_kernel<E: Numeric>(
+    input: &Tensor<Line<E>>,
+    output: &mut Tensor<Line<E>>,
+    num_elements: u32,
+    #[define(E)] _dtype: StorageType,
+) {
+    if ABSOLUTE_POS < num_elements {
+        let rank = output.rank();
+        let mut input_offset = 0u32;
+        let mut output_offset = 0u32;
+        let mut remainder = ABSOLUTE_POS;
+        for i in 0..rank {
+            let dim = rank - 1 - i;
+            let size = output.shape(dim);
+            let coordinate = remainder % size;
+            input_offset += coordinate * input.stride(dim);
+            output_offset += coordinate * output.stride(dim);
+            remainder /= size;
+        }
+
+        output[output_offset] = output[output_offset] + input[input_offset];
+    }
+}
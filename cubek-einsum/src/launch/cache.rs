@@ -0,0 +1,289 @@
+//! Process-wide cache of execution plans.
+//!
+//! Path search only depends on the structure of the notation, the input
+//! shapes, the strategy and the cost model, so plans are cached under a key
+//! where index names are normalized: `ij,jk->ik` and `ab,bc->ac` share an
+//! entry.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use std::sync::{Mutex, OnceLock};
+
+use cubecl::ir::StorageType;
+use hashbrown::HashMap;
+
+use crate::error::EinsumResult;
+use crate::notation::{CanonicalOptions, EinsumNotation};
+use crate::optimization::{create_plan_with_cost_model, ContractionStrategy, CostModel, ExecutionPlan};
+
+/// Number of plans kept by the global cache unless changed.
+pub const DEFAULT_PLAN_CACHE_CAPACITY: usize = 256;
+
+/// Key identifying a cached plan.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct PlanKey {
+    notation: EinsumNotation,
+    shapes: Vec<Vec<usize>>,
+    dtype: StorageType,
+    strategy: ContractionStrategy,
+    cost_model: CostModel,
+}
+
+struct Entry {
+    plan: Arc<ExecutionPlan>,
+    last_used: u64,
+}
+
+struct CacheState {
+    entries: HashMap<PlanKey, Entry>,
+    capacity: usize,
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl CacheState {
+    fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            capacity,
+            clock: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Removes least recently used entries until at most `len` remain.
+    fn shrink_to(&mut self, len: usize) {
+        while self.entries.len() > len {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+
+            match oldest {
+                Some(key) => self.entries.remove(&key),
+                None => break,
+            };
+        }
+    }
+}
+
+/// Thread-safe LRU cache of execution plans.
+///
+/// `einsum` consults the [global](PlanCache::global) cache when
+/// [`EinsumConfig::plan_cache`](super::EinsumConfig::plan_cache) is enabled.
+pub struct PlanCache {
+    state: Mutex<CacheState>,
+}
+
+impl PlanCache {
+    /// Creates an empty cache holding at most `capacity` plans.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            state: Mutex::new(CacheState::new(capacity)),
+        }
+    }
+
+    /// Returns the process-wide cache.
+    pub fn global() -> &'static PlanCache {
+        static GLOBAL: OnceLock<PlanCache> = OnceLock::new();
+        GLOBAL.get_or_init(|| PlanCache::new(DEFAULT_PLAN_CACHE_CAPACITY))
+    }
+
+    /// Returns the plan for `notation` and `shapes`, creating it on a miss.
+    ///
+    /// On a miss the plan is created for the canonical notation, so its
+    /// steps refer to canonical index names. The executor only relies on
+    /// the names inside the plan, which makes it valid for every notation
+    /// with the same structure. Planning errors are not cached.
+    pub fn get_or_create(
+        &self,
+        notation: &EinsumNotation,
+        shapes: &[&[usize]],
+        dtype: StorageType,
+        strategy: ContractionStrategy,
+    ) -> EinsumResult<Arc<ExecutionPlan>> {
+        self.get_or_create_with_cost_model(notation, shapes, dtype, strategy, &CostModel::default())
+    }
+
+    /// Like [`get_or_create`](Self::get_or_create), planning with
+    /// `cost_model` on a miss.
+    ///
+    /// Plans created with different cost models are cached separately.
+    pub fn get_or_create_with_cost_model(
+        &self,
+        notation: &EinsumNotation,
+        shapes: &[&[usize]],
+        dtype: StorageType,
+        strategy: ContractionStrategy,
+        cost_model: &CostModel,
+    ) -> EinsumResult<Arc<ExecutionPlan>> {
+        let canonical = notation.canonicalize(CanonicalOptions::default()).notation;
+        let key = PlanKey {
+            notation: canonical.clone(),
+            shapes: shapes.iter().map(|s| s.to_vec()).collect(),
+            dtype,
+            strategy: strategy.clone(),
+            cost_model: cost_model.clone(),
+        };
+
+        {
+            let mut state = self.state.lock().unwrap();
+            state.clock += 1;
+            let clock = state.clock;
+            if let Some(entry) = state.entries.get_mut(&key) {
+                entry.last_used = clock;
+                let plan = entry.plan.clone();
+                state.hits += 1;
+                return Ok(plan);
+            }
+            state.misses += 1;
+        }
+
+        // Plan outside the lock, path search can take a while
+        let plan = Arc::new(create_plan_with_cost_model(&canonical, shapes, strategy, cost_model)?);
+
+        let mut state = self.state.lock().unwrap();
+        if state.capacity == 0 {
+            return Ok(plan);
+        }
+
+        state.clock += 1;
+        let clock = state.clock;
+        if !state.entries.contains_key(&key) {
+            let len = state.capacity - 1;
+            state.shrink_to(len);
+        }
+        state.entries.insert(
+            key,
+            Entry {
+                plan: plan.clone(),
+                last_used: clock,
+            },
+        );
+
+        Ok(plan)
+    }
+
+    /// Changes the capacity, evicting the least recently used plans if needed.
+    pub fn set_capacity(&self, capacity: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.capacity = capacity;
+        state.shrink_to(capacity);
+    }
+
+    /// Returns the maximum number of cached plans.
+    pub fn capacity(&self) -> usize {
+        self.state.lock().unwrap().capacity
+    }
+
+    /// Returns the number of cached plans.
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    /// Returns true if no plan is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of lookups served from the cache.
+    pub fn hits(&self) -> u64 {
+        self.state.lock().unwrap().hits
+    }
+
+    /// Returns the number of lookups that had to create a plan.
+    pub fn misses(&self) -> u64 {
+        self.state.lock().unwrap().misses
+    }
+
+    /// Removes all cached plans and resets the statistics.
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        *state = CacheState::new(state.capacity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_einsum;
+    use cubecl::prelude::*;
+
+    fn dtype() -> StorageType {
+        f32::as_type_native_unchecked()
+    }
+
+    #[test]
+    fn test_renamed_notation_hits() {
+        let cache = PlanCache::new(4);
+        let shapes: &[&[usize]] = &[&[2, 3], &[3, 4], &[4, 5]];
+
+        cache.get_or_create(&parse_einsum("ij,jk,kl->il").unwrap(), shapes, dtype(), ContractionStrategy::Auto).unwrap();
+        cache.get_or_create(&parse_einsum("ab,bc,cd->ad").unwrap(), shapes, dtype(), ContractionStrategy::Auto).unwrap();
+
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(cache.misses(), 1);
+    }
+
+    #[test]
+    fn test_shapes_and_strategy_are_part_of_key() {
+        let cache = PlanCache::new(4);
+        let notation = parse_einsum("ij,jk,kl->il").unwrap();
+
+        cache.get_or_create(&notation, &[&[2, 3], &[3, 4], &[4, 5]], dtype(), ContractionStrategy::Auto).unwrap();
+        cache.get_or_create(&notation, &[&[2, 3], &[3, 4], &[4, 6]], dtype(), ContractionStrategy::Auto).unwrap();
+        cache.get_or_create(&notation, &[&[2, 3], &[3, 4], &[4, 6]], dtype(), ContractionStrategy::Greedy).unwrap();
+
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.hits(), 0);
+    }
+
+    #[test]
+    fn test_cost_model_is_part_of_key() {
+        let cache = PlanCache::new(4);
+        let notation = parse_einsum("ij,jk,kl->il").unwrap();
+        let shapes: &[&[usize]] = &[&[2, 3], &[3, 4], &[4, 5]];
+        let limited = CostModel::default().with_memory_limit(8);
+
+        cache.get_or_create(&notation, shapes, dtype(), ContractionStrategy::Auto).unwrap();
+        cache.get_or_create_with_cost_model(&notation, shapes, dtype(), ContractionStrategy::Auto, &limited).unwrap();
+        cache.get_or_create_with_cost_model(&notation, shapes, dtype(), ContractionStrategy::Auto, &limited).unwrap();
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.hits(), 1);
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let cache = PlanCache::new(2);
+        let a = parse_einsum("ij,jk->ik").unwrap();
+        let b = parse_einsum("ij,kj->ik").unwrap();
+        let c = parse_einsum("ji,jk->ik").unwrap();
+        let shapes: &[&[usize]] = &[&[3, 3], &[3, 3]];
+
+        cache.get_or_create(&a, shapes, dtype(), ContractionStrategy::Auto).unwrap();
+        cache.get_or_create(&b, shapes, dtype(), ContractionStrategy::Auto).unwrap();
+        // Touch `a` so that `b` is the oldest
+        cache.get_or_create(&a, shapes, dtype(), ContractionStrategy::Auto).unwrap();
+        cache.get_or_create(&c, shapes, dtype(), ContractionStrategy::Auto).unwrap();
+
+        assert_eq!(cache.len(), 2);
+        cache.get_or_create(&a, shapes, dtype(), ContractionStrategy::Auto).unwrap();
+        assert_eq!(cache.hits(), 2);
+        cache.get_or_create(&b, shapes, dtype(), ContractionStrategy::Auto).unwrap();
+        assert_eq!(cache.misses(), 4);
+    }
+
+    #[test]
+    fn test_zero_capacity_disables_caching() {
+        let cache = PlanCache::new(0);
+        let notation = parse_einsum("ij,jk->ik").unwrap();
+
+        cache.get_or_create(&notation, &[&[2, 3], &[3, 4]], dtype(), ContractionStrategy::Auto).unwrap();
+        assert!(cache.is_empty());
+    }
+}
@@ -1,27 +1,41 @@
 //! Configuration for einsum operations.
 
-use crate::optimization::ContractionStrategy;
+use crate::optimization::{ContractionStrategy, CostModel, Objective};
 
 /// Configuration options for einsum execution.
 #[derive(Debug, Clone)]
 pub struct EinsumConfig {
     /// Strategy for finding contraction paths.
     pub strategy: ContractionStrategy,
+    /// Cost model the path search minimizes, including its memory limit.
+    pub cost_model: CostModel,
     /// Whether to use tensor cores when available.
     pub use_tensor_cores: bool,
     /// Whether to enable autotuning.
     pub autotune: bool,
     /// Whether to validate shapes before execution.
     pub validate_shapes: bool,
+    /// Whether a pure permutation may replace the output handle with a
+    /// zero-copy view of its input instead of writing into the output buffer.
+    ///
+    /// The returned output then aliases the input. Off by default.
+    pub allow_output_view: bool,
+    /// Whether to reuse plans from the process-wide plan cache.
+    ///
+    /// Requires the `std` feature; ignored otherwise.
+    pub plan_cache: bool,
 }
 
 impl Default for EinsumConfig {
     fn default() -> Self {
         Self {
             strategy: ContractionStrategy::Auto,
+            cost_model: CostModel::default(),
             use_tensor_cores: true,
             autotune: true,
             validate_shapes: true,
+            allow_output_view: false,
+            plan_cache: false,
         }
     }
 }
@@ -38,6 +52,27 @@ impl EinsumConfig {
         self
     }
 
+    /// Sets the cost model used by the path search.
+    pub fn with_cost_model(mut self, cost_model: CostModel) -> Self {
+        self.cost_model = cost_model;
+        self
+    }
+
+    /// Limits intermediate tensors to `elements` elements.
+    ///
+    /// The path search avoids larger intermediates, and execution fails with
+    /// a memory error if the plan still needs one.
+    pub fn with_memory_limit(mut self, elements: u64) -> Self {
+        self.cost_model.memory_limit = Some(elements);
+        self
+    }
+
+    /// Sets what the path search minimizes.
+    pub fn with_objective(mut self, objective: Objective) -> Self {
+        self.cost_model.objective = objective;
+        self
+    }
+
     /// Enables or disables tensor cores.
     pub fn with_tensor_cores(mut self, enabled: bool) -> Self {
         self.use_tensor_cores = enabled;
@@ -56,13 +91,28 @@ impl EinsumConfig {
         self
     }
 
+    /// Allows or forbids aliasing views as outputs of pure permutations.
+    pub fn with_output_view(mut self, enabled: bool) -> Self {
+        self.allow_output_view = enabled;
+        self
+    }
+
+    /// Enables or disables the process-wide plan cache.
+    pub fn with_plan_cache(mut self, enabled: bool) -> Self {
+        self.plan_cache = enabled;
+        self
+    }
+
     /// Creates a config optimized for speed (minimal validation).
     pub fn fast() -> Self {
         Self {
             strategy: ContractionStrategy::Greedy,
+            cost_model: CostModel::default(),
             use_tensor_cores: true,
             autotune: false,
             validate_shapes: false,
+            allow_output_view: false,
+            plan_cache: false,
         }
     }
 
@@ -70,9 +120,12 @@ impl EinsumConfig {
     pub fn safe() -> Self {
         Self {
             strategy: ContractionStrategy::Optimal,
+            cost_model: CostModel::default(),
             use_tensor_cores: true,
             autotune: true,
             validate_shapes: true,
+            allow_output_view: false,
+            plan_cache: false,
         }
     }
 }
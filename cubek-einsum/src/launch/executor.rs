@@ -20,8 +20,8 @@ use cubek_reduce::ReduceStrategy;
 use crate::error::{EinsumError, EinsumResult};
 use crate::notation::{parse_einsum, EinsumNotation, validate_notation};
 use crate::notation::validation::validate_shapes;
-use crate::optimization::{create_plan, ExecutionStep, ReductionOp};
-use crate::pattern::FastPath;
+use crate::optimization::{create_plan_with_cost_model, CostModel, ExecutionPlan, ExecutionStep, ReductionOp};
+use crate::pattern::{FastPath, GemmLayout};
 use crate::kernels;
 use super::config::EinsumConfig;
 
@@ -68,13 +68,101 @@ pub fn einsum<R: Runtime, E: CubePrimitive + Numeric>(
         let _ = validate_shapes(&notation, &shapes)?;
     }
 
+    // Reuse a cached plan if enabled
+    #[cfg(feature = "std")]
+    if config.plan_cache {
+        let plan = super::cache::PlanCache::global().get_or_create_with_cost_model(
+            &notation,
+            &shapes,
+            E::as_type_native_unchecked(),
+            config.strategy.clone(),
+            &config.cost_model,
+        )?;
+        check_memory_limit(&plan, &shapes, &config.cost_model)?;
+        return execute_plan::<R, E>(client, &plan, inputs, output, &config);
+    }
+
     // Create execution plan
-    let plan = create_plan(&notation, &shapes, config.strategy);
+    let plan = create_plan_with_cost_model(&notation, &shapes, config.strategy.clone(), &config.cost_model)?;
+    check_memory_limit(&plan, &shapes, &config.cost_model)?;
 
     // Execute plan
     execute_plan::<R, E>(client, &plan, inputs, output, &config)
 }
 
+/// Executes an einsum operation, allocating the output tensor.
+///
+/// The output shape is inferred from the notation and input shapes, see
+/// [`einsum_output_shape`].
+///
+/// # Example
+///
+/// ```ignore
+/// let c = einsum_alloc::<R, f32>(&client, "ij,jk->ik", &[&a, &b], None)?;
+/// ```
+pub fn einsum_alloc<R: Runtime, E: CubePrimitive + Numeric>(
+    client: &ComputeClient<R>,
+    notation_str: &str,
+    inputs: &[&TensorHandle<R>],
+    config: Option<EinsumConfig>,
+) -> EinsumResult<TensorHandle<R>> {
+    let shapes: Vec<&[usize]> = inputs.iter().map(|t| t.shape.as_slice()).collect();
+    let output_shape = einsum_output_shape(notation_str, &shapes)?;
+
+    let mut output = TensorHandle::empty(client, output_shape, E::as_type_native_unchecked());
+    einsum::<R, E>(client, notation_str, inputs, &mut output, config)?;
+
+    Ok(output)
+}
+
+/// Computes the shape of the output tensor for the given input shapes.
+///
+/// Ellipsis dimensions are expanded. Scalar outputs have shape `[1]`, which
+/// is what the kernels write to.
+pub fn einsum_output_shape(notation_str: &str, shapes: &[&[usize]]) -> EinsumResult<Vec<usize>> {
+    let notation = parse_einsum(notation_str)?;
+    validate_notation(&notation)?;
+
+    output_shape(&notation, shapes)
+}
+
+/// Computes the output shape of a validated notation, see [`einsum_output_shape`].
+pub(super) fn output_shape(notation: &EinsumNotation, shapes: &[&[usize]]) -> EinsumResult<Vec<usize>> {
+    let ellipsis_dims = validate_shapes(notation, shapes)?.ellipsis_dims;
+    let mut output_shape = notation.compute_output_shape(shapes, ellipsis_dims)?;
+    if output_shape.is_empty() {
+        output_shape.push(1);
+    }
+
+    Ok(output_shape)
+}
+
+/// Fails if an intermediate of `plan` exceeds the memory limit of `cost_model`.
+///
+/// The path search avoids such intermediates, but cannot when every path
+/// needs one, and imported plans are not searched at all.
+pub(super) fn check_memory_limit(
+    plan: &ExecutionPlan,
+    shapes: &[&[usize]],
+    cost_model: &CostModel,
+) -> EinsumResult<()> {
+    let Some(limit) = cost_model.memory_limit else {
+        return Ok(());
+    };
+
+    for shape in plan.workspace_sizing(shapes).intermediate_shapes {
+        let elements = shape.iter().fold(1u64, |acc, &d| acc.saturating_mul(d as u64));
+        if elements > limit {
+            return Err(EinsumError::memory(alloc::format!(
+                "plan needs an intermediate of shape {:?} ({} elements), memory limit is {} elements",
+                shape, elements, limit
+            )));
+        }
+    }
+
+    Ok(())
+}
+
 /// Executes a pre-parsed einsum notation.
 ///
 /// Useful when the same notation will be executed multiple times.
@@ -97,14 +185,15 @@ pub fn einsum_with_notation<R: Runtime, E: CubePrimitive + Numeric>(
     }
 
     // Create execution plan
-    let plan = create_plan(notation, &shapes, config.strategy);
+    let plan = create_plan_with_cost_model(notation, &shapes, config.strategy.clone(), &config.cost_model)?;
+    check_memory_limit(&plan, &shapes, &config.cost_model)?;
 
     // Execute plan
     execute_plan::<R, E>(client, &plan, inputs, output, &config)
 }
 
 /// Executes an execution plan.
-fn execute_plan<R: Runtime, E: CubePrimitive + Numeric>(
+pub(super) fn execute_plan<R: Runtime, E: CubePrimitive + Numeric>(
     client: &ComputeClient<R>,
     plan: &crate::optimization::ExecutionPlan,
     inputs: &[&TensorHandle<R>],
@@ -131,7 +220,7 @@ fn execute_fast_path<R: Runtime, E: CubePrimitive + Numeric>(
     fast_path: &FastPath,
     inputs: &[&TensorHandle<R>],
     output: &mut TensorHandle<R>,
-    _config: &EinsumConfig,
+    config: &EinsumConfig,
 ) -> EinsumResult<()> {
     match fast_path {
         FastPath::Matmul { transpose_a, transpose_b } => {
@@ -144,7 +233,7 @@ fn execute_fast_path<R: Runtime, E: CubePrimitive + Numeric>(
             execute_reduce::<R, E>(client, inputs, output, axes)
         }
         FastPath::Transpose { permutation } => {
-            execute_transpose::<R, E>(inputs, output, permutation)
+            execute_transpose::<R, E>(client, inputs, output, permutation, config)
         }
         FastPath::Hadamard => {
             execute_hadamard::<R, E>(client, inputs, output)
@@ -226,7 +315,7 @@ fn execute_matmul<R: Runtime, E: CubePrimitive + Numeric>(
 ///
 /// Note: cubek_reduce expects output to keep reduced dimension with size 1,
 /// but einsum semantics remove the dimension entirely. We handle this by
-/// creating an intermediate tensor if needed.
+/// reducing into a keep-dim view of the output buffer.
 fn execute_reduce<R: Runtime, E: CubePrimitive + Numeric>(
     client: &ComputeClient<R>,
     inputs: &[&TensorHandle<R>],
@@ -258,51 +347,24 @@ fn execute_reduce<R: Runtime, E: CubePrimitive + Numeric>(
 
     // cubek_reduce expects output shape to keep reduced dim with size 1
     // e.g., input [1024, 1024] reduced on axis 1 -> output [1024, 1]
-    // But einsum expects [1024] (dim removed). Create intermediate if needed.
+    // But einsum expects [1024] (dim removed), so reduce into a keep-dim
+    // view of the caller's output.
     let mut keep_dim_shape = input.shape.clone();
     keep_dim_shape[axis] = 1;
+    let output_view = keep_dim_view(output, &keep_dim_shape)?;
 
-    let output_matches_keepdim = output.shape == keep_dim_shape;
-
-    if output_matches_keepdim {
-        // Output already has keep-dim shape, reduce directly
-        cubek_reduce::reduce(
-            client,
-            input.as_ref(),
-            output.as_ref(),
-            axis,
-            ReduceStrategy {
-                line_size: LineSizeStrategy { parallel_output_vectorization: false },
-                routine: RoutineStrategy::Unit(BlueprintStrategy::Inferred(UnitStrategy)),
-            },
-            operation,
-            dtypes,
-        ).map_err(|e| EinsumError::launch(alloc::format!("reduce failed: {:?}", e)))
-    } else {
-        // Create intermediate with keep-dim shape, then copy/reshape to output
-        let intermediate = TensorHandle::zeros(client, keep_dim_shape.clone(), input.dtype);
-
-        cubek_reduce::reduce(
-            client,
-            input.as_ref(),
-            intermediate.as_ref(),
-            axis,
-            ReduceStrategy {
-                line_size: LineSizeStrategy { parallel_output_vectorization: false },
-                routine: RoutineStrategy::Unit(BlueprintStrategy::Inferred(UnitStrategy)),
-            },
-            operation,
-            dtypes,
-        ).map_err(|e| EinsumError::launch(alloc::format!("reduce failed: {:?}", e)))?;
-
-        // The intermediate and output have the same number of elements,
-        // just different shapes. We can treat them as the same memory.
-        // Update output to point to intermediate's data with squeezed shape.
-        output.handle = intermediate.handle;
-        output.strides = compute_strides(&output.shape);
-
-        Ok(())
-    }
+    cubek_reduce::reduce(
+        client,
+        input.as_ref(),
+        output_view.as_ref(),
+        axis,
+        ReduceStrategy {
+            line_size: LineSizeStrategy { parallel_output_vectorization: false },
+            routine: RoutineStrategy::Unit(BlueprintStrategy::Inferred(UnitStrategy)),
+        },
+        operation,
+        dtypes,
+    ).map_err(|e| EinsumError::launch(alloc::format!("reduce failed: {:?}", e)))
 }
 
 /// Executes multi-axis reduction by reducing one axis at a time.
@@ -334,42 +396,21 @@ fn execute_multi_axis_reduce<R: Runtime, E: CubePrimitive + Numeric>(
         keep_dim_shape[axis] = 1;
 
         if is_last {
-            // Final reduction - check if output shape matches keep-dim
-            if output.shape == keep_dim_shape {
-                // Direct reduction to output
-                cubek_reduce::reduce(
-                    client,
-                    current.as_ref(),
-                    output.as_ref(),
-                    axis,
-                    ReduceStrategy {
-                line_size: LineSizeStrategy { parallel_output_vectorization: false },
-                routine: RoutineStrategy::Unit(BlueprintStrategy::Inferred(UnitStrategy)),
-            },
-                    operation,
-                    dtypes,
-                ).map_err(|e| EinsumError::launch(alloc::format!("reduce failed: {:?}", e)))?;
-            } else {
-                // Need intermediate, then copy to squeezed output
-                let intermediate = TensorHandle::zeros(client, keep_dim_shape.clone(), dtype);
+            // Final reduction writes into a keep-dim view of the output
+            let output_view = keep_dim_view(output, &keep_dim_shape)?;
 
-                cubek_reduce::reduce(
-                    client,
-                    current.as_ref(),
-                    intermediate.as_ref(),
-                    axis,
-                    ReduceStrategy {
+            cubek_reduce::reduce(
+                client,
+                current.as_ref(),
+                output_view.as_ref(),
+                axis,
+                ReduceStrategy {
                 line_size: LineSizeStrategy { parallel_output_vectorization: false },
                 routine: RoutineStrategy::Unit(BlueprintStrategy::Inferred(UnitStrategy)),
             },
-                    operation,
-                    dtypes,
-                ).map_err(|e| EinsumError::launch(alloc::format!("reduce failed: {:?}", e)))?;
-
-                // Copy handle to output (same data, different shape interpretation)
-                output.handle = intermediate.handle;
-                output.strides = compute_strides(&output.shape);
-            }
+                operation,
+                dtypes,
+            ).map_err(|e| EinsumError::launch(alloc::format!("reduce failed: {:?}", e)))?;
         } else {
             // Intermediate reduction - use keep-dim shape
             let workspace = TensorHandle::zeros(client, keep_dim_shape.clone(), dtype);
@@ -404,29 +445,90 @@ fn compute_strides(shape: &[usize]) -> Vec<usize> {
     strides
 }
 
-/// Executes transpose operation (zero-copy via stride manipulation).
+/// Returns a view of `output` with the reduced dimensions kept as size 1.
+///
+/// The view shares the output's buffer, so a reduction launched on it writes
+/// directly into the caller's memory. Size-1 dimensions carry no offset, so
+/// only the remaining dimensions need to line up with the output's.
+fn keep_dim_view<R: Runtime>(
+    output: &TensorHandle<R>,
+    keep_dim_shape: &[usize],
+) -> EinsumResult<TensorHandle<R>> {
+    if output.shape == keep_dim_shape {
+        return Ok(output.clone());
+    }
+
+    let mut output_dims = output.shape.iter()
+        .zip(output.strides.iter())
+        .filter(|&(&size, _)| size != 1);
+
+    let mut strides = Vec::with_capacity(keep_dim_shape.len());
+    for &size in keep_dim_shape {
+        if size == 1 {
+            strides.push(1);
+            continue;
+        }
+        match output_dims.next() {
+            Some((&out_size, &stride)) if out_size == size => strides.push(stride),
+            _ => {
+                return Err(EinsumError::shape(alloc::format!(
+                    "reduce output shape {:?} does not match {:?}",
+                    output.shape, keep_dim_shape
+                )));
+            }
+        }
+    }
+    if output_dims.next().is_some() {
+        return Err(EinsumError::shape(alloc::format!(
+            "reduce output shape {:?} does not match {:?}",
+            output.shape, keep_dim_shape
+        )));
+    }
+
+    let mut view = output.clone();
+    view.shape = keep_dim_shape.to_vec();
+    view.strides = strides;
+    Ok(view)
+}
+
+/// Executes transpose operation.
+///
+/// The permuted input is written into the output buffer, unless the config
+/// allows output views, in which case the output becomes a zero-copy view.
 fn execute_transpose<R: Runtime, E: CubePrimitive + Numeric>(
+    client: &ComputeClient<R>,
     inputs: &[&TensorHandle<R>],
     output: &mut TensorHandle<R>,
     permutation: &[usize],
+    config: &EinsumConfig,
 ) -> EinsumResult<()> {
     if inputs.is_empty() {
         return Err(EinsumError::unsupported("transpose requires 1 input"));
     }
 
-    let input = inputs[0];
-
-    // Apply permutation to shape and strides
-    let new_shape: Vec<usize> = permutation.iter().map(|&i| input.shape[i]).collect();
-    let new_strides: Vec<usize> = permutation.iter().map(|&i| input.strides[i]).collect();
+    write_permuted::<R, E>(client, inputs[0], output, permutation, config)
+}
 
-    // Update output metadata
-    // Note: This assumes output shares the same underlying buffer as input
-    // For a true einsum operation, the caller should set up output appropriately
-    output.shape = new_shape;
-    output.strides = new_strides;
+/// Writes `input` permuted by `permutation` to `output`.
+///
+/// With `allow_output_view`, the output handle is replaced by a permuted view
+/// that aliases the input.
+fn write_permuted<R: Runtime, E: CubePrimitive + Numeric>(
+    client: &ComputeClient<R>,
+    input: &TensorHandle<R>,
+    output: &mut TensorHandle<R>,
+    permutation: &[usize],
+    config: &EinsumConfig,
+) -> EinsumResult<()> {
+    if config.allow_output_view {
+        let mut view = input.clone();
+        view.shape = permutation.iter().map(|&i| input.shape[i]).collect();
+        view.strides = permutation.iter().map(|&i| input.strides[i]).collect();
+        *output = view;
+        return Ok(());
+    }
 
-    Ok(())
+    kernels::launch_permute::<R, E>(client, input, output, permutation)
 }
 
 /// Executes Hadamard (element-wise) product.
@@ -443,8 +545,9 @@ fn execute_hadamard<R: Runtime, E: CubePrimitive + Numeric>(
 
 /// Executes broadcast multiply when no indices are contracted.
 ///
-/// Handles patterns like `ij,j->ij` where one tensor broadcasts over the other.
-/// This is NOT a matmul - it's element-wise multiplication with broadcasting.
+/// Handles patterns like `ij,j->ij` where one tensor broadcasts over the other,
+/// and `ij,jk->ijk` where both do. This is NOT a matmul - it's element-wise
+/// multiplication with broadcasting, written directly in `result` order.
 fn execute_broadcast_multiply<R: Runtime, E: CubePrimitive + Numeric>(
     client: &ComputeClient<R>,
     lhs: &TensorHandle<R>,
@@ -452,91 +555,50 @@ fn execute_broadcast_multiply<R: Runtime, E: CubePrimitive + Numeric>(
     output: &mut TensorHandle<R>,
     lhs_indices: &[char],
     rhs_indices: &[char],
+    result: &[char],
 ) -> EinsumResult<()> {
-    // For broadcast multiply, we need to expand tensors to match the output shape
-    // and then do element-wise multiplication with proper stride handling.
-    //
-    // Example: ij,j->ij with shapes [512, 1024], [1024] -> [512, 1024]
-    // The vector needs to be broadcast across the first dimension (stride=0 for that dim).
-
-    let lhs_rank = lhs_indices.len();
-    let rhs_rank = rhs_indices.len();
-
-    // If shapes already match (same indices in same order), use Hadamard directly
-    // (contiguous case, no broadcasting needed)
-    if lhs_indices == rhs_indices && lhs.shape == rhs.shape {
+    // If both inputs already have the output's indices in order, use Hadamard
+    // directly (contiguous case, no broadcasting needed)
+    if lhs_indices == result && rhs_indices == result && lhs.shape == rhs.shape {
         return kernels::launch_hadamard::<R, E>(client, lhs, rhs, output);
     }
 
-    // For broadcast cases, we expand both tensors to match output shape/indices.
-    // The output indices should be the union, preserving order from the larger tensor.
-
-    // Determine output indices (union of both, preserving order)
-    let output_indices: Vec<char> = if lhs_rank >= rhs_rank {
-        lhs_indices.to_vec()
-    } else {
-        rhs_indices.to_vec()
-    };
-    let output_rank = output_indices.len();
-
-    // Simple case: one tensor's indices are a subset of the other's
-    // Example: ij,j->ij means rhs broadcasts over i
-    if rhs_rank < lhs_rank && rhs_indices.iter().all(|c| lhs_indices.contains(c)) {
-        // rhs broadcasts to match lhs (output)
-        let mut rhs_broadcast = rhs.clone();
-
-        // Expand rhs to match output shape with stride 0 for broadcast dims
-        let mut new_shape = Vec::with_capacity(output_rank);
-        let mut new_strides = Vec::with_capacity(output_rank);
-
-        for (i, &idx) in lhs_indices.iter().enumerate() {
-            if let Some(pos) = rhs_indices.iter().position(|&c| c == idx) {
-                // This dimension exists in rhs
-                new_shape.push(rhs.shape[pos]);
-                new_strides.push(rhs.strides[pos]);
-            } else {
-                // Broadcast dimension - size from lhs/output, stride 0
-                new_shape.push(lhs.shape[i]);
-                new_strides.push(0);
-            }
-        }
-
-        rhs_broadcast.shape = new_shape;
-        rhs_broadcast.strides = new_strides;
-
-        // Use the broadcast-aware kernel (handles strided tensors)
-        return kernels::launch_broadcast_multiply::<R, E>(client, lhs, &rhs_broadcast, output);
+    if lhs_indices.iter().chain(rhs_indices).any(|c| !result.contains(c)) {
+        return Err(EinsumError::launch(alloc::format!(
+            "broadcast multiply of {:?} and {:?} cannot produce {:?}",
+            lhs_indices, rhs_indices, result
+        )));
     }
 
-    // Symmetric case: lhs broadcasts to match rhs (output)
-    if lhs_rank < rhs_rank && lhs_indices.iter().all(|c| rhs_indices.contains(c)) {
-        let mut lhs_broadcast = lhs.clone();
-
-        let mut new_shape = Vec::with_capacity(output_rank);
-        let mut new_strides = Vec::with_capacity(output_rank);
-
-        for (i, &idx) in rhs_indices.iter().enumerate() {
-            if let Some(pos) = lhs_indices.iter().position(|&c| c == idx) {
-                new_shape.push(lhs.shape[pos]);
-                new_strides.push(lhs.strides[pos]);
-            } else {
-                new_shape.push(rhs.shape[i]);
-                new_strides.push(0);
-            }
+    // Expand both tensors to the result indices, with stride 0 along the
+    // dimensions they do not have. Repeated indices read the diagonal.
+    // Example: ij,j->ij with shapes [512, 1024], [1024] -> [512, 1024]
+    // The vector is broadcast across the first dimension.
+    let size_of = |c: &char| {
+        lhs_indices
+            .iter()
+            .position(|i| i == c)
+            .map(|pos| lhs.shape[pos])
+            .or_else(|| rhs_indices.iter().position(|i| i == c).map(|pos| rhs.shape[pos]))
+            .unwrap_or(1)
+    };
+    let broadcast = |tensor: &TensorHandle<R>, indices: &[char]| {
+        let mut view = tensor.clone();
+        view.shape = result.iter().map(size_of).collect();
+        view.strides = result.iter().map(|c| index_stride(tensor, indices, *c)).collect();
+        if result.is_empty() {
+            // Scalar operands are stored with shape [1]
+            view.shape.push(1);
+            view.strides.push(0);
         }
+        view
+    };
 
-        lhs_broadcast.shape = new_shape;
-        lhs_broadcast.strides = new_strides;
-
-        // Use the broadcast-aware kernel (handles strided tensors)
-        return kernels::launch_broadcast_multiply::<R, E>(client, &lhs_broadcast, rhs, output);
-    }
+    let lhs_broadcast = broadcast(lhs, lhs_indices);
+    let rhs_broadcast = broadcast(rhs, rhs_indices);
 
-    // Complex broadcast case - not yet supported
-    Err(EinsumError::unsupported(alloc::format!(
-        "complex broadcast multiply not yet supported: {:?} x {:?}",
-        lhs_indices, rhs_indices
-    )))
+    // Use the broadcast-aware kernel (handles strided tensors)
+    kernels::launch_broadcast_multiply::<R, E>(client, &lhs_broadcast, &rhs_broadcast, output)
 }
 
 /// Executes outer product.
@@ -593,7 +655,7 @@ struct TrackedTensor<R: Runtime> {
     indices: Vec<char>,
 }
 
-/// Executes a general contraction sequence.
+/// Executes a general contraction sequence, once per slice for sliced plans.
 fn execute_contractions<R: Runtime, E: CubePrimitive + Numeric>(
     client: &ComputeClient<R>,
     plan: &crate::optimization::ExecutionPlan,
@@ -601,14 +663,118 @@ fn execute_contractions<R: Runtime, E: CubePrimitive + Numeric>(
     output: &mut TensorHandle<R>,
     config: &EinsumConfig,
 ) -> EinsumResult<()> {
-    let steps = plan.steps();
-    if steps.is_empty() {
-        return Ok(());
+    if plan.sliced_indices().is_empty() {
+        return execute_contraction_steps::<R, E>(client, plan, inputs, output, config);
     }
 
+    // A slice count of 0 would leave the output unwritten
+    let shapes: Vec<&[usize]> = inputs.iter().map(|t| t.shape.as_slice()).collect();
+    plan.validate_slicing(&shapes)?;
+    execute_sliced_contractions::<R, E>(client, plan, inputs, output, config)
+}
+
+/// Runs the steps of a sliced plan once per combination of values of the
+/// sliced indices and sums the results into the output.
+///
+/// Each slice contracts copies of the inputs restricted to its values, with
+/// the sliced dimensions kept at size 1, so no intermediate is larger than
+/// the plan's workspace sizing.
+fn execute_sliced_contractions<R: Runtime, E: CubePrimitive + Numeric>(
+    client: &ComputeClient<R>,
+    plan: &crate::optimization::ExecutionPlan,
+    inputs: &[&TensorHandle<R>],
+    output: &mut TensorHandle<R>,
+    config: &EinsumConfig,
+) -> EinsumResult<()> {
+    let sliced = plan.sliced_indices();
+    let plan_indices = plan.input_indices();
+    let dtype = inputs.get(0).map(|t| t.dtype).unwrap_or_else(|| E::as_type_native_unchecked());
+
+    // Every slice has to write real memory to be summed
+    let config = config.clone().with_output_view(false);
+
+    let mut values = vec![0usize; sliced.len()];
+    let mut partial: Option<TensorHandle<R>> = None;
+
+    for slice in 0..plan.num_slices() {
+        let mut remainder = slice;
+        for (value, &(_, size)) in values.iter_mut().zip(sliced).rev() {
+            *value = remainder % size;
+            remainder /= size;
+        }
+
+        let mut slice_inputs: Vec<TensorHandle<R>> = Vec::with_capacity(inputs.len());
+        for (idx, input) in inputs.iter().enumerate() {
+            let indices = plan_indices.get(idx).map(Vec::as_slice).unwrap_or(&[]);
+            let mut shape = input.shape.clone();
+            let mut offset = 0usize;
+            for (dim, c) in indices.iter().enumerate() {
+                // Broadcast dimensions of size 1 are the same in every slice
+                if let Some(pos) = sliced.iter().position(|&(s, _)| s == *c)
+                    && dim < shape.len()
+                    && shape[dim] > 1
+                {
+                    offset += values[pos] * input.strides[dim];
+                    shape[dim] = 1;
+                }
+            }
+
+            if shape == input.shape {
+                slice_inputs.push((*input).clone());
+            } else {
+                let mut copy = TensorHandle::zeros(client, shape, dtype);
+                kernels::launch_copy_slice::<R, E>(client, input, &mut copy, offset)?;
+                slice_inputs.push(copy);
+            }
+        }
+        let slice_inputs: Vec<&TensorHandle<R>> = slice_inputs.iter().collect();
+
+        if slice == 0 {
+            execute_contraction_steps::<R, E>(client, plan, &slice_inputs, output, &config)?;
+        } else {
+            let partial = partial.get_or_insert_with(|| TensorHandle::zeros(client, output.shape.clone(), dtype));
+            execute_contraction_steps::<R, E>(client, plan, &slice_inputs, partial, &config)?;
+            kernels::launch_accumulate::<R, E>(client, partial, output)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Executes the steps of a plan on one set of inputs.
+fn execute_contraction_steps<R: Runtime, E: CubePrimitive + Numeric>(
+    client: &ComputeClient<R>,
+    plan: &crate::optimization::ExecutionPlan,
+    inputs: &[&TensorHandle<R>],
+    output: &mut TensorHandle<R>,
+    config: &EinsumConfig,
+) -> EinsumResult<()> {
+    let steps = plan.steps();
+
     // Get actual indices from the plan (parsed from notation)
     let plan_indices = plan.input_indices();
 
+    if steps.is_empty() {
+        // The planner cannot express diagonals of a single operand as steps
+        let has_repeats = plan_indices.first().is_some_and(|indices| {
+            indices.iter().enumerate().any(|(pos, c)| indices[..pos].contains(c))
+        });
+        if has_repeats {
+            return Err(EinsumError::unsupported(
+                "repeated indices in a single operand without a fast path",
+            ));
+        }
+
+        // Identity: the output still has to receive the input
+        return match inputs.first() {
+            Some(input) => {
+                let identity: Vec<usize> = (0..input.shape.len()).collect();
+                write_permuted::<R, E>(client, input, output, &identity, config)
+            }
+            None => Ok(()),
+        };
+    }
+
     // Initialize tracked tensors with input tensors and their actual indices from notation
     let mut tracked: Vec<TrackedTensor<R>> = inputs.iter().enumerate().map(|(idx, t)| {
         // Use indices from plan if available, otherwise infer
@@ -644,7 +810,7 @@ fn execute_contractions<R: Runtime, E: CubePrimitive + Numeric>(
                 let rhs_indices = tracked[j].indices.clone();
 
                 // Compute output shape using actual indices
-                let contraction_output_shape = compute_contraction_shape_with_indices(
+                let mut contraction_output_shape = compute_contraction_shape_with_indices(
                     &tracked[i].tensor,
                     &tracked[j].tensor,
                     &lhs_indices,
@@ -662,8 +828,12 @@ fn execute_contractions<R: Runtime, E: CubePrimitive + Numeric>(
                         &lhs_indices,
                         &rhs_indices,
                         contracted,
+                        result,
                     )?;
                 } else {
+                    if contraction_output_shape.is_empty() {
+                        contraction_output_shape.push(1);
+                    }
                     let mut workspace = TensorHandle::zeros(client, contraction_output_shape, dtype);
 
                     execute_general_contraction_with_indices::<R, E>(
@@ -674,6 +844,7 @@ fn execute_contractions<R: Runtime, E: CubePrimitive + Numeric>(
                         &lhs_indices,
                         &rhs_indices,
                         contracted,
+                        result,
                     )?;
 
                     // Update tracked list: remove i and j (higher index first), add result
@@ -699,6 +870,11 @@ fn execute_contractions<R: Runtime, E: CubePrimitive + Numeric>(
                 if *input >= tracked.len() {
                     return Err(EinsumError::launch("permutation references invalid tensor"));
                 }
+                if is_last {
+                    write_permuted::<R, E>(client, &tracked[*input].tensor, output, perm, config)?;
+                    continue;
+                }
+
                 let tracked_tensor = &mut tracked[*input];
                 let new_shape: Vec<usize> = perm.iter().map(|&p| tracked_tensor.tensor.shape[p]).collect();
                 let new_strides: Vec<usize> = perm.iter().map(|&p| tracked_tensor.tensor.strides[p]).collect();
@@ -790,16 +966,21 @@ fn compute_contraction_shape_with_indices<R: Runtime>(
 /// Executes a general two-tensor contraction with explicit index tracking.
 ///
 /// Uses an optimized batched GEMM approach:
-/// 1. Identify batch dimensions (indices in both LHS and RHS, not contracted)
-/// 2. Permute tensors to [batch..., non-contracted..., contracted...] layout
+/// 1. Split the indices into batch, M, K and N groups (see [`GemmLayout`])
+/// 2. Permute tensors to [batch..., M..., K...] and [batch..., K..., N...] layout
 /// 3. Reshape preserving batch structure: [batch..., M, K] and [batch..., K, N]
 /// 4. Perform batched GEMM (cubek-matmul handles batches efficiently)
-/// 5. Output already has correct shape from permutation
+/// 5. Permute the [batch..., M..., N...] product into `result` order, unless
+///    it already is in that order
+///
+/// Contracted indices held by one operand only are broadcast into the other
+/// operand's K dimensions, so they are summed by the GEMM as well.
 ///
 /// This is superior to flattening batch dims into M, as it:
 /// - Preserves cache locality (batch as outer loop)
 /// - Enables efficient batched kernel dispatch
 /// - Handles strided tensors correctly
+#[allow(clippy::too_many_arguments)]
 fn execute_general_contraction_with_indices<R: Runtime, E: CubePrimitive + Numeric>(
     client: &ComputeClient<R>,
     lhs: &TensorHandle<R>,
@@ -808,13 +989,14 @@ fn execute_general_contraction_with_indices<R: Runtime, E: CubePrimitive + Numer
     lhs_indices: &[char],
     rhs_indices: &[char],
     contracted: &[char],
+    result: &[char],
 ) -> EinsumResult<()> {
-    use hashbrown::{HashMap, HashSet};
+    use hashbrown::HashMap;
 
     // If no indices are contracted, this is a broadcast multiply, not a matmul
     // Fall back to element-wise kernel with broadcasting
     if contracted.is_empty() {
-        return execute_broadcast_multiply::<R, E>(client, lhs, rhs, output, lhs_indices, rhs_indices);
+        return execute_broadcast_multiply::<R, E>(client, lhs, rhs, output, lhs_indices, rhs_indices, result);
     }
 
     // Build dimension map from index char to size
@@ -826,232 +1008,141 @@ fn execute_general_contraction_with_indices<R: Runtime, E: CubePrimitive + Numer
         dim_map.insert(idx, size);
     }
 
-    let contracted_set: HashSet<char> = contracted.iter().copied().collect();
-
-    // Identify batch dimensions: indices that appear in both LHS and RHS but are not contracted
-    let lhs_set: HashSet<char> = lhs_indices.iter().copied().collect();
-    let rhs_set: HashSet<char> = rhs_indices.iter().copied().collect();
-    let common_indices: HashSet<char> = lhs_set.intersection(&rhs_set).copied().collect();
-    let batch_indices: Vec<char> = common_indices
-        .difference(&contracted_set)
-        .copied()
-        .collect();
-    let batch_set: HashSet<char> = batch_indices.iter().copied().collect();
-
-    // For LHS: separate batch, non-contracted (M), and contracted (K) dimensions
-    // We want order: [batch..., M..., K...]
-    let lhs_batch: Vec<usize> = lhs_indices.iter()
-        .enumerate()
-        .filter(|(_, c)| batch_set.contains(*c))
-        .map(|(i, _)| i)
-        .collect();
-    let lhs_m: Vec<usize> = lhs_indices.iter()
-        .enumerate()
-        .filter(|(_, c)| !contracted_set.contains(*c) && !batch_set.contains(*c))
-        .map(|(i, _)| i)
-        .collect();
-    let lhs_contracted: Vec<usize> = lhs_indices.iter()
-        .enumerate()
-        .filter(|(_, c)| contracted_set.contains(*c))
-        .map(|(i, _)| i)
-        .collect();
-
-    // For RHS: separate batch, contracted (K), and non-contracted (N) dimensions
-    // We want order: [batch..., K..., N...]
-    let rhs_batch: Vec<usize> = rhs_indices.iter()
-        .enumerate()
-        .filter(|(_, c)| batch_set.contains(*c))
-        .map(|(i, _)| i)
-        .collect();
-
-    // CRITICAL: RHS contracted dimensions must be in the same ORDER as LHS contracted
-    // dimensions for the merged K dimension to match. We build a map from contracted
-    // index char to position in RHS, then order by LHS contracted order.
-    let rhs_contracted_map: HashMap<char, usize> = rhs_indices.iter()
-        .enumerate()
-        .filter(|(_, c)| contracted_set.contains(*c))
-        .map(|(i, &c)| (c, i))
-        .collect();
-
-    // Get contracted chars in the order they appear in LHS
-    let lhs_contracted_chars: Vec<char> = lhs_indices.iter()
-        .filter(|c| contracted_set.contains(*c))
-        .copied()
-        .collect();
-
-    // Order RHS contracted positions to match LHS contracted order
-    let rhs_contracted: Vec<usize> = lhs_contracted_chars.iter()
-        .filter_map(|c| rhs_contracted_map.get(c).copied())
-        .collect();
-
-    let rhs_n: Vec<usize> = rhs_indices.iter()
-        .enumerate()
-        .filter(|(_, c)| !contracted_set.contains(*c) && !batch_set.contains(*c))
-        .map(|(i, _)| i)
-        .collect();
-
-    // Compute batch dimensions, M, K, N
-    let batch_shape: Vec<usize> = batch_indices.iter()
-        .map(|c| dim_map[c])
-        .collect();
-
-    let m: usize = lhs_m.iter()
-        .map(|&i| lhs.shape[i])
-        .product::<usize>()
-        .max(1);
-    let k: usize = lhs_contracted.iter()
-        .map(|&i| lhs.shape[i])
-        .product::<usize>()
-        .max(1);
-    let n: usize = rhs_n.iter()
-        .map(|&i| rhs.shape[i])
-        .product::<usize>()
-        .max(1);
-
-    // Build permutation for LHS: [batch..., M..., K...]
-    let lhs_perm: Vec<usize> = lhs_batch.iter()
-        .chain(lhs_m.iter())
-        .chain(lhs_contracted.iter())
-        .copied()
-        .collect();
-
-    // Build permutation for RHS: [batch..., K..., N...]
-    let rhs_perm: Vec<usize> = rhs_batch.iter()
-        .chain(rhs_contracted.iter())
-        .chain(rhs_n.iter())
-        .copied()
-        .collect();
-
-    // Check if LHS needs transposition (permutation is not identity)
-    let lhs_needs_transpose = !is_identity_permutation(&lhs_perm);
-    let rhs_needs_transpose = !is_identity_permutation(&rhs_perm);
-
-    // Determine target shapes for matmul
-    let lhs_target_shape = if !batch_shape.is_empty() {
-        [batch_shape.clone(), vec![m, k]].concat()
-    } else {
-        vec![m, k]
-    };
-    let rhs_target_shape = if !batch_shape.is_empty() {
-        [batch_shape.clone(), vec![k, n]].concat()
-    } else {
-        vec![k, n]
-    };
+    let layout = GemmLayout::new(lhs_indices, rhs_indices, contracted);
+    let result_perm = layout.permutation_to(result).ok_or_else(|| {
+        EinsumError::launch(alloc::format!(
+            "contraction of {:?} and {:?} over {:?} cannot produce {:?}",
+            lhs_indices, rhs_indices, contracted, result
+        ))
+    })?;
+
+    // Contracted indices only held by the right operand go after the left ones
+    let mut k_indices = layout.k.clone();
+    for &c in rhs_indices {
+        if contracted.contains(&c) && !k_indices.contains(&c) {
+            k_indices.push(c);
+        }
+    }
 
-    // Check if reshape is needed (merging multiple dimensions)
-    let lhs_needs_reshape = lhs_perm.len() != lhs_target_shape.len();
-    let rhs_needs_reshape = rhs_perm.len() != rhs_target_shape.len();
-
-    // For permute+reshape, we need to materialize the permuted tensor
-    // because reshape requires contiguous data in the permuted order
-    let lhs_reshaped = if lhs_needs_transpose && lhs_needs_reshape {
-        // Need to materialize: copy data in permuted order, then reshape
-        let permuted_shape: Vec<usize> = lhs_perm.iter().map(|&i| lhs.shape[i]).collect();
-        let permuted_strides: Vec<usize> = lhs_perm.iter().map(|&i| lhs.strides[i]).collect();
-
-        // Create permuted view
-        let mut permuted = lhs.clone();
-        permuted.shape = permuted_shape;
-        permuted.strides = permuted_strides;
-
-        // Materialize into contiguous tensor with target shape
-        let mut materialized = TensorHandle::empty(client, lhs_target_shape.clone(), lhs.dtype);
-        kernels::copy_reshape::<R, E>(client, &permuted, &mut materialized)?;
-        materialized
-    } else if lhs_needs_transpose {
-        // Just permute (no reshape) - can do zero-copy
-        let new_shape: Vec<usize> = lhs_perm.iter().map(|&i| lhs.shape[i]).collect();
-        let new_strides: Vec<usize> = lhs_perm.iter().map(|&i| lhs.strides[i]).collect();
-        let mut t = lhs.clone();
-        t.shape = new_shape;
-        t.strides = new_strides;
-        t
-    } else if lhs_needs_reshape {
-        // Just reshape (no permute) - can change shape directly
-        let mut t = lhs.clone();
-        t.shape = lhs_target_shape.clone();
-        t.strides = compute_strides(&t.shape);
-        t
-    } else {
-        lhs.clone()
-    };
+    let size_of = |indices: &[char]| -> usize { indices.iter().map(|c| dim_map[c]).product::<usize>() };
+    let batch_shape: Vec<usize> = layout.batch.iter().map(|c| dim_map[c]).collect();
+    let m = size_of(&layout.m);
+    let k = size_of(&k_indices);
+    let n = size_of(&layout.n);
 
-    let rhs_reshaped = if rhs_needs_transpose && rhs_needs_reshape {
-        // Need to materialize: copy data in permuted order, then reshape
-        let permuted_shape: Vec<usize> = rhs_perm.iter().map(|&i| rhs.shape[i]).collect();
-        let permuted_strides: Vec<usize> = rhs_perm.iter().map(|&i| rhs.strides[i]).collect();
-
-        // Create permuted view
-        let mut permuted = rhs.clone();
-        permuted.shape = permuted_shape;
-        permuted.strides = permuted_strides;
-
-        // Materialize into contiguous tensor with target shape
-        let mut materialized = TensorHandle::empty(client, rhs_target_shape.clone(), rhs.dtype);
-        kernels::copy_reshape::<R, E>(client, &permuted, &mut materialized)?;
-        materialized
-    } else if rhs_needs_transpose {
-        // Just permute (no reshape) - can do zero-copy
-        let new_shape: Vec<usize> = rhs_perm.iter().map(|&i| rhs.shape[i]).collect();
-        let new_strides: Vec<usize> = rhs_perm.iter().map(|&i| rhs.strides[i]).collect();
-        let mut t = rhs.clone();
-        t.shape = new_shape;
-        t.strides = new_strides;
-        t
-    } else if rhs_needs_reshape {
-        // Just reshape (no permute) - can change shape directly
-        let mut t = rhs.clone();
-        t.shape = rhs_target_shape.clone();
-        t.strides = compute_strides(&t.shape);
-        t
-    } else {
-        rhs.clone()
-    };
+    let lhs_order: Vec<char> = layout.batch.iter().chain(&layout.m).chain(&k_indices).copied().collect();
+    let rhs_order: Vec<char> = layout.batch.iter().chain(&k_indices).chain(&layout.n).copied().collect();
 
-    // Ensure output has at least 2 dimensions for matmul
-    // If output is 1D (happens with some contraction patterns), reshape to 2D
-    let mut output_for_matmul = output.clone();
-    let original_output_shape = output.shape.clone();
-    let needs_reshape = output.shape.len() < 2;
-
-    if needs_reshape {
-        // Reshape output to 2D by adding dimensions of size 1
-        if !batch_shape.is_empty() {
-            // Batched case: [batch..., M, N] where M or N might be 1
-            let _total_elements: usize = output.shape.iter().product();
-            let _batch_size: usize = batch_shape.iter().product();
-            output_for_matmul.shape = [batch_shape.clone(), vec![m.max(1), n.max(1)]].concat();
-        } else {
-            // Non-batched case: reshape to [m, n]
-            output_for_matmul.shape = vec![m, n];
-        }
-        output_for_matmul.strides = compute_strides(&output_for_matmul.shape);
-    }
+    let lhs_reshaped = gemm_operand::<R, E>(
+        client,
+        lhs,
+        lhs_indices,
+        &lhs_order,
+        &dim_map,
+        [batch_shape.clone(), vec![m, k]].concat(),
+    )?;
+    let rhs_reshaped = gemm_operand::<R, E>(
+        client,
+        rhs,
+        rhs_indices,
+        &rhs_order,
+        &dim_map,
+        [batch_shape.clone(), vec![k, n]].concat(),
+    )?;
 
     // Perform batched GEMM: [batch..., M, K] @ [batch..., K, N] -> [batch..., M, N]
     // The cubek-matmul library will automatically detect and handle batch dimensions
+    let gemm_shape = [batch_shape, vec![m, n]].concat();
+    let gemm_strides = compute_strides(&gemm_shape);
+    let writes_in_place = is_identity_permutation(&result_perm)
+        && output.strides == compute_strides(&output.shape);
+
+    let gemm_output = if writes_in_place {
+        // The product already has the output's layout, write it directly
+        let mut view = output.clone();
+        view.shape = gemm_shape;
+        view.strides = gemm_strides;
+        view
+    } else {
+        TensorHandle::empty(client, gemm_shape, output.dtype)
+    };
+
     let elem_type = MatmulElemType::new(E::as_type_native_unchecked(), false);
     let dtypes = MatmulElems::from_single_dtype(elem_type);
 
-    let lhs_handle = MatmulInputHandle::Normal(lhs_reshaped);
-    let rhs_handle = MatmulInputHandle::Normal(rhs_reshaped);
-
     launch(
         &MatmulStrategy::Auto,
         client,
-        lhs_handle,
-        rhs_handle,
-        output_for_matmul,
+        MatmulInputHandle::Normal(lhs_reshaped),
+        MatmulInputHandle::Normal(rhs_reshaped),
+        gemm_output.clone(),
         dtypes,
     ).map_err(|e| EinsumError::launch(alloc::format!("contraction failed: {:?}", e)))?;
 
-    // Restore original shape if we reshaped
-    if needs_reshape {
-        output.shape = original_output_shape;
-        output.strides = compute_strides(&output.shape);
+    if writes_in_place {
+        return Ok(());
     }
 
-    Ok(())
+    // Permute [batch..., M..., N...] into the result order
+    let mut product = gemm_output;
+    product.shape = layout.output().iter().map(|c| dim_map[c]).collect();
+    product.strides = compute_strides(&product.shape);
+    if product.shape.is_empty() {
+        product.shape.push(1);
+        product.strides.push(1);
+        return kernels::launch_permute::<R, E>(client, &product, output, &[0]);
+    }
+    kernels::launch_permute::<R, E>(client, &product, output, &result_perm)
+}
+
+/// Lays out one GEMM operand as `target_shape`.
+///
+/// `order` lists the operand's indices grouped as the GEMM expects them.
+/// Indices of `order` the operand does not have are broadcast with stride 0,
+/// repeated indices of the operand read its diagonal. The operand is only
+/// copied when its dimensions cannot be merged in place.
+fn gemm_operand<R: Runtime, E: CubePrimitive + Numeric>(
+    client: &ComputeClient<R>,
+    tensor: &TensorHandle<R>,
+    indices: &[char],
+    order: &[char],
+    dim_map: &hashbrown::HashMap<char, usize>,
+    target_shape: Vec<usize>,
+) -> EinsumResult<TensorHandle<R>> {
+    let mut view = tensor.clone();
+    view.shape = order.iter().map(|c| dim_map[c]).collect();
+    view.strides = order.iter().map(|c| index_stride(tensor, indices, *c)).collect();
+    let is_permutation = order.len() == indices.len() && order.iter().all(|c| indices.contains(c));
+
+    if is_permutation && view.shape == target_shape {
+        // Just permute (no reshape) - can do zero-copy
+        return Ok(view);
+    }
+    if is_permutation && view.strides == compute_strides(&view.shape) {
+        // Just reshape (no permute) - can change shape directly
+        view.strides = compute_strides(&target_shape);
+        view.shape = target_shape;
+        return Ok(view);
+    }
+
+    // Need to materialize: copy data in permuted order, then reshape
+    let mut materialized = TensorHandle::empty(client, target_shape, tensor.dtype);
+    kernels::copy_reshape::<R, E>(client, &view, &mut materialized)?;
+    Ok(materialized)
+}
+
+/// Stride of a view of `tensor` along index `c`.
+///
+/// The strides of every dimension labelled `c` are added, so a repeated
+/// index walks the diagonal, and an index the tensor does not have gets
+/// stride 0, i.e. is broadcast.
+fn index_stride<R: Runtime>(tensor: &TensorHandle<R>, indices: &[char], c: char) -> usize {
+    indices
+        .iter()
+        .zip(&tensor.strides)
+        .filter(|&(&i, _)| i == c)
+        .map(|(_, &stride)| stride)
+        .sum()
 }
 
 /// Checks if a permutation is the identity (no reordering needed).
@@ -1062,4 +1153,28 @@ fn is_identity_permutation(perm: &[usize]) -> bool {
 #[cfg(test)]
 mod tests {
     // Integration tests would go here, but require a runtime
+    use super::*;
+
+    #[test]
+    fn test_output_shape_matmul() {
+        let shape = einsum_output_shape("ij,jk->ik", &[&[2, 3], &[3, 4]]).unwrap();
+        assert_eq!(shape, vec![2, 4]);
+    }
+
+    #[test]
+    fn test_output_shape_ellipsis() {
+        let shape = einsum_output_shape("...ij,...jk->...ik", &[&[5, 6, 2, 3], &[5, 6, 3, 4]]).unwrap();
+        assert_eq!(shape, vec![5, 6, 2, 4]);
+    }
+
+    #[test]
+    fn test_output_shape_scalar() {
+        let shape = einsum_output_shape("i,i->", &[&[3], &[3]]).unwrap();
+        assert_eq!(shape, vec![1]);
+    }
+
+    #[test]
+    fn test_output_shape_mismatch() {
+        assert!(einsum_output_shape("ij,jk->ik", &[&[2, 3], &[4, 4]]).is_err());
+    }
 }
@@ -2,10 +2,16 @@
 //!
 //! Provides the high-level API for executing einsum operations on GPU.
 
+#[cfg(feature = "std")]
+mod cache;
 mod config;
 mod executor;
+mod prepared;
 mod workspace;
 
+#[cfg(feature = "std")]
+pub use cache::{PlanCache, DEFAULT_PLAN_CACHE_CAPACITY};
 pub use config::EinsumConfig;
-pub use executor::einsum;
+pub use executor::{einsum, einsum_alloc, einsum_output_shape};
+pub use prepared::PreparedEinsum;
 pub use workspace::Workspace;
@@ -0,0 +1,329 @@
+//! Prepared einsum expressions.
+//!
+//! Parsing, validation, pattern recognition and path search only depend on
+//! the notation and input shapes, so they can be done once and reused for
+//! every call with the same shapes.
+
+use alloc::vec::Vec;
+
+use cubecl::prelude::*;
+use cubecl::Runtime;
+use cubecl::client::ComputeClient;
+use cubecl::std::tensor::TensorHandle;
+
+use crate::error::{EinsumError, EinsumResult};
+use crate::notation::validation::expanded_dims;
+use crate::notation::{parse_einsum, EinsumNotation, validate_notation};
+use crate::optimization::{create_plan_with_cost_model, ExecutionPlan, ExecutionStep, WorkspaceSizing};
+use crate::pattern::recognize_pattern;
+use super::config::EinsumConfig;
+use super::executor::{check_memory_limit, execute_plan, output_shape};
+
+/// An einsum expression planned for fixed input shapes.
+///
+/// # Example
+///
+/// ```ignore
+/// let scores = PreparedEinsum::new("bhqd,bhkd->bhqk", &[&q_shape, &k_shape], None)?;
+///
+/// for _ in 0..steps {
+///     scores.execute::<R, f32>(&client, &[&q, &k], &mut out)?;
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PreparedEinsum {
+    notation: EinsumNotation,
+    input_shapes: Vec<Vec<usize>>,
+    output_shape: Vec<usize>,
+    plan: ExecutionPlan,
+    workspace: WorkspaceSizing,
+    config: EinsumConfig,
+}
+
+impl PreparedEinsum {
+    /// Parses, validates and plans `notation_str` for the given input shapes.
+    pub fn new(
+        notation_str: &str,
+        shapes: &[&[usize]],
+        config: Option<EinsumConfig>,
+    ) -> EinsumResult<Self> {
+        let config = config.unwrap_or_default();
+
+        let notation = parse_einsum(notation_str)?;
+        validate_notation(&notation)?;
+
+        let output_shape = output_shape(&notation, shapes)?;
+        let plan = create_plan_with_cost_model(&notation, shapes, config.strategy.clone(), &config.cost_model)?;
+        check_memory_limit(&plan, shapes, &config.cost_model)?;
+        let workspace = plan.workspace_sizing(shapes);
+
+        Ok(Self {
+            notation,
+            input_shapes: shapes.iter().map(|s| s.to_vec()).collect(),
+            output_shape,
+            plan,
+            workspace,
+            config,
+        })
+    }
+
+    /// Prepares `notation_str` with an existing plan instead of planning it.
+    ///
+    /// This executes plans that were deserialized or built from an imported
+    /// path with [`plan_from_path`](crate::optimization::plan_from_path). The
+    /// plan must have been created for the same notation and shapes: its
+    /// output shape and input indices, or its fast path, must match the
+    /// expression's, and its sliced indices must have the sizes of `shapes`.
+    pub fn with_plan(
+        notation_str: &str,
+        shapes: &[&[usize]],
+        plan: ExecutionPlan,
+        config: Option<EinsumConfig>,
+    ) -> EinsumResult<Self> {
+        let config = config.unwrap_or_default();
+
+        let notation = parse_einsum(notation_str)?;
+        validate_notation(&notation)?;
+
+        let output_shape = output_shape(&notation, shapes)?;
+        let (expanded, _) = expanded_dims(&notation, shapes)?;
+        let expected_shape = expanded.compute_output_shape(shapes, 0)?;
+        if plan.output_shape() != expected_shape.as_slice() {
+            return Err(EinsumError::shape(alloc::format!(
+                "plan produces shape {:?}, expression produces {:?}",
+                plan.output_shape(),
+                expected_shape
+            )));
+        }
+        if plan.uses_fast_path() {
+            // Fast-path plans keep no input indices, but the operation
+            // encodes the operand layout
+            let expected = recognize_pattern(&expanded);
+            let matches = match (plan.steps(), &expected) {
+                ([ExecutionStep::FastPath(fast_path)], Some(expected)) => fast_path == expected,
+                _ => false,
+            };
+            if !matches {
+                return Err(EinsumError::shape(alloc::format!(
+                    "plan runs {:?}, expression needs {:?}",
+                    plan.steps(),
+                    expected
+                )));
+            }
+        } else {
+            let input_indices: Vec<Vec<char>> = expanded
+                .inputs()
+                .iter()
+                .map(|s| s.named_indices().collect())
+                .collect();
+            if plan.input_indices() != input_indices.as_slice() {
+                return Err(EinsumError::shape(alloc::format!(
+                    "plan was created for inputs {:?}, expression has {:?}",
+                    plan.input_indices(),
+                    input_indices
+                )));
+            }
+        }
+        plan.validate_slicing(shapes)?;
+        check_memory_limit(&plan, shapes, &config.cost_model)?;
+        let workspace = plan.workspace_sizing(shapes);
+
+        Ok(Self {
+            notation,
+            input_shapes: shapes.iter().map(|s| s.to_vec()).collect(),
+            output_shape,
+            plan,
+            workspace,
+            config,
+        })
+    }
+
+    /// Executes the prepared plan.
+    ///
+    /// Inputs must have the shapes the expression was prepared for.
+    pub fn execute<R: Runtime, E: CubePrimitive + Numeric>(
+        &self,
+        client: &ComputeClient<R>,
+        inputs: &[&TensorHandle<R>],
+        output: &mut TensorHandle<R>,
+    ) -> EinsumResult<()> {
+        if inputs.len() != self.input_shapes.len() {
+            return Err(EinsumError::shape(alloc::format!(
+                "expected {} inputs, got {}",
+                self.input_shapes.len(),
+                inputs.len()
+            )));
+        }
+
+        for (idx, (input, shape)) in inputs.iter().zip(self.input_shapes.iter()).enumerate() {
+            if &input.shape != shape {
+                return Err(EinsumError::shape(alloc::format!(
+                    "input {} has shape {:?}, prepared for {:?}",
+                    idx, input.shape, shape
+                )));
+            }
+        }
+
+        execute_plan::<R, E>(client, &self.plan, inputs, output, &self.config)
+    }
+
+    /// Returns the parsed notation.
+    pub fn notation(&self) -> &EinsumNotation {
+        &self.notation
+    }
+
+    /// Returns the execution plan.
+    pub fn plan(&self) -> &ExecutionPlan {
+        &self.plan
+    }
+
+    /// Returns the input shapes the expression was prepared for.
+    pub fn input_shapes(&self) -> &[Vec<usize>] {
+        &self.input_shapes
+    }
+
+    /// Returns the output shape. Scalar outputs have shape `[1]`.
+    pub fn output_shape(&self) -> &[usize] {
+        &self.output_shape
+    }
+
+    /// Returns the shapes of the intermediate tensors, in allocation order.
+    pub fn intermediate_shapes(&self) -> &[Vec<usize>] {
+        &self.workspace.intermediate_shapes
+    }
+
+    /// Returns the peak number of intermediate elements alive at once.
+    pub fn workspace_elements(&self) -> usize {
+        self.workspace.peak_elements
+    }
+
+    /// Returns the config used for execution.
+    pub fn config(&self) -> &EinsumConfig {
+        &self.config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prepare_matmul() {
+        let prepared = PreparedEinsum::new("ij,jk->ik", &[&[2, 3], &[3, 4]], None).unwrap();
+
+        assert_eq!(prepared.output_shape(), &[2, 4]);
+        assert!(prepared.plan().uses_fast_path());
+        assert!(prepared.intermediate_shapes().is_empty());
+    }
+
+    #[test]
+    fn test_prepare_chain_intermediates() {
+        let shapes: &[&[usize]] = &[&[2, 30], &[30, 40], &[40, 5]];
+        let config = EinsumConfig::new().with_strategy(crate::optimization::ContractionStrategy::Optimal);
+        let prepared = PreparedEinsum::new("ij,jk,kl->il", shapes, Some(config)).unwrap();
+
+        assert_eq!(prepared.output_shape(), &[2, 5]);
+        assert_eq!(prepared.intermediate_shapes().len(), 1);
+        assert_eq!(prepared.workspace_elements(), prepared.intermediate_shapes()[0].iter().product::<usize>());
+    }
+
+    #[test]
+    fn test_prepare_ellipsis_scalar() {
+        let prepared = PreparedEinsum::new("...i,...i->...", &[&[3, 4], &[3, 4]], None).unwrap();
+        assert_eq!(prepared.output_shape(), &[3]);
+
+        let prepared = PreparedEinsum::new("i,i->", &[&[4], &[4]], None).unwrap();
+        assert_eq!(prepared.output_shape(), &[1]);
+    }
+
+    #[test]
+    fn test_prepare_with_imported_plan() {
+        use crate::optimization::{plan_from_path, ContractionPath, CostModel};
+
+        let notation = parse_einsum("ij,jk,kl->il").unwrap();
+        let shapes: &[&[usize]] = &[&[2, 30], &[30, 40], &[40, 5]];
+        let path = ContractionPath::from_pairs(&notation, shapes, &[(1, 2), (0, 1)], &CostModel::default()).unwrap();
+        let plan = plan_from_path(&notation, shapes, path).unwrap();
+
+        let prepared = PreparedEinsum::with_plan("ij,jk,kl->il", shapes, plan.clone(), None).unwrap();
+        assert_eq!(prepared.plan(), &plan);
+        assert_eq!(prepared.intermediate_shapes(), &[alloc::vec![30, 5]]);
+
+        assert!(PreparedEinsum::with_plan("ij,jk->ik", &[&[2, 30], &[30, 40]], plan.clone(), None).is_err());
+
+        // Same number of output elements, transposed
+        assert!(PreparedEinsum::with_plan("ij,jk,kl->li", shapes, plan.clone(), None).is_err());
+        // Same shapes, other operand layout
+        assert!(PreparedEinsum::with_plan("ij,jk,lk->il", &[&[2, 30], &[30, 40], &[5, 40]], plan, None).is_err());
+    }
+
+    #[test]
+    fn test_prepare_with_fast_path_plan() {
+        let square: &[&[usize]] = &[&[4, 4], &[4, 4]];
+        let plan = PreparedEinsum::new("ij,jk->ik", square, None).unwrap().plan().clone();
+        assert!(plan.uses_fast_path());
+
+        assert!(PreparedEinsum::with_plan("ij,jk->ik", square, plan.clone(), None).is_ok());
+        // Same shapes, first operand transposed
+        assert!(PreparedEinsum::with_plan("ji,jk->ik", square, plan, None).is_err());
+
+        let plan = PreparedEinsum::new("ij->ji", &[&[4, 4]], None).unwrap().plan().clone();
+        assert!(plan.uses_fast_path());
+        assert!(PreparedEinsum::with_plan("ij->ij", &[&[4, 4]], plan, None).is_err());
+    }
+
+    #[test]
+    fn test_prepare_with_imported_ellipsis_plan() {
+        use crate::optimization::{plan_from_path, ContractionPath, CostModel};
+
+        let notation = parse_einsum("...ij,...jk,...kl->...il").unwrap();
+        let shapes: &[&[usize]] = &[&[3, 2, 30], &[3, 30, 40], &[3, 40, 5]];
+        let expanded = notation.expand_ellipsis(1);
+        let path = ContractionPath::from_pairs(&expanded, shapes, &[(0, 1), (0, 1)], &CostModel::default()).unwrap();
+        let plan = plan_from_path(&notation, shapes, path).unwrap();
+
+        let prepared = PreparedEinsum::with_plan("...ij,...jk,...kl->...il", shapes, plan, None).unwrap();
+        assert_eq!(prepared.output_shape(), &[3, 2, 5]);
+    }
+
+    #[test]
+    fn test_prepare_memory_limit() {
+        let shapes: &[&[usize]] = &[&[2, 30], &[30, 40], &[40, 5]];
+
+        // (ij,jk) gives 80 elements, (jk,kl) gives 150
+        let config = EinsumConfig::new().with_memory_limit(100);
+        let prepared = PreparedEinsum::new("ij,jk,kl->il", shapes, Some(config.clone())).unwrap();
+        assert_eq!(prepared.intermediate_shapes(), &[alloc::vec![2, 40]]);
+
+        // Every first step needs at least 80 elements, so the plan is sliced
+        let config = config.with_memory_limit(50);
+        let prepared = PreparedEinsum::new("ij,jk,kl->il", shapes, Some(config.clone())).unwrap();
+        assert_eq!(prepared.plan().sliced_indices().len(), 1);
+        assert!(prepared.intermediate_shapes().iter().all(|s| s.iter().product::<usize>() <= 50));
+
+        // Output indices are never sliced, and every intermediate here holds only those
+        let err = PreparedEinsum::new("i,j,ij->ij", &[&[10], &[10], &[10, 10]], Some(config)).unwrap_err();
+        assert!(matches!(err, EinsumError::MemoryError { .. }));
+    }
+
+    #[test]
+    fn test_prepare_with_sliced_plan() {
+        let shapes: &[&[usize]] = &[&[2, 30], &[30, 40], &[40, 5]];
+        let config = EinsumConfig::new().with_memory_limit(50);
+        let plan = PreparedEinsum::new("ij,jk,kl->il", shapes, Some(config.clone())).unwrap().plan().clone();
+
+        assert!(PreparedEinsum::with_plan("ij,jk,kl->il", shapes, plan.clone(), Some(config.clone())).is_ok());
+
+        // Same notation, but the sliced index has another size
+        let (index, _) = plan.sliced_indices()[0];
+        let mut json = serde_json::to_value(&plan).unwrap();
+        json["sliced_indices"] = serde_json::json!([[index.to_string(), 0]]);
+        let plan: ExecutionPlan = serde_json::from_value(json).unwrap();
+        assert!(PreparedEinsum::with_plan("ij,jk,kl->il", shapes, plan, Some(config)).is_err());
+    }
+
+    #[test]
+    fn test_prepare_shape_mismatch() {
+        assert!(PreparedEinsum::new("ij,jk->ik", &[&[2, 3], &[4, 5]], None).is_err());
+    }
+}
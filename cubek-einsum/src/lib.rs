@@ -9,6 +9,7 @@
 //! - Pattern recognition for fast paths (matmul, reduce, transpose)
 //! - Integration with optimized cubek kernels
 //! - Autotuning support
+//! - Host reference evaluator for correctness checking without a GPU
 //!
 //! ## Example
 //!
@@ -32,9 +33,20 @@ pub mod optimization;
 pub mod pattern;
 pub mod kernels;
 pub mod launch;
+pub mod reference;
+mod rng;
+
+#[cfg(any(
+    feature = "einsum_tests_matmul",
+    feature = "einsum_tests_reduce",
+    feature = "einsum_tests_transpose",
+    feature = "einsum_tests_batch",
+    feature = "einsum_tests_contraction",
+))]
+pub mod tests;
 
 pub use error::EinsumError;
 pub use notation::{EinsumNotation, Subscript, parse_einsum};
-pub use optimization::{ExecutionPlan, ExecutionStep, ContractionStrategy};
+pub use optimization::{ExecutionPlan, ExecutionStep, ContractionStrategy, PlanReport, explain};
 pub use pattern::{FastPath, PatternMatcher};
-pub use launch::{einsum, EinsumConfig};
+pub use launch::{einsum, einsum_alloc, einsum_output_shape, EinsumConfig, PreparedEinsum};
@@ -0,0 +1,246 @@
+//! Canonical form of einsum notations.
+//!
+//! Two notations that only differ in index names (`ij,jk->ik` and
+//! `ab,bc->ac`) have the same canonical form, which makes it suitable as a
+//! cache key, for deduplication and for golden files.
+
+use alloc::vec::Vec;
+
+use super::notation::EinsumNotation;
+use super::parser::is_index_char;
+use super::subscript::{Index, Subscript};
+
+/// Options for [`EinsumNotation::canonicalize`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct CanonicalOptions {
+    /// Replaces the ellipsis with this many explicit indices.
+    ///
+    /// `None` keeps the ellipsis as is.
+    pub ellipsis_dims: Option<usize>,
+    /// Reorders operands by a name-independent signature.
+    ///
+    /// Einsum operands commute, so this maps `ij,j->i` and `j,ij->i` to the
+    /// same form. The permutation is reported in
+    /// [`CanonicalForm::operand_order`] so shapes and tensors can follow.
+    pub sort_operands: bool,
+}
+
+impl CanonicalOptions {
+    /// Sets the number of dimensions the ellipsis is expanded to.
+    pub fn with_ellipsis_dims(mut self, ellipsis_dims: usize) -> Self {
+        self.ellipsis_dims = Some(ellipsis_dims);
+        self
+    }
+
+    /// Enables or disables operand sorting.
+    pub fn with_sorted_operands(mut self, enabled: bool) -> Self {
+        self.sort_operands = enabled;
+        self
+    }
+}
+
+/// Result of [`EinsumNotation::canonicalize`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CanonicalForm {
+    /// Notation with indices renamed `a, b, c, ...` in order of first
+    /// appearance.
+    pub notation: EinsumNotation,
+    /// `operand_order[i]` is the original position of canonical operand `i`.
+    pub operand_order: Vec<usize>,
+    /// `index_names[i]` is the original name of the `i`-th canonical index.
+    ///
+    /// Indices created by expanding the ellipsis have generated names.
+    pub index_names: Vec<char>,
+}
+
+impl CanonicalForm {
+    /// Reorders per-operand data (shapes, tensors) into canonical order.
+    pub fn permute_operands<T: Clone>(&self, items: &[T]) -> Vec<T> {
+        self.operand_order.iter().map(|&i| items[i].clone()).collect()
+    }
+}
+
+impl EinsumNotation {
+    /// Computes the canonical form of this notation.
+    ///
+    /// Indices are renamed to `a`–`z`, then `A`–`Z`, in order of first
+    /// appearance across the inputs and then the output. Operand sorting is
+    /// a heuristic: operands with identical signatures keep their relative
+    /// order, so some equivalent notations can still differ.
+    pub fn canonicalize(&self, options: CanonicalOptions) -> CanonicalForm {
+        let notation = match options.ellipsis_dims {
+            Some(ellipsis_dims) => self.expand_ellipsis(ellipsis_dims),
+            None => self.clone(),
+        };
+
+        let mut operand_order: Vec<usize> = (0..notation.num_inputs()).collect();
+        if options.sort_operands {
+            let signatures: Vec<_> = notation
+                .inputs()
+                .iter()
+                .map(|s| operand_signature(&notation, s))
+                .collect();
+            operand_order.sort_by(|&a, &b| signatures[a].cmp(&signatures[b]));
+        }
+
+        let mut index_names: Vec<char> = Vec::new();
+        let mut rename = |subscript: &Subscript| {
+            let indices = subscript
+                .iter()
+                .map(|index| match index {
+                    Index::Named(c) => {
+                        let id = match index_names.iter().position(|n| n == c) {
+                            Some(id) => id,
+                            None => {
+                                index_names.push(*c);
+                                index_names.len() - 1
+                            }
+                        };
+                        Index::Named(canonical_char(id))
+                    }
+                    Index::Ellipsis => Index::Ellipsis,
+                })
+                .collect();
+            Subscript::from_indices(indices)
+        };
+
+        let inputs: Vec<Subscript> = operand_order
+            .iter()
+            .map(|&i| rename(&notation.inputs()[i]))
+            .collect();
+        let output = rename(notation.output());
+
+        CanonicalForm {
+            notation: EinsumNotation::new(inputs, output),
+            operand_order,
+            index_names,
+        }
+    }
+}
+
+/// Name-independent description of an operand used to sort operands.
+///
+/// Per position: where the index first appears in the operand (captures
+/// repeated indices), how often it appears across all inputs, and its
+/// position in the output (`usize::MAX` if summed). The ellipsis position
+/// comes last.
+fn operand_signature(
+    notation: &EinsumNotation,
+    subscript: &Subscript,
+) -> (Vec<(usize, usize, usize)>, Option<usize>) {
+    let output: Vec<char> = notation.output().named_indices().collect();
+    let named: Vec<char> = subscript.named_indices().collect();
+
+    let positions = named
+        .iter()
+        .map(|&c| {
+            let first = named.iter().position(|&x| x == c).unwrap_or(0);
+            let count = notation.count_in_inputs(c);
+            let out = output.iter().position(|&x| x == c).unwrap_or(usize::MAX);
+            (first, count, out)
+        })
+        .collect();
+
+    (positions, subscript.ellipsis_position())
+}
+
+/// Returns the canonical name of the `id`-th index.
+fn canonical_char(id: usize) -> char {
+    const LETTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+    match LETTERS.get(id) {
+        Some(&c) => c as char,
+        // Past 52 indices, fall back to non-ASCII letters, which still parse
+        None => (0xC0..=char::MAX as u32)
+            .filter_map(char::from_u32)
+            .filter(|&c| is_index_char(c))
+            .nth(id - LETTERS.len())
+            .unwrap_or('?'),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_einsum;
+    use alloc::string::ToString;
+
+    fn canonical(notation: &str, options: CanonicalOptions) -> CanonicalForm {
+        parse_einsum(notation).unwrap().canonicalize(options)
+    }
+
+    #[test]
+    fn test_renames_by_first_appearance() {
+        let a = canonical("ij,jk->ik", CanonicalOptions::default());
+        let b = canonical("xb,bq->xq", CanonicalOptions::default());
+
+        assert_eq!(a.notation.to_string(), "ab,bc->ac");
+        assert_eq!(a.notation, b.notation);
+        assert_eq!(b.index_names, ['x', 'b', 'q']);
+    }
+
+    #[test]
+    fn test_structure_differs() {
+        let a = canonical("ij,jk->ik", CanonicalOptions::default());
+        let b = canonical("ij,kj->ik", CanonicalOptions::default());
+
+        assert_ne!(a.notation, b.notation);
+    }
+
+    #[test]
+    fn test_keeps_ellipsis() {
+        let form = canonical("...qd,...kd->...qk", CanonicalOptions::default());
+        assert_eq!(form.notation.to_string(), "...ab,...cb->...ac");
+    }
+
+    #[test]
+    fn test_expands_ellipsis() {
+        let form = canonical("...ij,...jk->...ik", CanonicalOptions::default().with_ellipsis_dims(1));
+
+        assert_eq!(form.notation.to_string(), "abc,acd->abd");
+        assert!(!form.notation.has_ellipsis());
+    }
+
+    #[test]
+    fn test_sorted_operands() {
+        let options = CanonicalOptions::default().with_sorted_operands(true);
+        let a = canonical("ij,j->i", options);
+        let b = canonical("j,ij->i", options);
+
+        assert_eq!(a.notation, b.notation);
+        assert_eq!(a.notation.to_string(), "ab,b->a");
+        assert_eq!(a.operand_order, [0, 1]);
+        assert_eq!(b.operand_order, [1, 0]);
+        assert_eq!(b.permute_operands(&["j", "ij"]), ["ij", "j"]);
+    }
+
+    #[test]
+    fn test_unsorted_keeps_operand_order() {
+        let form = canonical("ij,j->i", CanonicalOptions::default());
+
+        assert_eq!(form.operand_order, [0, 1]);
+        assert_eq!(form.notation.to_string(), "ab,b->a");
+    }
+
+    #[test]
+    fn test_equal_forms_hash_equally() {
+        use core::hash::BuildHasher;
+
+        let a = canonical("ij,jk->ik", CanonicalOptions::default());
+        let b = canonical("pq,qr->pr", CanonicalOptions::default());
+
+        let state = hashbrown::DefaultHashBuilder::default();
+        assert_eq!(state.hash_one(&a.notation), state.hash_one(&b.notation));
+    }
+
+    #[test]
+    fn test_many_indices_stay_parseable() {
+        let names: alloc::string::String = (0..60).map(canonical_char).collect();
+        assert!(names.chars().all(char::is_alphabetic));
+        assert!(!names.contains('×') && !names.contains('÷'));
+
+        let form = canonical(&alloc::format!("{names}->"), CanonicalOptions::default());
+        let text = form.notation.to_string();
+        assert_eq!(parse_einsum(&text).unwrap(), form.notation);
+    }
+}
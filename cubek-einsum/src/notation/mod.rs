@@ -8,9 +8,12 @@
 mod parser;
 mod subscript;
 mod notation;
+mod canonical;
 pub mod validation;
 
 pub use parser::parse_einsum;
 pub use subscript::{Subscript, Index};
 pub use notation::EinsumNotation;
+pub use canonical::{CanonicalForm, CanonicalOptions};
+pub(crate) use notation::generate_batch_indices;
 pub use validation::validate_notation;
@@ -5,6 +5,9 @@ use alloc::vec::Vec;
 use alloc::collections::BTreeSet;
 use alloc::string::String;
 use core::fmt;
+use core::hash::{Hash, Hasher};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use super::subscript::Subscript;
 
@@ -278,6 +281,24 @@ impl EinsumNotation {
         )
     }
 
+    /// Replaces the ellipsis with `ellipsis_dims` explicit batch indices.
+    ///
+    /// The result has only named indices, so it can be handled by code that
+    /// works on `named_indices()` alone (path search, the executor).
+    pub fn expand_ellipsis(&self, ellipsis_dims: usize) -> EinsumNotation {
+        if !self.has_ellipsis {
+            return self.clone();
+        }
+
+        let batch = generate_batch_indices(ellipsis_dims);
+        let inputs = self.inputs.iter().map(|s| s.expand_ellipsis(&batch)).collect();
+        let output = self.output.expand_ellipsis(&batch);
+
+        let mut expanded = EinsumNotation::new(inputs, output);
+        expanded.original = self.original.clone();
+        expanded
+    }
+
     /// Returns the dimensions for the output tensor given input dimensions.
     pub fn compute_output_shape(
         &self,
@@ -371,7 +392,7 @@ impl EinsumNotation {
 }
 
 /// Generates batch index characters for ellipsis expansion.
-fn generate_batch_indices(count: usize) -> Vec<char> {
+pub(crate) fn generate_batch_indices(count: usize) -> Vec<char> {
     // Use uppercase letters starting from 'A' for batch indices
     // These won't conflict with typical lowercase indices
     (0..count)
@@ -383,6 +404,23 @@ fn generate_batch_indices(count: usize) -> Vec<char> {
         .collect()
 }
 
+/// Notations are equal when their subscripts are, regardless of the
+/// original string they were parsed from.
+impl PartialEq for EinsumNotation {
+    fn eq(&self, other: &Self) -> bool {
+        self.inputs == other.inputs && self.output == other.output
+    }
+}
+
+impl Eq for EinsumNotation {}
+
+impl Hash for EinsumNotation {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inputs.hash(state);
+        self.output.hash(state);
+    }
+}
+
 impl fmt::Display for EinsumNotation {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for (i, input) in self.inputs.iter().enumerate() {
@@ -395,6 +433,42 @@ impl fmt::Display for EinsumNotation {
     }
 }
 
+/// Serialized as its string form, e.g. `"ij,jk->ik"`.
+///
+/// Derived index sets are recomputed on deserialization and the original
+/// string is not kept. Deserialization validates the notation like
+/// [`validate_notation`](super::validate_notation).
+impl Serialize for EinsumNotation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for EinsumNotation {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let notation = decode_notation(&s).ok_or_else(|| {
+            serde::de::Error::custom(alloc::format!("invalid notation '{}'", s))
+        })?;
+        super::validate_notation(&notation).map_err(|e| {
+            serde::de::Error::custom(alloc::format!("invalid notation '{}': {}", s, e))
+        })?;
+        Ok(notation)
+    }
+}
+
+/// Decodes a notation written by its `Display` implementation.
+fn decode_notation(s: &str) -> Option<EinsumNotation> {
+    let (inputs, output) = s.split_once("->")?;
+    let inputs = inputs
+        .split(',')
+        .map(Subscript::decode)
+        .collect::<Option<Vec<_>>>()?;
+    let output = Subscript::decode(output)?;
+
+    Some(EinsumNotation::new(inputs, output))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -449,4 +523,50 @@ mod tests {
 
         assert_eq!(format!("{}", notation), "ij,jk->ik");
     }
+
+    #[test]
+    fn test_expand_ellipsis() {
+        let notation = crate::notation::parse_einsum("...ij,...jk->...ik").unwrap();
+
+        let expanded = notation.expand_ellipsis(2);
+
+        assert!(!expanded.has_ellipsis());
+        assert_eq!(expanded.inputs()[0].len(), 4);
+        assert_eq!(expanded.output_indices().len(), 4);
+        assert!(expanded.batch_indices().contains(&'\u{2460}'));
+    }
+
+    #[test]
+    fn test_serde_roundtrip() {
+        let notation = crate::notation::parse_einsum("...ij,...jk->...ik").unwrap();
+
+        let json = serde_json::to_string(&notation).unwrap();
+        assert_eq!(json, "\"...ij,...jk->...ik\"");
+
+        let decoded: EinsumNotation = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, notation);
+        assert_eq!(decoded.contraction_indices(), notation.contraction_indices());
+        assert_eq!(decoded.batch_indices(), notation.batch_indices());
+    }
+
+    #[test]
+    fn test_serde_roundtrip_generated_indices() {
+        let notation = crate::notation::parse_einsum("...ij,...jk->...ik").unwrap();
+        let expanded = notation.expand_ellipsis(2);
+
+        let json = serde_json::to_string(&expanded).unwrap();
+        let decoded: EinsumNotation = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, expanded);
+    }
+
+    #[test]
+    fn test_serde_rejects_invalid() {
+        assert!(serde_json::from_str::<EinsumNotation>("\"ij,jk\"").is_err());
+        assert!(serde_json::from_str::<EinsumNotation>("\"i..j->i\"").is_err());
+        // Characters the parser rejects
+        assert!(serde_json::from_str::<EinsumNotation>("\"i×j,jk->ik\"").is_err());
+        assert!(serde_json::from_str::<EinsumNotation>("\"i j->ij\"").is_err());
+        // Output index missing from the inputs
+        assert!(serde_json::from_str::<EinsumNotation>("\"ij,jk->iz\"").is_err());
+    }
 }
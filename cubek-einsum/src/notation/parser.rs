@@ -17,7 +17,7 @@ use crate::error::{EinsumError, EinsumResult};
 /// inputs      ::= subscript (',' subscript)*
 /// output      ::= subscript
 /// subscript   ::= index* | '...' index* | index* '...' | index* '...' index*
-/// index       ::= [a-zA-Z]
+/// index       ::= any alphabetic character outside U+2460..=U+24FF, usually [a-zA-Z]
 /// ```
 ///
 /// # Examples
@@ -86,8 +86,8 @@ fn parse_subscript(s: &str) -> EinsumResult<Subscript> {
                 }
                 subscript.push_ellipsis();
             }
-            // Named index
-            'a'..='z' | 'A'..='Z' => {
+            // Named index, non-ASCII letters extend the 52 ASCII ones
+            c if is_index_char(c) => {
                 subscript.push_named(c);
             }
             // Whitespace is ignored
@@ -105,6 +105,17 @@ fn parse_subscript(s: &str) -> EinsumResult<Subscript> {
     Ok(subscript)
 }
 
+/// Code points reserved for the batch indices an ellipsis expands to.
+///
+/// Batch indices are taken from U+2460 on; the rest of the enclosed
+/// alphanumerics block includes letters (`ⓐ`) that would otherwise parse.
+pub(crate) const BATCH_INDEX_RANGE: core::ops::RangeInclusive<char> = '\u{2460}'..='\u{24FF}';
+
+/// Returns whether `c` can name an index in a notation string.
+pub(crate) fn is_index_char(c: char) -> bool {
+    c.is_alphabetic() && !BATCH_INDEX_RANGE.contains(&c)
+}
+
 /// Infers the output subscript when not explicitly provided.
 ///
 /// Rules (NumPy einsum convention):
@@ -265,6 +276,22 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_parse_non_ascii_letters() {
+        let notation = parse_einsum("iÀ,Àk->ik").unwrap();
+        assert!(notation.contraction_indices().contains(&'À'));
+        assert!(parse_einsum("i×j->ij").is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_batch_index_range() {
+        // Ellipsis expansion names batch dimensions with these
+        assert!(parse_einsum("i\u{2460},\u{2460}k->ik").is_err());
+        // Alphabetic, but in the same block
+        assert!('\u{24D0}'.is_alphabetic());
+        assert!(parse_einsum("\u{24D0}j->j").is_err());
+    }
+
     #[test]
     fn test_parse_error_incomplete_ellipsis() {
         let result = parse_einsum("..ij,jk->ik");
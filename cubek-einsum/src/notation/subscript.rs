@@ -4,6 +4,10 @@ use alloc::vec::Vec;
 use alloc::string::String;
 use core::fmt;
 
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::parser::{is_index_char, BATCH_INDEX_RANGE};
+
 /// A single index in an einsum subscript.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Index {
@@ -42,7 +46,7 @@ impl fmt::Display for Index {
 /// A subscript representing the indices of a single tensor.
 ///
 /// For example, in `ij,jk->ik`, the subscripts are `ij`, `jk`, and `ik`.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Subscript {
     /// The indices in order.
     indices: Vec<Index>,
@@ -81,6 +85,31 @@ impl Subscript {
         Self::from_indices(indices)
     }
 
+    /// Decodes a subscript written by its `Display` implementation.
+    ///
+    /// Accepts the same indices as the parser, plus the batch indices an
+    /// ellipsis expands to, so expanded subscripts round-trip.
+    pub(crate) fn decode(s: &str) -> Option<Self> {
+        let mut subscript = Subscript::new();
+        let mut chars = s.chars();
+
+        while let Some(c) = chars.next() {
+            if is_index_char(c) || BATCH_INDEX_RANGE.contains(&c) {
+                subscript.push_named(c);
+                continue;
+            }
+            if c != '.' {
+                return None;
+            }
+            if chars.next() != Some('.') || chars.next() != Some('.') || subscript.has_ellipsis() {
+                return None;
+            }
+            subscript.push_ellipsis();
+        }
+
+        Some(subscript)
+    }
+
     /// Adds a named index.
     pub fn push_named(&mut self, c: char) {
         self.indices.push(Index::Named(c));
@@ -217,6 +246,22 @@ impl fmt::Display for Subscript {
     }
 }
 
+/// Serialized as its string form, e.g. `"...ij"`.
+impl Serialize for Subscript {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Subscript {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Subscript::decode(&s).ok_or_else(|| {
+            serde::de::Error::custom(alloc::format!("invalid subscript '{}'", s))
+        })
+    }
+}
+
 impl<'a> IntoIterator for &'a Subscript {
     type Item = &'a Index;
     type IntoIter = core::slice::Iter<'a, Index>;
@@ -278,4 +323,18 @@ mod tests {
         assert_eq!(sub.count('j'), 1);
         assert_eq!(sub.count('k'), 0);
     }
+
+    #[test]
+    fn test_serde_roundtrip() {
+        let sub = Subscript::decode("i...j").unwrap();
+        assert_eq!(sub.ellipsis_position(), Some(1));
+
+        let json = serde_json::to_string(&sub).unwrap();
+        assert_eq!(json, "\"i...j\"");
+        assert_eq!(serde_json::from_str::<Subscript>(&json).unwrap(), sub);
+
+        assert!(serde_json::from_str::<Subscript>("\"...i...\"").is_err());
+        assert!(serde_json::from_str::<Subscript>("\"i,j\"").is_err());
+        assert!(serde_json::from_str::<Subscript>("\"i j\"").is_err());
+    }
 }
@@ -129,6 +129,28 @@ pub fn validate_shapes(
     })
 }
 
+/// Expands the ellipsis of `notation` the way `create_plan` does and maps
+/// every index of the expanded notation to its size.
+///
+/// Paths and plans refer to the explicit batch indices of the expanded
+/// notation, so they are described with it.
+pub(crate) fn expanded_dims(
+    notation: &EinsumNotation,
+    shapes: &[&[usize]],
+) -> EinsumResult<(EinsumNotation, HashMap<char, usize>)> {
+    let ellipsis_dims = validate_shapes(notation, shapes)?.ellipsis_dims;
+    let expanded = notation.expand_ellipsis(ellipsis_dims);
+
+    let mut dims: HashMap<char, usize> = HashMap::new();
+    for (input, shape) in expanded.inputs().iter().zip(shapes.iter()) {
+        for (c, &d) in input.named_indices().zip(shape.iter()) {
+            dims.insert(c, d);
+        }
+    }
+
+    Ok((expanded, dims))
+}
+
 /// Result of shape validation.
 #[derive(Debug, Clone)]
 pub struct ValidationResult {
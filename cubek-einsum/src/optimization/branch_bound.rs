@@ -5,23 +5,39 @@
 //! full DP (optimal, exponential).
 
 use alloc::vec::Vec;
-use alloc::collections::BTreeSet;
+use alloc::collections::{BTreeMap, BTreeSet};
+use core::time::Duration;
+
+use hashbrown::HashMap;
 
 use super::cost::{CostModel, ContractionCost};
-use super::path::{ContractionPath, ContractionStep, TensorState};
+use super::path::{ContractionPath, ContractionStep, TensorState, pair_result_size, split_pair_indices_by};
 use super::greedy::greedy_path;
 use crate::notation::EinsumNotation;
 
-/// Maximum depth before falling back to greedy.
-const MAX_SEARCH_DEPTH: usize = 8;
+/// Default depth past which the remaining tensors are contracted greedily.
+pub const DEFAULT_MAX_DEPTH: usize = 8;
+
+/// Default number of nodes explored before returning the best path found.
+pub const DEFAULT_MAX_NODES: u64 = 100_000;
+
+/// Number of search states remembered for pruning revisits.
+const MAX_SEEN_STATES: usize = 1 << 20;
 
-/// Maximum nodes to explore before returning best found.
-const MAX_NODES: u64 = 100_000;
+/// Outcome of a budgeted branch and bound search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BranchBoundResult {
+    /// Best path found.
+    pub path: ContractionPath,
+    /// Whether the search ran to completion, so that no path of lower cost
+    /// exists. False if a budget ran out or the depth limit was reached.
+    pub proven_optimal: bool,
+    /// Number of nodes explored.
+    pub nodes_explored: u64,
+}
 
 /// Branch and bound search state.
 struct SearchState<'a> {
-    #[allow(dead_code)]
-    notation: &'a EinsumNotation,
     cost_model: &'a CostModel,
     output_indices: BTreeSet<char>,
 
@@ -31,6 +47,31 @@ struct SearchState<'a> {
     best_cost: ContractionCost,
     /// Number of nodes explored.
     nodes_explored: u64,
+    /// Nodes that may be explored.
+    max_nodes: u64,
+    /// Depth past which the remainder is contracted greedily.
+    max_depth: usize,
+    /// Time at which the search stops.
+    #[cfg(feature = "std")]
+    deadline: Option<std::time::Instant>,
+    /// Whether part of the search space was skipped for a budget.
+    truncated: bool,
+    /// Lowest cost at which each set of remaining tensors was reached.
+    seen: HashMap<Vec<char>, ContractionCost>,
+}
+
+impl SearchState<'_> {
+    /// Whether the node or time budget ran out.
+    fn out_of_budget(&self) -> bool {
+        if self.nodes_explored >= self.max_nodes {
+            return true;
+        }
+        #[cfg(feature = "std")]
+        if self.deadline.is_some_and(|deadline| std::time::Instant::now() >= deadline) {
+            return true;
+        }
+        false
+    }
 }
 
 /// Finds a contraction path using branch and bound.
@@ -39,6 +80,14 @@ struct SearchState<'a> {
 /// 1. Upper bound: best solution found so far (starts with greedy)
 /// 2. Lower bound: optimistic estimate of remaining cost
 /// 3. Prunes branches where lower_bound >= best_cost
+/// 4. Prunes sets of remaining tensors already reached at a cost that is no
+///    higher in both its summed and its largest-size part
+///
+/// Contractions whose result exceeds the cost model's memory limit are not
+/// explored. If no path fits the limit, the greedy path is returned.
+///
+/// Uses the default budgets of [`DEFAULT_MAX_NODES`] nodes and
+/// [`DEFAULT_MAX_DEPTH`] levels, see [`branch_bound_path_with_limits`].
 ///
 /// # Arguments
 /// * `notation` - The einsum notation
@@ -49,10 +98,31 @@ pub fn branch_bound_path(
     shapes: &[&[usize]],
     cost_model: &CostModel,
 ) -> ContractionPath {
+    branch_bound_path_with_limits(notation, shapes, cost_model, DEFAULT_MAX_NODES, DEFAULT_MAX_DEPTH, None).path
+}
+
+/// Branch and bound with configurable budgets.
+///
+/// The search is anytime: it starts from the greedy path and returns the
+/// best path found when `max_nodes` nodes have been explored or
+/// `time_limit` has passed. Past `max_depth` contractions, the remaining
+/// tensors are contracted greedily. The time limit needs the `std` feature
+/// and is ignored without it.
+///
+/// The result is proven optimal if no budget ran out and the depth limit
+/// was never reached.
+pub fn branch_bound_path_with_limits(
+    notation: &EinsumNotation,
+    shapes: &[&[usize]],
+    cost_model: &CostModel,
+    max_nodes: u64,
+    max_depth: usize,
+    time_limit: Option<Duration>,
+) -> BranchBoundResult {
     let n = notation.num_inputs();
 
     if n == 0 || n == 1 {
-        return ContractionPath::new();
+        return BranchBoundResult { path: ContractionPath::new(), proven_optimal: true, nodes_explored: 0 };
     }
 
     // Initialize tensor state
@@ -67,15 +137,23 @@ pub fn branch_bound_path(
 
     // Get greedy solution as initial upper bound
     let greedy = greedy_path(notation, shapes, cost_model);
-    let greedy_cost = compute_path_cost(&greedy, &initial_shapes, &initial_indices, cost_model);
+    let greedy_cost = compute_path_cost(&greedy, &initial_shapes, &initial_indices, cost_model)
+        .unwrap_or(ContractionCost::new(u64::MAX, u64::MAX, 1));
 
+    #[cfg(not(feature = "std"))]
+    let _ = time_limit;
     let mut state = SearchState {
-        notation,
         cost_model,
         output_indices,
         best_path: Some(greedy),
         best_cost: greedy_cost,
         nodes_explored: 0,
+        max_nodes,
+        max_depth,
+        #[cfg(feature = "std")]
+        deadline: time_limit.and_then(|limit| std::time::Instant::now().checked_add(limit)),
+        truncated: false,
+        seen: HashMap::new(),
     };
 
     // Start search
@@ -91,7 +169,11 @@ pub fn branch_bound_path(
         0,
     );
 
-    state.best_path.unwrap_or_else(ContractionPath::new)
+    BranchBoundResult {
+        path: state.best_path.unwrap_or_default(),
+        proven_optimal: !state.truncated,
+        nodes_explored: state.nodes_explored,
+    }
 }
 
 /// Recursive branch and bound search.
@@ -105,7 +187,8 @@ fn branch_bound_search(
     state.nodes_explored += 1;
 
     // Check termination conditions
-    if state.nodes_explored >= MAX_NODES {
+    if state.out_of_budget() {
+        state.truncated = true;
         return;
     }
 
@@ -122,23 +205,47 @@ fn branch_bound_search(
         return;
     }
 
+    // The same tensors are left after contracting independent pairs in
+    // either order, so a state reached again at no lower cost is pruned.
+    // Sizes combine by maximum, so both parts of the cost are compared
+    let key = state_key(&tensor_state);
+    if let Some(seen) = state.seen.get_mut(&key) {
+        if current_cost.is_dominated_by(seen) {
+            return;
+        }
+        *seen = current_cost;
+    } else if state.seen.len() < MAX_SEEN_STATES {
+        state.seen.insert(key, current_cost);
+    }
+
+    // Compute lower bound on remaining cost
+    let lower_bound = state.cost_model.optimistic_remaining_cost(
+        &tensor_state.shapes,
+        &tensor_state.indices,
+        &state.output_indices,
+    );
+
+    // Prune if we can't possibly beat the best
+    if current_cost + lower_bound >= state.best_cost {
+        return;
+    }
+
     // If too deep, use greedy for remainder
-    if depth >= MAX_SEARCH_DEPTH {
-        let remaining_cost = greedy_remaining_cost(
+    if depth >= state.max_depth {
+        // With two tensors left, the greedy step is the only one
+        state.truncated |= tensor_state.len() > 2;
+        let Some((greedy_steps, total_cost)) = greedy_remaining_steps(
             &tensor_state,
             &state.output_indices,
             state.cost_model,
-        );
-        let total_cost = current_cost + remaining_cost;
+            current_cost,
+            state.best_cost,
+        ) else {
+            return;
+        };
 
         if total_cost < state.best_cost {
             // Reconstruct full path with greedy remainder
-            let greedy_steps = greedy_remaining_steps(
-                &tensor_state,
-                &state.output_indices,
-                state.cost_model,
-            );
-
             state.best_cost = total_cost;
             let mut path = ContractionPath::with_capacity(current_path.len() + greedy_steps.len());
             for step in current_path.iter() {
@@ -152,22 +259,12 @@ fn branch_bound_search(
         return;
     }
 
-    // Compute lower bound on remaining cost
-    let lower_bound = state.cost_model.optimistic_remaining_cost(
-        &tensor_state.shapes,
-        &tensor_state.indices,
-    );
-
-    // Prune if we can't possibly beat the best
-    if current_cost + lower_bound >= state.best_cost {
-        return;
-    }
-
     // Generate all possible contractions, sorted by cost (best first)
     let mut candidates = generate_candidates(&tensor_state, &state.output_indices, state.cost_model);
 
-    // Sort by cost (greedy ordering for better pruning)
-    candidates.sort_by(|a, b| a.2.cmp(&b.2));
+    // Sort by cost (greedy ordering for better pruning). Sizes combine by
+    // maximum, so the order is by the cost of the branch, not of the step.
+    candidates.sort_by_key(|c| current_cost + c.2);
 
     // Try each candidate
     for (i, j, step_cost, step) in candidates {
@@ -187,72 +284,71 @@ fn branch_bound_search(
         branch_bound_search(state, new_tensor_state, current_path, new_cost, depth + 1);
         current_path.pop();
 
-        // Early termination if we've hit node limit
-        if state.nodes_explored >= MAX_NODES {
+        // Early termination if a budget ran out
+        if state.out_of_budget() {
+            state.truncated = true;
             return;
         }
     }
 }
 
+/// Identifies the remaining tensors by their sorted indices, independently
+/// of their order.
+fn state_key(state: &TensorState) -> Vec<char> {
+    let mut tensors: Vec<Vec<char>> = state
+        .indices
+        .iter()
+        .map(|indices| {
+            let mut indices = indices.clone();
+            indices.sort_unstable();
+            indices
+        })
+        .collect();
+    tensors.sort_unstable();
+    tensors.join(&',')
+}
+
 /// Generates all possible pairwise contractions for the current state.
+///
+/// Contractions whose result exceeds the memory limit are left out, except
+/// for the final one.
 fn generate_candidates(
     state: &TensorState,
     output_indices: &BTreeSet<char>,
     cost_model: &CostModel,
 ) -> Vec<(usize, usize, ContractionCost, ContractionStep)> {
     let n = state.len();
+    let is_last = n == 2;
     let mut candidates = Vec::with_capacity(n * (n - 1) / 2);
 
-    // Track which indices are needed elsewhere
-    let mut kept_indices = output_indices.clone();
-    for idx_vec in &state.indices {
-        for &c in idx_vec {
-            kept_indices.insert(c);
+    // Number of tensors each index appears in
+    let mut counts: BTreeMap<char, usize> = BTreeMap::new();
+    for indices in &state.indices {
+        for (pos, &c) in indices.iter().enumerate() {
+            if !indices[..pos].contains(&c) {
+                *counts.entry(c).or_default() += 1;
+            }
         }
     }
 
     for i in 0..n {
         for j in (i + 1)..n {
-            let indices_i: BTreeSet<char> = state.indices[i].iter().copied().collect();
-            let indices_j: BTreeSet<char> = state.indices[j].iter().copied().collect();
-
-            // Common indices
-            let common: BTreeSet<char> = indices_i.intersection(&indices_j).copied().collect();
-
-            // Indices that must be kept (appear in other tensors or output)
-            let mut must_keep = output_indices.clone();
-            for (k, idx) in state.indices.iter().enumerate() {
-                if k != i && k != j {
-                    for &c in idx {
-                        must_keep.insert(c);
-                    }
-                }
-            }
-
-            // Contracted indices
-            let contracted: Vec<char> = common
-                .iter()
-                .filter(|c| !must_keep.contains(c))
-                .copied()
-                .collect();
-
-            let contracted_set: BTreeSet<char> = contracted.iter().copied().collect();
-
-            // Result indices
-            let mut result_indices = Vec::new();
-            let mut seen = BTreeSet::new();
-
-            for &c in &state.indices[i] {
-                if !contracted_set.contains(&c) && !seen.contains(&c) {
-                    result_indices.push(c);
-                    seen.insert(c);
-                }
-            }
-            for &c in &state.indices[j] {
-                if !contracted_set.contains(&c) && !seen.contains(&c) {
-                    result_indices.push(c);
-                    seen.insert(c);
-                }
+            // Contracted indices: everything in the pair not needed by other
+            // tensors or the output
+            let (contracted, result_indices) = split_pair_indices_by(&state.indices[i], &state.indices[j], |c| {
+                let in_pair = usize::from(state.indices[i].contains(&c)) + usize::from(state.indices[j].contains(&c));
+                output_indices.contains(&c) || counts.get(&c).copied().unwrap_or(0) > in_pair
+            });
+
+            let size = pair_result_size(
+                &state.shapes[i],
+                &state.indices[i],
+                &state.shapes[j],
+                &state.indices[j],
+                &result_indices,
+            );
+            if !is_last && !cost_model.fits_memory_limit(size) {
+                continue;
             }
 
             // Compute cost
@@ -269,7 +365,8 @@ fn generate_candidates(
                 contracted,
                 result_indices,
                 cost.flops,
-            );
+            )
+            .with_cost(&cost);
 
             candidates.push((i, j, cost, step));
         }
@@ -279,16 +376,18 @@ fn generate_candidates(
 }
 
 /// Computes the total cost of a path.
+///
+/// Returns `None` if an intermediate exceeds the memory limit.
 fn compute_path_cost(
     path: &ContractionPath,
     initial_shapes: &[Vec<usize>],
     initial_indices: &[Vec<char>],
     cost_model: &CostModel,
-) -> ContractionCost {
+) -> Option<ContractionCost> {
     let mut total = ContractionCost::zero();
     let mut state = TensorState::new(initial_shapes.to_vec(), initial_indices.to_vec());
 
-    for step in path.steps() {
+    for (idx, step) in path.steps().iter().enumerate() {
         let (i, j) = step.inputs;
 
         let cost = cost_model.compute_pairwise_cost(
@@ -301,215 +400,51 @@ fn compute_path_cost(
 
         total = total + cost;
         state = state.contract(i, j, &step.result_indices);
-    }
-
-    total
-}
-
-/// Computes cost of greedy solution for remaining tensors.
-fn greedy_remaining_cost(
-    state: &TensorState,
-    output_indices: &BTreeSet<char>,
-    cost_model: &CostModel,
-) -> ContractionCost {
-    let steps = greedy_remaining_steps(state, output_indices, cost_model);
 
-    let mut total = ContractionCost::zero();
-    let mut current_state = state.clone();
-
-    for step in &steps {
-        let (i, j) = step.inputs;
-
-        let cost = cost_model.compute_pairwise_cost(
-            &current_state.shapes[i],
-            &current_state.shapes[j],
-            &current_state.indices[i],
-            &current_state.indices[j],
-            &step.contracted_indices,
-        );
-
-        total = total + cost;
-        current_state = current_state.contract(i, j, &step.result_indices);
+        let elements = state.shapes[state.len() - 1]
+            .iter()
+            .fold(1u64, |acc, &d| acc.saturating_mul(d as u64));
+        if idx + 1 < path.len() && !cost_model.fits_memory_limit(elements) {
+            return None;
+        }
     }
 
-    total
+    Some(total)
 }
 
-/// Gets greedy steps for remaining tensors.
+/// Gets greedy steps for the remaining tensors, after a path of cost
+/// `start`, and the total cost including `start`.
+///
+/// Returns `None` if the greedy remainder gets stuck on the memory limit or
+/// its cost reaches `bound`, the cost of the best path found so far.
 fn greedy_remaining_steps(
     state: &TensorState,
     output_indices: &BTreeSet<char>,
     cost_model: &CostModel,
-) -> Vec<ContractionStep> {
+    start: ContractionCost,
+    bound: ContractionCost,
+) -> Option<(Vec<ContractionStep>, ContractionCost)> {
     let mut steps = Vec::new();
+    let mut total = start;
     let mut current_state = state.clone();
 
     while current_state.len() > 1 {
         let candidates = generate_candidates(&current_state, output_indices, cost_model);
 
         // Pick lowest cost
-        let best = candidates
+        let (i, j, cost, step) = candidates
             .into_iter()
-            .min_by(|a, b| a.2.cmp(&b.2))
-            .expect("should have candidates");
+            .min_by(|a, b| a.2.cmp(&b.2))?;
 
-        let (i, j, _, step) = best;
+        total = total + cost;
+        if total >= bound {
+            return None;
+        }
         current_state = current_state.contract(i, j, &step.result_indices);
         steps.push(step);
     }
 
-    steps
-}
-
-/// Branch and bound with configurable limits.
-#[allow(dead_code)]
-pub fn branch_bound_path_with_limits(
-    notation: &EinsumNotation,
-    shapes: &[&[usize]],
-    cost_model: &CostModel,
-    max_nodes: u64,
-    max_depth: usize,
-) -> ContractionPath {
-    let n = notation.num_inputs();
-
-    if n == 0 || n == 1 {
-        return ContractionPath::new();
-    }
-
-    let initial_shapes: Vec<Vec<usize>> = shapes.iter().map(|s| s.to_vec()).collect();
-    let initial_indices: Vec<Vec<char>> = notation
-        .inputs()
-        .iter()
-        .map(|s| s.named_indices().collect())
-        .collect();
-
-    let output_indices: BTreeSet<char> = notation.output().named_indices().collect();
-
-    let greedy = greedy_path(notation, shapes, cost_model);
-    let greedy_cost = compute_path_cost(&greedy, &initial_shapes, &initial_indices, cost_model);
-
-    let mut search_state = SearchState {
-        notation,
-        cost_model,
-        output_indices: output_indices.clone(),
-        best_path: Some(greedy),
-        best_cost: greedy_cost,
-        nodes_explored: 0,
-    };
-
-    // Custom search with limits
-    let tensor_state = TensorState::new(initial_shapes, initial_indices);
-    let mut current_path = Vec::new();
-    let current_cost = ContractionCost::zero();
-
-    branch_bound_search_with_limits(
-        &mut search_state,
-        tensor_state,
-        &mut current_path,
-        current_cost,
-        0,
-        max_nodes,
-        max_depth,
-        &output_indices,
-    );
-
-    search_state.best_path.unwrap_or_else(ContractionPath::new)
-}
-
-fn branch_bound_search_with_limits(
-    state: &mut SearchState,
-    tensor_state: TensorState,
-    current_path: &mut Vec<ContractionStep>,
-    current_cost: ContractionCost,
-    depth: usize,
-    max_nodes: u64,
-    max_depth: usize,
-    output_indices: &BTreeSet<char>,
-) {
-    state.nodes_explored += 1;
-
-    if state.nodes_explored >= max_nodes {
-        return;
-    }
-
-    if tensor_state.len() <= 1 {
-        if current_cost < state.best_cost {
-            state.best_cost = current_cost;
-            let mut path = ContractionPath::with_capacity(current_path.len());
-            for step in current_path.iter() {
-                path.push(step.clone());
-            }
-            state.best_path = Some(path);
-        }
-        return;
-    }
-
-    if depth >= max_depth {
-        let remaining_cost = greedy_remaining_cost(
-            &tensor_state,
-            output_indices,
-            state.cost_model,
-        );
-        let total_cost = current_cost + remaining_cost;
-
-        if total_cost < state.best_cost {
-            let greedy_steps = greedy_remaining_steps(
-                &tensor_state,
-                output_indices,
-                state.cost_model,
-            );
-
-            state.best_cost = total_cost;
-            let mut path = ContractionPath::with_capacity(current_path.len() + greedy_steps.len());
-            for step in current_path.iter() {
-                path.push(step.clone());
-            }
-            for step in greedy_steps {
-                path.push(step);
-            }
-            state.best_path = Some(path);
-        }
-        return;
-    }
-
-    let lower_bound = state.cost_model.optimistic_remaining_cost(
-        &tensor_state.shapes,
-        &tensor_state.indices,
-    );
-
-    if current_cost + lower_bound >= state.best_cost {
-        return;
-    }
-
-    let mut candidates = generate_candidates(&tensor_state, output_indices, state.cost_model);
-    candidates.sort_by(|a, b| a.2.cmp(&b.2));
-
-    for (i, j, step_cost, step) in candidates {
-        let new_cost = current_cost + step_cost;
-
-        if new_cost >= state.best_cost {
-            break;
-        }
-
-        let new_tensor_state = tensor_state.contract(i, j, &step.result_indices);
-
-        current_path.push(step);
-        branch_bound_search_with_limits(
-            state,
-            new_tensor_state,
-            current_path,
-            new_cost,
-            depth + 1,
-            max_nodes,
-            max_depth,
-            output_indices,
-        );
-        current_path.pop();
-
-        if state.nodes_explored >= max_nodes {
-            return;
-        }
-    }
+    Some((steps, total))
 }
 
 #[cfg(test)]
@@ -546,7 +481,7 @@ mod tests {
         // 2x10, 10x1000, 1000x3
         let notation = parse_einsum("ij,jk,kl->il").unwrap();
         let shapes: &[&[usize]] = &[&[2, 10], &[10, 1000], &[1000, 3]];
-        let cost_model = CostModel { alpha: 0 };
+        let cost_model = CostModel { alpha: 0, ..CostModel::default() };
 
         let bb_path = branch_bound_path(&notation, shapes, &cost_model);
         let greedy = greedy_path(&notation, shapes, &cost_model);
@@ -570,6 +505,38 @@ mod tests {
         assert_eq!(path.len(), 3);
     }
 
+    #[test]
+    fn test_branch_bound_memory_limit() {
+        let notation = parse_einsum("i,j,ij,k,jk->").unwrap();
+        let shapes: &[&[usize]] = &[&[100], &[100], &[100, 100], &[100], &[100, 100]];
+        let cost_model = CostModel::default().with_memory_limit(1_000);
+
+        let path = branch_bound_path(&notation, shapes, &cost_model);
+
+        assert_eq!(path.len(), 4);
+        let mut state = TensorState::new(
+            shapes.iter().map(|s| s.to_vec()).collect(),
+            notation.inputs().iter().map(|s| s.named_indices().collect()).collect(),
+        );
+        for step in &path.steps()[..3] {
+            state = state.contract(step.inputs.0, step.inputs.1, &step.result_indices);
+            let elements: usize = state.shapes[state.len() - 1].iter().product();
+            assert!(elements <= 1_000, "{:?}", state.shapes);
+        }
+    }
+
+    #[test]
+    fn test_branch_bound_size_objective() {
+        let notation = parse_einsum("ab,bc,cd->ad").unwrap();
+        let shapes: &[&[usize]] = &[&[5, 20], &[20, 5], &[5, 2]];
+        let cost_model = CostModel::default().with_objective(crate::optimization::Objective::Size);
+
+        let path = branch_bound_path(&notation, shapes, &cost_model);
+
+        assert_eq!(path.largest_intermediate(), 25);
+        assert_eq!(path.total_write(), 25 + 10);
+    }
+
     #[test]
     fn test_branch_bound_batch_matmul() {
         let notation = parse_einsum("bij,bjk->bik").unwrap();
@@ -590,12 +557,160 @@ mod tests {
         let cost_model = CostModel::default();
 
         // With low limits, should still produce valid path
-        let path = branch_bound_path_with_limits(
+        let result = branch_bound_path_with_limits(
             &notation, shapes, &cost_model,
             100,  // max_nodes
             3,    // max_depth
+            None,
         );
 
-        assert_eq!(path.len(), 4);
+        assert_eq!(result.path.len(), 4);
+        assert!(result.nodes_explored <= 100);
+    }
+
+    #[test]
+    fn test_branch_bound_proven_optimal() {
+        let notation = parse_einsum("ij,jk,kl,lm->im").unwrap();
+        let shapes: &[&[usize]] = &[&[10, 20], &[20, 30], &[30, 40], &[40, 50]];
+        let cost_model = CostModel::default();
+
+        let result = branch_bound_path_with_limits(&notation, shapes, &cost_model, u64::MAX, usize::MAX, None);
+
+        assert!(result.proven_optimal);
+        let optimal = crate::optimization::optimal_path(&notation, shapes, &cost_model);
+        assert_eq!(result.path.total_cost(&cost_model), optimal.total_cost(&cost_model));
+    }
+
+    #[test]
+    fn test_branch_bound_node_budget() {
+        let notation = parse_einsum("ab,bc,cd,de,ef,fg,ga->").unwrap();
+        let shapes: &[&[usize]] = &[&[2, 3], &[3, 5], &[5, 7], &[7, 2], &[2, 3], &[3, 5], &[5, 2]];
+        let cost_model = CostModel::default();
+
+        // A single node leaves the greedy path
+        let result = branch_bound_path_with_limits(&notation, shapes, &cost_model, 1, usize::MAX, None);
+
+        assert!(!result.proven_optimal);
+        assert_eq!(result.nodes_explored, 1);
+        assert_eq!(result.path, greedy_path(&notation, shapes, &cost_model));
+    }
+
+    #[test]
+    fn test_branch_bound_proves_ring_within_default_budget() {
+        let notation = parse_einsum("ab,bc,cd,de,ef,fg,gh,hi,ij,ja->").unwrap();
+        let shapes: &[&[usize]] = &[
+            &[2, 3], &[3, 5], &[5, 7], &[7, 2], &[2, 3],
+            &[3, 5], &[5, 7], &[7, 2], &[2, 3], &[3, 2],
+        ];
+        let cost_model = CostModel::default();
+
+        let result = branch_bound_path_with_limits(
+            &notation, shapes, &cost_model,
+            DEFAULT_MAX_NODES, usize::MAX, None,
+        );
+
+        assert!(result.proven_optimal);
+        let optimal = crate::optimization::optimal_path(&notation, shapes, &cost_model);
+        assert_eq!(result.path.total_cost(&cost_model), optimal.total_cost(&cost_model));
+    }
+
+    #[test]
+    fn test_branch_bound_proves_larger_networks() {
+        use crate::optimization::{connected_optimal_path, optimal_path};
+
+        let letters: Vec<char> = ('a'..='z').collect();
+        let cost_model = CostModel::default();
+        let search = |notation: &EinsumNotation, shapes: &[&[usize]]| {
+            branch_bound_path_with_limits(notation, shapes, &cost_model, DEFAULT_MAX_NODES, usize::MAX, None)
+        };
+
+        // A ring of 12 tensors, the largest `optimal_path` handles
+        let terms: Vec<alloc::string::String> = (0..12).map(|i| [letters[i], letters[(i + 1) % 12]].iter().collect()).collect();
+        let notation = parse_einsum(&alloc::format!("{}->", terms.join(","))).unwrap();
+        let shapes: Vec<Vec<usize>> = (0..12).map(|i| vec![[2, 7, 3, 11][i % 4], [2, 7, 3, 11][(i + 1) % 4]]).collect();
+        let shapes: Vec<&[usize]> = shapes.iter().map(|s| s.as_slice()).collect();
+
+        let result = search(&notation, &shapes);
+        assert!(result.proven_optimal);
+        let optimal = optimal_path(&notation, &shapes, &cost_model);
+        assert_eq!(result.path.total_cost(&cost_model), optimal.total_cost(&cost_model));
+
+        // Matrix-vector chains of up to 20 tensors, past `optimal_path`
+        for n in [12, 16, 20] {
+            let mut terms: Vec<alloc::string::String> = vec![letters[0].into()];
+            terms.extend((1..n).map(|i| [letters[i - 1], letters[i]].iter().collect::<alloc::string::String>()));
+            let notation = parse_einsum(&alloc::format!("{}->{}", terms.join(","), letters[n - 1])).unwrap();
+            let mut shapes = vec![vec![8]];
+            shapes.extend((1..n).map(|_| vec![8, 8]));
+            let shapes: Vec<&[usize]> = shapes.iter().map(|s| s.as_slice()).collect();
+
+            let result = search(&notation, &shapes);
+            assert!(result.proven_optimal, "{} tensors", n);
+            let connected = connected_optimal_path(&notation, &shapes, &cost_model, false);
+            assert_eq!(result.path.total_cost(&cost_model), connected.total_cost(&cost_model));
+        }
+    }
+
+    /// Lowest cost over every linear path contracting the `n` operands.
+    fn exhaustive_cost(
+        notation: &EinsumNotation,
+        shapes: &[&[usize]],
+        cost_model: &CostModel,
+        pairs: &mut Vec<(usize, usize)>,
+        n: usize,
+    ) -> ContractionCost {
+        if n == 1 {
+            return ContractionPath::from_pairs(notation, shapes, pairs, cost_model).unwrap().total_cost(cost_model);
+        }
+        let mut best: Option<ContractionCost> = None;
+        for i in 0..n {
+            for j in i + 1..n {
+                pairs.push((i, j));
+                let cost = exhaustive_cost(notation, shapes, cost_model, pairs, n - 1);
+                pairs.pop();
+                best = Some(best.map_or(cost, |best| best.min(cost)));
+            }
+        }
+        best.unwrap()
+    }
+
+    #[test]
+    fn test_branch_bound_weighted_matches_exhaustive() {
+        // A cheaper prefix with a larger intermediate can win when the rest
+        // of the path creates an even larger one, so states reached at a
+        // lower total are not enough to prune
+        let notation = parse_einsum("ab,bc,cd,de,ef->af").unwrap();
+        let cases: [(&[&[usize]], u64); 2] = [
+            (&[&[90, 11], &[11, 30], &[30, 6], &[6, 4], &[4, 5]], 10),
+            (&[&[5, 13], &[13, 1], &[1, 2], &[2, 70], &[70, 13]], 100),
+        ];
+
+        for (shapes, size) in cases {
+            let objective = crate::optimization::Objective::Weighted { flops: 1, size, write: 0 };
+            let cost_model = CostModel::default().with_objective(objective);
+
+            let result = branch_bound_path_with_limits(&notation, shapes, &cost_model, u64::MAX, usize::MAX, None);
+
+            assert!(result.proven_optimal);
+            let exhaustive = exhaustive_cost(&notation, shapes, &cost_model, &mut Vec::new(), 5);
+            assert_eq!(result.path.total_cost(&cost_model).total, exhaustive.total, "{:?}", shapes);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_branch_bound_time_limit() {
+        let notation = parse_einsum("ab,bc,cd,de,ef,fg,gh,hi,ij,jk,ka->").unwrap();
+        let shapes = vec![vec![3, 3]; 11];
+        let shapes: Vec<&[usize]> = shapes.iter().map(|s| s.as_slice()).collect();
+        let cost_model = CostModel::default();
+
+        let result = branch_bound_path_with_limits(
+            &notation, &shapes, &cost_model,
+            u64::MAX, usize::MAX, Some(Duration::ZERO),
+        );
+
+        assert!(!result.proven_optimal);
+        assert_eq!(result.path.len(), 10);
     }
 }
@@ -1,7 +1,15 @@
 //! Cost model for contraction operations.
 
+use alloc::collections::BTreeSet;
 use alloc::vec::Vec;
 use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+use super::path::split_pair_indices_by;
+
+/// Number of remaining tensors up to which
+/// [`CostModel::optimistic_remaining_cost`] tries every order.
+const EXACT_REMAINING_TENSORS: usize = 4;
 
 /// Cost of a single contraction operation.
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -10,18 +18,37 @@ pub struct ContractionCost {
     pub flops: u64,
     /// Memory traffic in elements.
     pub memory: u64,
+    /// Number of elements written.
+    pub write: u64,
+    /// Number of elements of the largest result.
+    pub size: u64,
     /// Combined cost using the cost model.
     pub total: u64,
+    /// Part of `total` that comes from `size`, which combines by maximum
+    /// instead of sum.
+    size_cost: u64,
 }
 
 impl ContractionCost {
     pub fn new(flops: u64, memory: u64, alpha: u64) -> Self {
         let total = flops.saturating_add(memory.saturating_mul(alpha));
-        Self { flops, memory, total }
+        Self { flops, memory, write: 0, size: 0, total, size_cost: 0 }
     }
 
     pub fn zero() -> Self {
-        Self { flops: 0, memory: 0, total: 0 }
+        Self { flops: 0, memory: 0, write: 0, size: 0, total: 0, size_cost: 0 }
+    }
+
+    /// Returns whether the same contractions cost at least as much after
+    /// `self` as after `other`.
+    ///
+    /// Sizes combine by maximum, so a lower total can still end up costlier
+    /// when the rest of the path creates a larger tensor: the summed and
+    /// size parts must both be at least those of `other`.
+    pub(crate) fn is_dominated_by(&self, other: &Self) -> bool {
+        let additive = |cost: &Self| cost.total.saturating_sub(cost.size_cost);
+
+        *self >= *other && self.size_cost >= other.size_cost && additive(self) >= additive(other)
     }
 }
 
@@ -29,17 +56,30 @@ impl core::ops::Add for ContractionCost {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self {
+        let size_cost = self.size_cost.max(rhs.size_cost);
+        let total = self
+            .total
+            .saturating_sub(self.size_cost)
+            .saturating_add(rhs.total.saturating_sub(rhs.size_cost))
+            .saturating_add(size_cost);
+
         Self {
             flops: self.flops.saturating_add(rhs.flops),
             memory: self.memory.saturating_add(rhs.memory),
-            total: self.total.saturating_add(rhs.total),
+            write: self.write.saturating_add(rhs.write),
+            size: self.size.max(rhs.size),
+            total,
+            size_cost,
         }
     }
 }
 
 impl Ord for ContractionCost {
     fn cmp(&self, other: &Self) -> core::cmp::Ordering {
-        self.total.cmp(&other.total)
+        // FLOPs break ties, which matters for objectives like `Size`
+        self.total
+            .cmp(&other.total)
+            .then(self.flops.cmp(&other.flops))
     }
 }
 
@@ -51,30 +91,120 @@ impl PartialOrd for ContractionCost {
 
 impl Eq for ContractionCost {}
 
+/// What the path optimizers minimize.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Objective {
+    /// FLOPs plus `alpha` times the memory traffic.
+    #[default]
+    Combined,
+    /// Total FLOPs.
+    Flops,
+    /// Size of the largest tensor created.
+    Size,
+    /// Total number of elements written.
+    Write,
+    /// Weighted sum of total FLOPs, largest size and elements written.
+    Weighted { flops: u64, size: u64, write: u64 },
+}
+
+impl Objective {
+    /// Returns whether the objective adds the largest size to FLOPs or
+    /// writes.
+    ///
+    /// The best way to contract a sub-network then depends on the largest
+    /// size elsewhere in the path, so searches keeping the cheapest sub-path
+    /// per sub-network are not exact.
+    pub(crate) fn mixes_size(&self) -> bool {
+        matches!(*self, Objective::Weighted { flops, size, write } if size > 0 && (flops > 0 || write > 0))
+    }
+}
+
 /// Cost model for evaluating contraction operations.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct CostModel {
     /// Memory bandwidth penalty factor.
     /// Higher values penalize memory-bound operations more.
     pub alpha: u64,
+    /// Maximum number of elements of an intermediate tensor.
+    ///
+    /// The optimizers refuse pairs whose result exceeds it, except for the
+    /// final contraction, which produces the output. `None` means no limit.
+    pub memory_limit: Option<u64>,
+    /// What the optimizers minimize.
+    pub objective: Objective,
 }
 
 impl Default for CostModel {
     fn default() -> Self {
         // Default alpha suitable for GPUs where memory bandwidth is expensive
-        Self { alpha: 64 }
+        Self { alpha: 64, memory_limit: None, objective: Objective::Combined }
     }
 }
 
 impl CostModel {
     /// Creates a cost model optimized for GPU execution.
     pub fn gpu() -> Self {
-        Self { alpha: 64 }
+        Self { alpha: 64, memory_limit: None, objective: Objective::Combined }
     }
 
     /// Creates a cost model optimized for CPU execution.
     pub fn cpu() -> Self {
-        Self { alpha: 8 }
+        Self { alpha: 8, memory_limit: None, objective: Objective::Combined }
+    }
+
+    /// Sets what the optimizers minimize.
+    pub fn with_objective(mut self, objective: Objective) -> Self {
+        self.objective = objective;
+        self
+    }
+
+    /// Limits intermediate tensors to `elements` elements.
+    pub fn with_memory_limit(mut self, elements: u64) -> Self {
+        self.memory_limit = Some(elements);
+        self
+    }
+
+    /// Limits intermediate tensors to `bytes` bytes of `element_size`-byte
+    /// elements.
+    pub fn with_memory_limit_bytes(self, bytes: u64, element_size: usize) -> Self {
+        self.with_memory_limit(bytes / element_size.max(1) as u64)
+    }
+
+    /// Returns whether an intermediate of `elements` elements fits the
+    /// memory limit.
+    pub fn fits_memory_limit(&self, elements: u64) -> bool {
+        self.memory_limit.is_none_or(|limit| elements <= limit)
+    }
+
+    /// Builds the cost of a contraction that performs `flops` FLOPs, moves
+    /// `memory` elements and writes a result of `size` elements.
+    pub fn cost(&self, flops: u64, memory: u64, size: u64) -> ContractionCost {
+        self.combine(flops, memory, size, size)
+    }
+
+    /// Builds the cost of contractions that perform `flops` FLOPs in total,
+    /// move `memory` elements, write `write` elements and whose largest
+    /// result has `size` elements.
+    fn combine(&self, flops: u64, memory: u64, write: u64, size: u64) -> ContractionCost {
+        let (additive, size_cost) = match self.objective {
+            Objective::Combined => (flops.saturating_add(memory.saturating_mul(self.alpha)), 0),
+            Objective::Flops => (flops, 0),
+            Objective::Size => (0, size),
+            Objective::Write => (write, 0),
+            Objective::Weighted { flops: wf, size: ws, write: ww } => (
+                flops.saturating_mul(wf).saturating_add(write.saturating_mul(ww)),
+                size.saturating_mul(ws),
+            ),
+        };
+
+        ContractionCost {
+            flops,
+            memory,
+            write,
+            size,
+            total: additive.saturating_add(size_cost),
+            size_cost,
+        }
     }
 
     /// Computes the cost of contracting two tensors.
@@ -93,49 +223,39 @@ impl CostModel {
         indices_b: &[char],
         contracted: &[char],
     ) -> ContractionCost {
-        // Build dimension map
-        let mut dim_map: HashMap<char, usize> = HashMap::new();
-        for (&c, &d) in indices_a.iter().zip(shape_a.iter()) {
-            dim_map.insert(c, d);
-        }
-        for (&c, &d) in indices_b.iter().zip(shape_b.iter()) {
-            dim_map.insert(c, d);
-        }
+        // Size of an index, taken from the second tensor if both hold it.
+        // Called for a handful of indices per pair, so no map is built.
+        let dim_of = |c: &char| -> Option<u64> {
+            indices_b
+                .iter()
+                .position(|x| x == c)
+                .map(|pos| shape_b[pos])
+                .or_else(|| indices_a.iter().position(|x| x == c).map(|pos| shape_a[pos]))
+                .map(|d| d as u64)
+        };
 
         // Output indices: union minus contracted
-        let contracted_set: hashbrown::HashSet<char> = contracted.iter().copied().collect();
-
-        // Compute output size
         let mut output_size: u64 = 1;
-        for &c in indices_a.iter().chain(indices_b.iter()) {
-            if !contracted_set.contains(&c) {
-                if let Some(&d) = dim_map.get(&c) {
-                    // Only count each index once
-                    output_size = output_size.saturating_mul(d as u64);
-                }
+        for c in indices_a.iter().chain(indices_b.iter()) {
+            if !contracted.contains(c)
+                && let Some(d) = dim_of(c)
+            {
+                output_size = output_size.saturating_mul(d);
             }
         }
-        // Deduplicate: divide by indices that appear in both
-        let indices_a_set: hashbrown::HashSet<char> = indices_a.iter().copied().collect();
-        let indices_b_set: hashbrown::HashSet<char> = indices_b.iter().copied().collect();
-        let common_non_contracted: Vec<char> = indices_a_set
-            .intersection(&indices_b_set)
-            .filter(|&c| !contracted_set.contains(c))
-            .copied()
-            .collect();
-        for &c in &common_non_contracted {
-            if let Some(&d) = dim_map.get(&c) {
-                // These were counted twice, divide once
-                output_size /= d as u64;
+        // Indices in both tensors were counted twice, divide once
+        for (pos, c) in indices_a.iter().enumerate() {
+            if !indices_a[..pos].contains(c)
+                && indices_b.contains(c)
+                && !contracted.contains(c)
+                && let Some(d) = dim_of(c)
+            {
+                output_size /= d;
             }
         }
 
         // Compute contracted size
-        let contracted_size: u64 = contracted
-            .iter()
-            .filter_map(|c| dim_map.get(c))
-            .map(|&d| d as u64)
-            .product();
+        let contracted_size: u64 = contracted.iter().filter_map(dim_of).product();
 
         // FLOPs = output_size * contracted_size * 2
         let flops = output_size.saturating_mul(contracted_size).saturating_mul(2);
@@ -145,7 +265,7 @@ impl CostModel {
         let input_b_size: u64 = shape_b.iter().map(|&d| d as u64).product();
         let memory = input_a_size + input_b_size + output_size;
 
-        ContractionCost::new(flops, memory, self.alpha)
+        self.cost(flops, memory, output_size)
     }
 
     /// Computes the cost of contracting multiple tensors into one.
@@ -195,33 +315,192 @@ impl CostModel {
             .sum();
         let memory = input_size + output_size;
 
-        ContractionCost::new(flops, memory, self.alpha)
+        self.cost(flops, memory, output_size)
     }
 
     /// Estimates the remaining cost of contracting a set of tensors.
     /// Used as lower bound in branch-and-bound.
+    ///
+    /// The estimate never exceeds the cost of any order of contractions
+    /// producing `output_indices`. It is the largest of three bounds on the
+    /// FLOPs:
+    /// - every tensor is an operand once, and a pair costs at least twice
+    ///   its larger operand;
+    /// - eliminating a contracted index costs at least its size times the
+    ///   sizes of the indices that are still alive at that point: output
+    ///   indices of the tensors holding it, and indices shared by all of them;
+    /// - every contracted index is summed in some step, and the last step
+    ///   writes the output.
+    ///
+    /// With few tensors left, every order is tried instead, and the lowest
+    /// FLOPs, memory, writes and size of any order are combined.
     pub fn optimistic_remaining_cost(
         &self,
         shapes: &[Vec<usize>],
-        _indices: &[Vec<char>],
+        indices: &[Vec<char>],
+        output_indices: &BTreeSet<char>,
     ) -> ContractionCost {
-        if shapes.len() <= 1 {
+        let n = shapes.len();
+        if n <= 1 {
             return ContractionCost::zero();
         }
 
-        // Lower bound: assume all remaining contractions can be done optimally
-        // This is an underestimate, which is what we want for branch-and-bound
-        let total_elements: u64 = shapes
+        if n <= EXACT_REMAINING_TENSORS {
+            return self.exact_remaining_cost(shapes, indices, output_indices);
+        }
+
+        // Size and holders of every distinct index
+        let mut dims: HashMap<char, (u64, Vec<usize>)> = HashMap::new();
+        for (t, (shape, tensor_indices)) in shapes.iter().zip(indices).enumerate() {
+            for (&c, &d) in tensor_indices.iter().zip(shape) {
+                let (_, holders) = dims.entry(c).or_insert((d as u64, Vec::new()));
+                if holders.last() != Some(&t) {
+                    holders.push(t);
+                }
+            }
+        }
+        let space = |chars: &mut dyn Iterator<Item = char>| -> u64 {
+            let mut seen: Vec<char> = Vec::new();
+            chars.fold(1u64, |acc, c| {
+                if seen.contains(&c) {
+                    return acc;
+                }
+                seen.push(c);
+                acc.saturating_mul(dims.get(&c).map_or(1, |&(d, _)| d))
+            })
+        };
+
+        let output_size = space(&mut output_indices.iter().copied().filter(|c| dims.contains_key(c)));
+
+        // Pair the tensors by decreasing size
+        let mut sizes: Vec<u64> = indices.iter().map(|t| space(&mut t.iter().copied())).collect();
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        let pairing = sizes.iter().step_by(2).fold(0u64, |acc, &s| acc.saturating_add(s));
+
+        let mut elimination = 0u64;
+        let mut summed = 0u64;
+        for (&c, (d, holders)) in &dims {
+            if output_indices.contains(&c) {
+                continue;
+            }
+            if *d >= 2 {
+                summed = summed.saturating_add(*d);
+            }
+
+            let mut alive = holders.iter().flat_map(|&t| indices[t].iter().copied()).filter(|e| {
+                *e == c
+                    || holders.len() == 1
+                    || output_indices.contains(e)
+                    || dims.get(e).is_some_and(|(_, others)| holders.iter().all(|t| others.contains(t)))
+            });
+            elimination = elimination.max(space(&mut alive));
+        }
+
+        let flops = pairing
+            .max(elimination)
+            .max(summed.saturating_add(output_size).saturating_sub(1))
+            .saturating_mul(2);
+
+        // Every tensor is read once and the output written; the other
+        // results have at least one element and are written and read
+        let input_elements: u64 = shapes
             .iter()
-            .map(|s| s.iter().map(|&d| d as u64).product::<u64>())
-            .sum();
+            .map(|s| s.iter().fold(1u64, |acc, &d| acc.saturating_mul(d as u64)))
+            .fold(0u64, |acc, s| acc.saturating_add(s));
+        let memory = input_elements
+            .saturating_add(output_size)
+            .saturating_add(2 * (n as u64 - 2));
 
-        // Minimum FLOPs: at least need to touch all elements
-        let flops = total_elements;
-        let memory = total_elements;
+        self.cost(flops, memory, output_size)
+    }
 
-        ContractionCost::new(flops, memory, self.alpha)
+    /// Lower bound on the cost of contracting a few tensors, found by trying
+    /// every order. The memory limit is ignored, which can only lower the
+    /// cost.
+    ///
+    /// FLOPs, memory, writes and the largest size are minimized separately:
+    /// with the `Size` and `Weighted` objectives, the cheapest order need
+    /// not have the fewest FLOPs, which break ties between equal totals.
+    fn exact_remaining_cost(
+        &self,
+        shapes: &[Vec<usize>],
+        indices: &[Vec<char>],
+        output_indices: &BTreeSet<char>,
+    ) -> ContractionCost {
+        let [flops, memory, write, size] = self.remaining_minima(shapes, indices, output_indices);
+        self.combine(flops, memory, write, size)
     }
+
+    /// Lowest FLOPs, memory, writes and largest size over every order
+    /// contracting the tensors, each minimized on its own.
+    fn remaining_minima(
+        &self,
+        shapes: &[Vec<usize>],
+        indices: &[Vec<char>],
+        output_indices: &BTreeSet<char>,
+    ) -> [u64; 4] {
+        let n = shapes.len();
+        let mut best: Option<[u64; 4]> = None;
+
+        for i in 0..n {
+            for j in (i + 1)..n {
+                let (contracted, result) = split_pair_indices_by(&indices[i], &indices[j], |c| {
+                    output_indices.contains(&c)
+                        || indices.iter().enumerate().any(|(k, other)| k != i && k != j && other.contains(&c))
+                });
+                let step = self.compute_pairwise_cost(&shapes[i], &shapes[j], &indices[i], &indices[j], &contracted);
+                let mut cost = [step.flops, step.memory, step.write, step.size];
+                // Later steps only add to every component
+                if best.is_some_and(|best| cost.iter().zip(&best).all(|(c, b)| c >= b)) {
+                    continue;
+                }
+
+                if n > 2 {
+                    let result_shape = pair_shape(&shapes[i], &indices[i], &shapes[j], &indices[j], &result);
+                    let others = (0..n).filter(|&k| k != i && k != j);
+                    let mut rest_shapes: Vec<Vec<usize>> = others.clone().map(|k| shapes[k].clone()).collect();
+                    let mut rest_indices: Vec<Vec<char>> = others.map(|k| indices[k].clone()).collect();
+                    rest_shapes.push(result_shape);
+                    rest_indices.push(result);
+                    let rest = self.remaining_minima(&rest_shapes, &rest_indices, output_indices);
+                    cost = [
+                        cost[0].saturating_add(rest[0]),
+                        cost[1].saturating_add(rest[1]),
+                        cost[2].saturating_add(rest[2]),
+                        cost[3].max(rest[3]),
+                    ];
+                }
+
+                best = Some(match best {
+                    Some(best) => core::array::from_fn(|k| best[k].min(cost[k])),
+                    None => cost,
+                });
+            }
+        }
+
+        best.unwrap_or_default()
+    }
+}
+
+/// Shape of the result of a pairwise contraction.
+fn pair_shape(
+    shape_a: &[usize],
+    indices_a: &[char],
+    shape_b: &[usize],
+    indices_b: &[char],
+    result: &[char],
+) -> Vec<usize> {
+    result
+        .iter()
+        .map(|c| {
+            indices_a
+                .iter()
+                .position(|x| x == c)
+                .map(|pos| shape_a[pos])
+                .or_else(|| indices_b.iter().position(|x| x == c).map(|pos| shape_b[pos]))
+                .unwrap_or(1)
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -245,6 +524,109 @@ mod tests {
         assert_eq!(cost.flops, 12_000_000);
     }
 
+    #[test]
+    fn test_memory_limit() {
+        assert!(CostModel::default().fits_memory_limit(u64::MAX));
+
+        let model = CostModel::default().with_memory_limit(100);
+        assert!(model.fits_memory_limit(100));
+        assert!(!model.fits_memory_limit(101));
+
+        let model = CostModel::default().with_memory_limit_bytes(1024, 4);
+        assert_eq!(model.memory_limit, Some(256));
+    }
+
+    #[test]
+    fn test_objectives() {
+        let cost = |objective| CostModel::default().with_objective(objective).cost(1000, 300, 100).total;
+
+        assert_eq!(cost(Objective::Combined), 1000 + 64 * 300);
+        assert_eq!(cost(Objective::Flops), 1000);
+        assert_eq!(cost(Objective::Size), 100);
+        assert_eq!(cost(Objective::Write), 100);
+        assert_eq!(cost(Objective::Weighted { flops: 1, size: 2, write: 3 }), 1000 + 200 + 300);
+    }
+
+    #[test]
+    fn test_size_combines_by_maximum() {
+        let model = CostModel::default().with_objective(Objective::Weighted { flops: 1, size: 10, write: 0 });
+        let a = model.cost(100, 0, 50);
+        let b = model.cost(200, 0, 20);
+
+        let sum = a + b;
+        assert_eq!(sum.flops, 300);
+        assert_eq!(sum.size, 50);
+        assert_eq!(sum.write, 70);
+        assert_eq!(sum.total, 300 + 500);
+        assert_eq!((sum + ContractionCost::zero()).total, sum.total);
+    }
+
+    #[test]
+    fn test_ties_broken_by_flops() {
+        let model = CostModel::default().with_objective(Objective::Size);
+
+        assert!(model.cost(100, 0, 10) < model.cost(200, 0, 10));
+    }
+
+    #[test]
+    fn test_remaining_cost_of_last_pair() {
+        let model = CostModel::default();
+        let shapes = [vec![10, 20], vec![20, 30]];
+        let indices = [vec!['i', 'j'], vec!['j', 'k']];
+        let output: BTreeSet<char> = ['i', 'k'].into_iter().collect();
+
+        let bound = model.optimistic_remaining_cost(&shapes, &indices, &output);
+
+        assert_eq!(bound, model.compute_pairwise_cost(&shapes[0], &shapes[1], &indices[0], &indices[1], &['j']));
+    }
+
+    #[test]
+    fn test_remaining_cost_is_a_lower_bound() {
+        use crate::notation::parse_einsum;
+        use crate::optimization::optimal_path;
+
+        let cases: [(&str, &[&[usize]]); 6] = [
+            ("ij,jk,kl,lm,mn->in", &[&[10, 20], &[20, 30], &[30, 40], &[40, 50], &[50, 6]]),
+            ("ab,bc,cd,de,ea->", &[&[2, 3], &[3, 5], &[5, 7], &[7, 4], &[4, 2]]),
+            ("abc,cd,de,eb,af->f", &[&[4, 3, 2], &[2, 5], &[5, 6], &[6, 3], &[4, 7]]),
+            ("ij,ik,il,im,in,io->", &[&[2, 3], &[2, 4], &[2, 5], &[2, 6], &[2, 7], &[2, 8]]),
+            // Few enough tensors to try every order
+            ("bdfg,ae,g,bce->d", &[&[4, 2, 4, 5], &[3, 3], &[5], &[4, 6, 3]]),
+            ("ab,bc,cd,da->", &[&[2, 9], &[9, 3], &[3, 8], &[8, 2]]),
+        ];
+
+        let weighted = Objective::Weighted { flops: 1, size: 64, write: 8 };
+        for objective in [Objective::Combined, Objective::Flops, Objective::Size, Objective::Write, weighted] {
+            let model = CostModel::default().with_objective(objective);
+            for (expression, shapes) in cases {
+                let notation = parse_einsum(expression).unwrap();
+                let output: BTreeSet<char> = notation.output().named_indices().collect();
+                let indices: Vec<Vec<char>> = notation.inputs().iter().map(|s| s.named_indices().collect()).collect();
+                let owned: Vec<Vec<usize>> = shapes.iter().map(|s| s.to_vec()).collect();
+
+                let bound = model.optimistic_remaining_cost(&owned, &indices, &output);
+                let optimal = optimal_path(&notation, shapes, &model).total_cost(&model);
+
+                assert!(bound <= optimal, "{} {:?}: {:?} > {:?}", expression, objective, bound, optimal);
+            }
+        }
+    }
+
+    #[test]
+    fn test_remaining_cost_uses_index_structure() {
+        // Summing the 40x50 matrix away costs far more than reading it
+        let model = CostModel::default().with_objective(Objective::Flops);
+        let shapes = [vec![10, 20], vec![20, 30], vec![30, 40], vec![40, 50], vec![50, 6]];
+        let indices = [vec!['i', 'j'], vec!['j', 'k'], vec!['k', 'l'], vec!['l', 'm'], vec!['m', 'n']];
+        let output: BTreeSet<char> = ['i', 'n'].into_iter().collect();
+
+        let bound = model.optimistic_remaining_cost(&shapes, &indices, &output);
+
+        let elements: u64 = shapes.iter().map(|s| s.iter().product::<usize>() as u64).sum();
+        assert!(bound.flops > elements, "{} <= {}", bound.flops, elements);
+        assert_eq!(bound.size, 10 * 6);
+    }
+
     #[test]
     fn test_cost_ordering() {
         let cheap = ContractionCost::new(100, 10, 64);
@@ -0,0 +1,177 @@
+//! Graphviz export of contraction trees.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use super::path::ContractionPath;
+use crate::error::EinsumResult;
+use crate::notation::validation::expanded_dims;
+use crate::notation::EinsumNotation;
+
+impl ContractionPath {
+    /// Renders the path as a Graphviz DOT contraction tree.
+    ///
+    /// Leaves are the input operands; every step adds a node with its
+    /// indices, shape, summed indices, FLOPs and memory traffic (elements
+    /// read and written). The largest intermediate is filled and the root,
+    /// which holds the result, has a double border.
+    ///
+    /// `notation` and `shapes` must be the ones the path was created for.
+    /// Returns an error if the shapes are not valid for the notation.
+    pub fn to_dot(&self, notation: &EinsumNotation, shapes: &[&[usize]]) -> EinsumResult<String> {
+        let (expanded, dims) = expanded_dims(notation, shapes)?;
+        let shape_of = |indices: &[char]| -> Vec<usize> {
+            indices
+                .iter()
+                .map(|c| dims.get(c).copied().unwrap_or(1))
+                .collect()
+        };
+        let size = |indices: &[char]| -> u64 { shape_of(indices).iter().map(|&d| d as u64).product() };
+
+        let largest = self
+            .steps()
+            .iter()
+            .enumerate()
+            .rev()
+            .skip(1)
+            .max_by_key(|(_, step)| size(&step.result_indices))
+            .map(|(idx, _)| idx);
+
+        let mut dot = String::new();
+        dot.push_str("digraph contraction {\n");
+        dot.push_str("    rankdir=BT;\n");
+        dot.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+        let _ = writeln!(dot, "    label=\"{}\";", escape(&format!("{}", notation)));
+
+        // (node name, indices) of the tensors alive, in executor order
+        let mut tracked: Vec<(String, Vec<char>)> = Vec::with_capacity(expanded.num_inputs());
+        for (idx, input) in expanded.inputs().iter().enumerate() {
+            let indices: Vec<char> = input.named_indices().collect();
+            let _ = writeln!(
+                dot,
+                "    in{} [shape=ellipse, label=\"{}: {}\\n{:?}\"];",
+                idx,
+                idx,
+                escape(&String::from_iter(&indices)),
+                shape_of(&indices),
+            );
+            tracked.push((format!("in{}", idx), indices));
+        }
+
+        for (idx, step) in self.steps().iter().enumerate() {
+            let (i, j) = step.inputs;
+            if i >= tracked.len() || j >= tracked.len() {
+                break;
+            }
+
+            let result = &step.result_indices;
+            let memory = size(&tracked[i].1) + size(&tracked[j].1) + size(result);
+            let mut attributes = String::new();
+            if Some(idx) == largest {
+                attributes.push_str(", style=filled, fillcolor=\"#f4cccc\"");
+            }
+            if idx + 1 == self.len() {
+                attributes.push_str(", peripheries=2");
+            }
+
+            let _ = writeln!(
+                dot,
+                "    step{} [label=\"{}\\n{:?}\\nsum: {}\\nflops: {}\\nmemory: {}\"{}];",
+                idx,
+                escape(&String::from_iter(result)),
+                shape_of(result),
+                escape(&String::from_iter(&step.contracted_indices)),
+                step.estimated_flops,
+                memory,
+                attributes,
+            );
+            let _ = writeln!(dot, "    {} -> step{};", tracked[i].0, idx);
+            let _ = writeln!(dot, "    {} -> step{};", tracked[j].0, idx);
+
+            let (min_idx, max_idx) = if i < j { (i, j) } else { (j, i) };
+            tracked.remove(max_idx);
+            tracked.remove(min_idx);
+            tracked.push((format!("step{}", idx), result.clone()));
+        }
+
+        dot.push_str("}\n");
+        Ok(dot)
+    }
+}
+
+/// Escapes a string for use inside a quoted DOT label.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if c == '"' || c == '\\' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_einsum;
+    use crate::optimization::{greedy_path, ContractionStep, CostModel};
+
+    #[test]
+    fn test_chain_tree() {
+        let notation = parse_einsum("ij,jk,kl->il").unwrap();
+        let shapes: &[&[usize]] = &[&[2, 30], &[30, 40], &[40, 5]];
+        let path = greedy_path(&notation, shapes, &CostModel::default());
+
+        let dot = path.to_dot(&notation, shapes).unwrap();
+
+        assert!(dot.starts_with("digraph contraction {"));
+        assert!(dot.contains("in0 [shape=ellipse, label=\"0: ij\\n[2, 30]\"]"));
+        assert!(dot.contains("in2 [shape=ellipse, label=\"2: kl\\n[40, 5]\"]"));
+        // Two steps, each with two incoming edges
+        assert_eq!(dot.matches(" -> step").count(), 4);
+        // The path keeps the index order of the operands
+        assert!(dot.contains("step1 [label=\"li\\n[5, 2]\\nsum: k\\nflops: 800"));
+        assert!(dot.contains("peripheries=2"));
+        assert_eq!(dot.matches("fillcolor").count(), 1);
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[test]
+    fn test_steps_refer_to_current_tensors() {
+        let notation = parse_einsum("ij,jk,kl->il").unwrap();
+        let shapes: &[&[usize]] = &[&[2, 3], &[3, 4], &[4, 5]];
+
+        // Contract the last two operands, then the first with the result
+        let mut path = ContractionPath::new();
+        path.push(ContractionStep::new((1, 2), alloc::vec!['k'], alloc::vec!['j', 'l'], 0));
+        path.push(ContractionStep::new((0, 1), alloc::vec!['j'], alloc::vec!['i', 'l'], 0));
+
+        let dot = path.to_dot(&notation, shapes).unwrap();
+
+        assert!(dot.contains("in1 -> step0;"));
+        assert!(dot.contains("in2 -> step0;"));
+        assert!(dot.contains("in0 -> step1;"));
+        assert!(dot.contains("step0 -> step1;"));
+    }
+
+    #[test]
+    fn test_ellipsis_shapes() {
+        let notation = parse_einsum("...ij,...jk,...kl->...il").unwrap();
+        let shapes: &[&[usize]] = &[&[7, 2, 3], &[7, 3, 4], &[7, 4, 5]];
+        let expanded = notation.expand_ellipsis(1);
+        let path = greedy_path(&expanded, shapes, &CostModel::default());
+
+        let dot = path.to_dot(&notation, shapes).unwrap();
+
+        assert!(dot.contains("label=\"0: \u{2460}ij\\n[7, 2, 3]\""));
+        assert!(dot.contains("label=\"...ij,...jk,...kl->...il\";"));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a\"b\\c"), "a\\\"b\\\\c");
+    }
+}
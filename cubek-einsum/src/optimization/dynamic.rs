@@ -2,25 +2,46 @@
 //!
 //! Finds the globally optimal contraction order by trying all possible
 //! bipartitions. Exponential in the number of tensors, but optimal.
+//! [`connected_optimal_path`] only tries bipartitions into connected
+//! sub-networks (DPccp), which keeps sparse networks of a few dozen tensors
+//! tractable.
 
+use alloc::vec;
 use alloc::vec::Vec;
 use alloc::collections::BTreeSet;
 use hashbrown::HashMap;
 
 use super::cost::{CostModel, ContractionCost};
-use super::path::{ContractionPath, ContractionStep};
+use super::greedy::greedy_path;
+use super::path::{ContractionPath, ContractionStep, split_pair_indices};
 use crate::notation::EinsumNotation;
 
 /// Maximum number of tensors for which DP is feasible.
 /// For n tensors, we have 2^n subsets to consider.
 pub const MAX_DP_TENSORS: usize = 12;
 
+/// Maximum number of tensors for the connected DP, whose subsets are `u64`
+/// masks. How far below this it stays feasible depends on how densely the
+/// tensors are connected.
+pub const MAX_CONNECTED_DP_TENSORS: usize = 64;
+
+/// Number of sub-network pairs the connected DP enumerates before giving up.
+pub(super) const MAX_CONNECTED_DP_PAIRS: usize = 1 << 23;
+
 /// Finds the optimal contraction path using dynamic programming.
 ///
 /// Uses memoization over all subsets of tensors to find the globally
 /// optimal contraction order. Time complexity: O(3^n) where n is
 /// the number of input tensors.
 ///
+/// Sub-networks whose result exceeds the cost model's memory limit are
+/// never formed. If no path fits the limit, the greedy path is returned.
+///
+/// The path is optimal for the `Combined`, `Flops`, `Write` and `Size`
+/// objectives. Only the cheapest path of each subset is kept, so a
+/// `Weighted` objective that adds the largest size to FLOPs or writes may
+/// get a costlier path.
+///
 /// # Arguments
 /// * `notation` - The einsum notation
 /// * `shapes` - Shapes of input tensors
@@ -69,9 +90,19 @@ pub fn optimal_path(
         result_cache.insert(subset, (tensor_shapes[i].clone(), tensor_indices[i].clone()));
     }
 
+    let full_subset = (1u32 << n) - 1;
+
     // DP over subset sizes
     for size in 2..=n {
         for subset in subsets_of_size(n, size) {
+            // Indices still needed by the output or by tensors outside the subset
+            let mut keep = output_set.clone();
+            for (i, indices) in tensor_indices.iter().enumerate() {
+                if subset & (1 << i) == 0 {
+                    keep.extend(indices.iter().copied());
+                }
+            }
+
             let mut best_cost = ContractionCost::new(u64::MAX, u64::MAX, 1);
             let mut best_path = Vec::new();
             let mut best_result: Option<(Vec<usize>, Vec<char>)> = None;
@@ -87,20 +118,23 @@ pub fn optimal_path(
                     continue;
                 }
 
-                let (left_cost, left_path) = memo.get(&left).unwrap();
-                let (right_cost, right_path) = memo.get(&right).unwrap();
+                // Sub-networks over the memory limit have no entry
+                let (Some((left_cost, left_path)), Some((right_cost, right_path))) =
+                    (memo.get(&left), memo.get(&right))
+                else {
+                    continue;
+                };
 
                 let (left_shape, left_indices) = result_cache.get(&left).unwrap();
                 let (right_shape, right_indices) = result_cache.get(&right).unwrap();
 
                 // Compute contraction cost and result
-                let (contract_cost, result_shape, result_indices) = compute_contraction(
+                let (contract_cost, result_shape, result_indices, _) = compute_contraction(
                     left_shape,
                     left_indices,
                     right_shape,
                     right_indices,
-                    &output_set,
-                    subset == (1u32 << n) - 1, // is_final
+                    &keep,
                     cost_model,
                 );
 
@@ -127,65 +161,357 @@ pub fn optimal_path(
                 }
             }
 
-            memo.insert(subset, (best_cost, best_path));
-            if let Some(result) = best_result {
-                result_cache.insert(subset, result);
+            // The result of a subset does not depend on the split; the full
+            // set produces the output, which is not limited
+            if let Some((result_shape, result_indices)) = best_result {
+                let elements = result_shape.iter().fold(1u64, |acc, &d| acc.saturating_mul(d as u64));
+                if subset == full_subset || cost_model.fits_memory_limit(elements) {
+                    memo.insert(subset, (best_cost, best_path));
+                    result_cache.insert(subset, (result_shape, result_indices));
+                }
             }
         }
     }
 
     // Extract final path
-    let full_subset = (1u32 << n) - 1;
-    let (_, pair_path) = memo.get(&full_subset).unwrap();
+    let Some((_, pair_path)) = memo.get(&full_subset) else {
+        return greedy_path(notation, shapes, cost_model);
+    };
 
     // Convert pair path to ContractionPath
-    build_contraction_path(pair_path, &tensor_indices, &output_set)
+    build_contraction_path(pair_path, &tensor_shapes, &tensor_indices, &output_set, cost_model)
 }
 
-/// Computes the contraction of two tensor results.
-fn compute_contraction(
-    shape_a: &[usize],
-    indices_a: &[char],
-    shape_b: &[usize],
-    indices_b: &[char],
-    output_set: &BTreeSet<char>,
-    is_final: bool,
+/// Finds the optimal contraction path among those that only contract
+/// connected sub-networks, with DPccp.
+///
+/// Tensors sharing an index are connected. Instead of every bipartition of
+/// every subset, only pairs of connected, disjoint sub-networks that share
+/// an index are enumerated, so chains, rings, trees and grids of 15 to 25
+/// tensors are solved exactly. With `outer_products`, any two sub-networks
+/// may be contracted, which makes the search as exhaustive, and as
+/// expensive, as [`optimal_path`]. Without, disconnected parts of the
+/// network are solved separately and joined at the end, smallest first.
+///
+/// Sub-networks whose result exceeds the cost model's memory limit are
+/// never formed. If no path fits the limit, or the network is too densely
+/// connected to enumerate its sub-networks, the greedy path is returned.
+/// Like [`optimal_path`], it is not exact for `Weighted` objectives that
+/// add the largest size to FLOPs or writes.
+///
+/// # Panics
+/// Panics if the number of tensors exceeds MAX_CONNECTED_DP_TENSORS.
+pub fn connected_optimal_path(
+    notation: &EinsumNotation,
+    shapes: &[&[usize]],
     cost_model: &CostModel,
-) -> (ContractionCost, Vec<usize>, Vec<char>) {
-    let indices_a_set: BTreeSet<char> = indices_a.iter().copied().collect();
-    let indices_b_set: BTreeSet<char> = indices_b.iter().copied().collect();
-
-    // Common indices
-    let common: BTreeSet<char> = indices_a_set.intersection(&indices_b_set).copied().collect();
-
-    // For intermediate results, contract common indices not in final output
-    // For final result, contract all common indices not in output
-    let contracted: Vec<char> = if is_final {
-        common.iter().filter(|c| !output_set.contains(c)).copied().collect()
-    } else {
-        // For intermediate, we might want to keep some indices
-        // Actually, for pairwise contraction, we contract common indices
-        common.iter().filter(|c| !output_set.contains(c)).copied().collect()
-    };
+    outer_products: bool,
+) -> ContractionPath {
+    connected_dp(notation, shapes, cost_model, outer_products, MAX_CONNECTED_DP_PAIRS)
+        .unwrap_or_else(|| greedy_path(notation, shapes, cost_model))
+}
+
+/// Runs the connected DP, giving up after `max_pairs` sub-network pairs.
+///
+/// Returns `None` if it gives up or no path fits the memory limit.
+pub(super) fn connected_dp(
+    notation: &EinsumNotation,
+    shapes: &[&[usize]],
+    cost_model: &CostModel,
+    outer_products: bool,
+    max_pairs: usize,
+) -> Option<ContractionPath> {
+    let n = notation.num_inputs();
+
+    if n == 0 || n == 1 {
+        return Some(ContractionPath::new());
+    }
+
+    assert!(
+        n <= MAX_CONNECTED_DP_TENSORS,
+        "connected DP is only feasible for {} tensors or fewer, got {}",
+        MAX_CONNECTED_DP_TENSORS,
+        n
+    );
+
+    let tensor_shapes: Vec<Vec<usize>> = shapes.iter().map(|s| s.to_vec()).collect();
+    let tensor_indices: Vec<Vec<char>> = notation
+        .inputs()
+        .iter()
+        .map(|s| s.named_indices().collect())
+        .collect();
+    let output_set: BTreeSet<char> = notation.output().named_indices().collect();
+
+    let mut dp = ConnectedDp::new(&tensor_shapes, &tensor_indices, &output_set, cost_model, outer_products, max_pairs);
+    dp.solve()?;
+
+    if !dp.plans.contains_key(&dp.full) {
+        dp.join_components();
+    }
+    dp.plans.get(&dp.full)?;
+
+    let mut pair_path = Vec::with_capacity(n - 1);
+    dp.collect_pairs(dp.full, &mut pair_path);
+    Some(build_contraction_path(&pair_path, &tensor_shapes, &tensor_indices, &output_set, cost_model))
+}
+
+/// Best contraction of a connected sub-network.
+struct SubsetPlan {
+    cost: ContractionCost,
+    /// Tensors of the left operand of the last contraction, 0 for a leaf.
+    split: u64,
+    /// Number of elements of the result.
+    size: u64,
+}
+
+/// State of the connected DP over `u64` tensor masks.
+struct ConnectedDp<'a> {
+    full: u64,
+    /// Tensors connected to each tensor.
+    neighbors: Vec<u64>,
+    /// Each index with the tensors holding it, its size and whether the
+    /// output keeps it.
+    index_info: Vec<(char, u64, usize, bool)>,
+    cost_model: &'a CostModel,
+    plans: HashMap<u64, SubsetPlan>,
+    /// Pairs that may still be evaluated before giving up.
+    pairs_left: usize,
+}
+
+impl<'a> ConnectedDp<'a> {
+    fn new(
+        shapes: &[Vec<usize>],
+        indices: &[Vec<char>],
+        output_set: &BTreeSet<char>,
+        cost_model: &'a CostModel,
+        outer_products: bool,
+        max_pairs: usize,
+    ) -> Self {
+        let n = indices.len();
+        let full = u64::MAX >> (64 - n);
+
+        let mut index_info: Vec<(char, u64, usize, bool)> = Vec::new();
+        for (t, (tensor_indices, shape)) in indices.iter().zip(shapes).enumerate() {
+            for (&c, &d) in tensor_indices.iter().zip(shape.iter()) {
+                match index_info.iter_mut().find(|info| info.0 == c) {
+                    Some(info) => info.1 |= 1 << t,
+                    None => index_info.push((c, 1 << t, d, output_set.contains(&c))),
+                }
+            }
+        }
+
+        let mut neighbors = vec![0u64; n];
+        for (t, mask) in neighbors.iter_mut().enumerate() {
+            *mask = if outer_products {
+                full
+            } else {
+                index_info
+                    .iter()
+                    .filter(|info| info.1 & (1 << t) != 0)
+                    .fold(0, |acc, info| acc | info.1)
+            } & !(1 << t);
+        }
+
+        let mut plans = HashMap::new();
+        for (t, shape) in shapes.iter().enumerate() {
+            plans.insert(1u64 << t, SubsetPlan {
+                cost: ContractionCost::zero(),
+                split: 0,
+                size: shape.iter().map(|&d| d as u64).product(),
+            });
+        }
+
+        Self { full, neighbors, index_info, cost_model, plans, pairs_left: max_pairs }
+    }
+
+    /// Tensors outside `set` connected to a tensor of `set`.
+    fn neighborhood(&self, set: u64) -> u64 {
+        bits(set).fold(0, |acc, t| acc | self.neighbors[t]) & !set
+    }
+
+    /// Evaluates every unordered pair of disjoint connected sub-networks
+    /// whose union is connected, or gives up when `pairs_left` runs out.
+    ///
+    /// This is DPccp (Moerkotte and Neumann, 2006): each connected set is
+    /// grown from its smallest tensor only by neighbors, so it is produced
+    /// exactly once, and the pairs forming a set come after those forming
+    /// its halves.
+    fn solve(&mut self) -> Option<()> {
+        for t in (0..self.neighbors.len()).rev() {
+            let start = 1u64 << t;
+            self.emit_csg(start)?;
+            self.enumerate_csg(start, lower_bits(t))?;
+        }
+        Some(())
+    }
 
-    let contracted_set: BTreeSet<char> = contracted.iter().copied().collect();
+    fn enumerate_csg(&mut self, set: u64, excluded: u64) -> Option<()> {
+        let neighborhood = self.neighborhood(set) & !excluded;
+        for grown in subsets(neighborhood) {
+            self.emit_csg(set | grown)?;
+        }
+        for grown in subsets(neighborhood) {
+            self.enumerate_csg(set | grown, excluded | neighborhood)?;
+        }
+        Some(())
+    }
+
+    /// Evaluates the pairs of `set` with the connected complements grown
+    /// from its neighbors above its smallest tensor.
+    fn emit_csg(&mut self, set: u64) -> Option<()> {
+        let excluded = set | lower_bits(set.trailing_zeros() as usize);
+        let neighborhood = self.neighborhood(set) & !excluded;
+        for t in bits(neighborhood).collect::<Vec<_>>().into_iter().rev() {
+            let other = 1u64 << t;
+            self.emit_pair(set, other)?;
+            self.enumerate_cmp(set, other, excluded | (lower_bits(t) & neighborhood))?;
+        }
+        Some(())
+    }
+
+    fn enumerate_cmp(&mut self, set: u64, other: u64, excluded: u64) -> Option<()> {
+        let neighborhood = self.neighborhood(other) & !excluded;
+        for grown in subsets(neighborhood) {
+            self.emit_pair(set, other | grown)?;
+        }
+        for grown in subsets(neighborhood) {
+            self.enumerate_cmp(set, other | grown, excluded | neighborhood)?;
+        }
+        Some(())
+    }
+
+    fn emit_pair(&mut self, left: u64, right: u64) -> Option<()> {
+        self.pairs_left = self.pairs_left.checked_sub(1)?;
+        self.join(left, right);
+        Some(())
+    }
+
+    /// Records the contraction of two solved sub-networks if it is the best
+    /// way found so far to form their union.
+    ///
+    /// Costs are those of [`CostModel::compute_pairwise_cost`], computed
+    /// from the masks of the tensors holding each index.
+    fn join(&mut self, left: u64, right: u64) {
+        let (Some(l), Some(r)) = (self.plans.get(&left), self.plans.get(&right)) else {
+            return;
+        };
+        let set = left | right;
+
+        // An operand holds an index if it is a single tensor holding it, or
+        // if the index is also held outside the operand
+        let holds = |operand: u64, other: u64, mask: u64| {
+            mask & operand != 0 && (operand.is_power_of_two() || mask & other != 0)
+        };
+        let mut elements = 1u64;
+        let mut contracted = 1u64;
+        for &(_, mask, dim, in_output) in &self.index_info {
+            if mask & set == 0 {
+                continue;
+            }
+            if in_output || mask & self.full & !set != 0 {
+                elements = elements.saturating_mul(dim as u64);
+            } else if holds(left, right, mask) || holds(right, left, mask) {
+                contracted = contracted.saturating_mul(dim as u64);
+            }
+        }
+
+        if set != self.full && !self.cost_model.fits_memory_limit(elements) {
+            return;
+        }
+
+        let flops = elements.saturating_mul(contracted).saturating_mul(2);
+        let memory = l.size.saturating_add(r.size).saturating_add(elements);
+        let cost = l.cost + r.cost + self.cost_model.cost(flops, memory, elements);
+        if self.plans.get(&set).is_some_and(|plan| plan.cost <= cost) {
+            return;
+        }
+        self.plans.insert(set, SubsetPlan { cost, split: left, size: elements });
+    }
 
-    // Result indices
-    let mut result_indices: Vec<char> = Vec::new();
-    let mut seen: BTreeSet<char> = BTreeSet::new();
+    /// Joins the disconnected parts of the network with outer products,
+    /// the two smallest results first.
+    fn join_components(&mut self) {
+        let mut parts: Vec<u64> = Vec::new();
+        let mut seen = 0u64;
+        for t in 0..self.neighbors.len() {
+            if seen & (1 << t) != 0 {
+                continue;
+            }
+            let mut component = 1u64 << t;
+            loop {
+                let grown = component | self.neighborhood(component);
+                if grown == component {
+                    break;
+                }
+                component = grown;
+            }
+            seen |= component;
+            parts.push(component);
+        }
 
-    for &c in indices_a {
-        if !contracted_set.contains(&c) && !seen.contains(&c) {
-            result_indices.push(c);
-            seen.insert(c);
+        while parts.len() > 1 {
+            parts.sort_by_key(|part| core::cmp::Reverse(self.plans.get(part).map(|plan| plan.size)));
+            let (a, b) = (parts.pop().unwrap(), parts.pop().unwrap());
+            self.join(a, b);
+            parts.push(a | b);
         }
     }
-    for &c in indices_b {
-        if !contracted_set.contains(&c) && !seen.contains(&c) {
-            result_indices.push(c);
-            seen.insert(c);
+
+    /// Appends the contractions forming `set`, as pairs of the smallest
+    /// tensor of each operand.
+    fn collect_pairs(&self, set: u64, pairs: &mut Vec<(usize, usize)>) {
+        let split = self.plans[&set].split;
+        if split == 0 {
+            return;
         }
+        let (left, right) = (split, set ^ split);
+        self.collect_pairs(left, pairs);
+        self.collect_pairs(right, pairs);
+        pairs.push((left.trailing_zeros() as usize, right.trailing_zeros() as usize));
     }
+}
+
+/// Mask of tensors `0..=t`.
+fn lower_bits(t: usize) -> u64 {
+    u64::MAX >> (63 - t)
+}
+
+/// Positions of the set bits of `mask`, in increasing order.
+fn bits(mask: u64) -> impl Iterator<Item = usize> {
+    let mut rest = mask;
+    core::iter::from_fn(move || {
+        if rest == 0 {
+            return None;
+        }
+        let t = rest.trailing_zeros() as usize;
+        rest &= rest - 1;
+        Some(t)
+    })
+}
+
+/// Non-empty subsets of `set`, in increasing order.
+fn subsets(set: u64) -> impl Iterator<Item = u64> {
+    let mut subset = 0u64;
+    core::iter::from_fn(move || {
+        subset = subset.wrapping_sub(set) & set;
+        (subset != 0).then_some(subset)
+    })
+}
+
+/// Computes the contraction of two tensor results.
+///
+/// `keep` holds the indices needed by the output or by tensors outside
+/// the two operands; every other index is summed out. Returns the cost, the
+/// result shape and indices, and the contracted indices.
+fn compute_contraction(
+    shape_a: &[usize],
+    indices_a: &[char],
+    shape_b: &[usize],
+    indices_b: &[char],
+    keep: &BTreeSet<char>,
+    cost_model: &CostModel,
+) -> (ContractionCost, Vec<usize>, Vec<char>, Vec<char>) {
+    let (contracted, result_indices) = split_pair_indices(indices_a, indices_b, keep);
 
     // Build dimension map
     let mut dim_map: HashMap<char, usize> = HashMap::new();
@@ -211,7 +537,7 @@ fn compute_contraction(
         &contracted,
     );
 
-    (cost, result_shape, result_indices)
+    (cost, result_shape, result_indices, contracted)
 }
 
 /// Generates all subsets of {0..n-1} with exactly `size` elements.
@@ -255,19 +581,23 @@ fn proper_subsets(set: u32) -> impl Iterator<Item = u32> {
 /// Builds a ContractionPath from a list of (left, right) tensor pairs.
 fn build_contraction_path(
     pairs: &[(usize, usize)],
+    initial_shapes: &[Vec<usize>],
     initial_indices: &[Vec<char>],
     output_set: &BTreeSet<char>,
+    cost_model: &CostModel,
 ) -> ContractionPath {
     let mut path = ContractionPath::with_capacity(pairs.len());
 
-    // Simulate the contraction process to get correct indices at each step
+    // Simulate the contraction process to get correct indices at each step.
+    // Each tensor is identified by the smallest original index it contains.
+    let mut current_shapes: Vec<Vec<usize>> = initial_shapes.to_vec();
     let mut current_indices: Vec<Vec<char>> = initial_indices.to_vec();
-    let mut index_to_position: Vec<usize> = (0..initial_indices.len()).collect();
+    let mut current_reps: Vec<usize> = (0..initial_indices.len()).collect();
 
     for &(left_orig, right_orig) in pairs {
         // Find current positions
-        let left_pos = index_to_position[left_orig];
-        let right_pos = index_to_position[right_orig];
+        let left_pos = current_reps.iter().position(|&r| r == left_orig).unwrap();
+        let right_pos = current_reps.iter().position(|&r| r == right_orig).unwrap();
 
         let (i, j) = if left_pos < right_pos {
             (left_pos, right_pos)
@@ -275,53 +605,39 @@ fn build_contraction_path(
             (right_pos, left_pos)
         };
 
-        let indices_i: BTreeSet<char> = current_indices[i].iter().copied().collect();
-        let indices_j: BTreeSet<char> = current_indices[j].iter().copied().collect();
-
-        // Contracted indices
-        let common: BTreeSet<char> = indices_i.intersection(&indices_j).copied().collect();
-        let contracted: Vec<char> = common
-            .iter()
-            .filter(|c| !output_set.contains(c))
-            .copied()
-            .collect();
-        let contracted_set: BTreeSet<char> = contracted.iter().copied().collect();
-
-        // Result indices
-        let mut result_indices: Vec<char> = Vec::new();
-        let mut seen: BTreeSet<char> = BTreeSet::new();
-        for &c in &current_indices[i] {
-            if !contracted_set.contains(&c) && !seen.contains(&c) {
-                result_indices.push(c);
-                seen.insert(c);
-            }
-        }
-        for &c in &current_indices[j] {
-            if !contracted_set.contains(&c) && !seen.contains(&c) {
-                result_indices.push(c);
-                seen.insert(c);
+        // Indices still needed by the output or by other remaining tensors
+        let mut keep = output_set.clone();
+        for (k, indices) in current_indices.iter().enumerate() {
+            if k != i && k != j {
+                keep.extend(indices.iter().copied());
             }
         }
 
-        path.push(ContractionStep::new(
-            (i, j),
-            contracted,
-            result_indices.clone(),
-            0, // FLOPs computed elsewhere
-        ));
-
-        // Update state: remove j, replace i with result
-        current_indices[i] = result_indices;
+        let (cost, result_shape, result_indices, contracted) = compute_contraction(
+            &current_shapes[i],
+            &current_indices[i],
+            &current_shapes[j],
+            &current_indices[j],
+            &keep,
+            cost_model,
+        );
+
+        path.push(
+            ContractionStep::new((i, j), contracted, result_indices.clone(), cost.flops)
+                .with_cost(&cost),
+        );
+
+        // Update state like the executor: remove both, append the result
+        current_shapes.remove(j);
+        current_shapes.remove(i);
+        current_shapes.push(result_shape);
         current_indices.remove(j);
+        current_indices.remove(i);
+        current_indices.push(result_indices);
 
-        // Update position mapping
-        for pos in &mut index_to_position {
-            if *pos == j {
-                *pos = i;
-            } else if *pos > j {
-                *pos -= 1;
-            }
-        }
+        current_reps.remove(j);
+        current_reps.remove(i);
+        current_reps.push(left_orig.min(right_orig));
     }
 
     path
@@ -331,6 +647,7 @@ fn build_contraction_path(
 mod tests {
     use super::*;
     use crate::notation::parse_einsum;
+    use crate::optimization::Objective;
 
     #[test]
     fn test_optimal_matmul() {
@@ -352,6 +669,8 @@ mod tests {
         let path = optimal_path(&notation, shapes, &cost_model);
 
         assert_eq!(path.len(), 2);
+        // (AB)C: 2*10*30*20 + 2*10*40*30
+        assert_eq!(path.total_flops(), 36_000);
     }
 
     #[test]
@@ -360,7 +679,7 @@ mod tests {
         // 2x10, 10x1000, 1000x3
         let notation = parse_einsum("ij,jk,kl->il").unwrap();
         let shapes: &[&[usize]] = &[&[2, 10], &[10, 1000], &[1000, 3]];
-        let cost_model = CostModel { alpha: 0 }; // Pure FLOP cost
+        let cost_model = CostModel { alpha: 0, ..CostModel::default() }; // Pure FLOP cost
 
         let path = optimal_path(&notation, shapes, &cost_model);
 
@@ -369,6 +688,175 @@ mod tests {
         assert_eq!(path.len(), 2);
     }
 
+    #[test]
+    fn test_optimal_memory_limit() {
+        // Every intermediate with two indices has 10_000 elements
+        let notation = parse_einsum("a,b,ab,c,d,cd->").unwrap();
+        let shapes: &[&[usize]] = &[&[100], &[100], &[100, 100], &[100], &[100], &[100, 100]];
+        let cost_model = CostModel { alpha: 0, ..CostModel::default() };
+
+        let limited = cost_model.clone().with_memory_limit(1_000);
+        let path = optimal_path(&notation, shapes, &limited);
+
+        assert_eq!(path.len(), 5);
+        let (steps, last) = path.steps().split_at(4);
+        for step in steps {
+            assert!(step.result_indices.len() <= 1, "{:?}", step);
+        }
+        assert!(last[0].result_indices.is_empty());
+        assert!(path.total_flops() >= optimal_path(&notation, shapes, &cost_model).total_flops());
+    }
+
+    #[test]
+    fn test_optimal_memory_limit_unsatisfiable() {
+        let notation = parse_einsum("ij,jk,kl->il").unwrap();
+        let shapes: &[&[usize]] = &[&[10, 10], &[10, 10], &[10, 10]];
+        let cost_model = CostModel::default().with_memory_limit(10);
+
+        let path = optimal_path(&notation, shapes, &cost_model);
+
+        assert_eq!(path, greedy_path(&notation, shapes, &cost_model));
+    }
+
+    #[test]
+    fn test_optimal_objectives() {
+        let notation = parse_einsum("ab,bc,cd->ad").unwrap();
+        let shapes: &[&[usize]] = &[&[5, 20], &[20, 5], &[5, 2]];
+        let cost_model = CostModel::default();
+
+        // (bc,cd) first: fewer FLOPs, 20x2 intermediate
+        let flops = optimal_path(&notation, shapes, &cost_model.clone().with_objective(Objective::Flops));
+        assert_eq!((flops.total_flops(), flops.largest_intermediate()), (800, 40));
+
+        // (ab,bc) first: 5x5 intermediate
+        let size = optimal_path(&notation, shapes, &cost_model.clone().with_objective(Objective::Size));
+        assert_eq!((size.total_flops(), size.largest_intermediate()), (1100, 25));
+
+        // 800 + 10 * 40 < 1100 + 10 * 25, but 800 + 30 * 40 > 1100 + 30 * 25
+        let weighted = |size| {
+            let objective = Objective::Weighted { flops: 1, size, write: 0 };
+            let path = optimal_path(&notation, shapes, &cost_model.clone().with_objective(objective));
+            (path.total_flops(), path.largest_intermediate())
+        };
+        assert_eq!(weighted(10), (800, 40));
+        assert_eq!(weighted(30), (1100, 25));
+    }
+
+    #[test]
+    fn test_optimal_populates_memory() {
+        let notation = parse_einsum("ij,jk,kl->il").unwrap();
+        let shapes: &[&[usize]] = &[&[10, 20], &[20, 30], &[30, 40]];
+
+        let path = optimal_path(&notation, shapes, &CostModel::default());
+
+        // (AB)C: (200 + 600 + 300) + (300 + 1200 + 400)
+        assert_eq!(path.total_memory(), 3_000);
+    }
+
+    /// Ring of `n` tensors with sizes cycling through a few primes.
+    fn ring(n: usize) -> (EinsumNotation, Vec<Vec<usize>>) {
+        let letters: Vec<char> = ('a'..='z').chain('A'..='Z').collect();
+        let terms: Vec<alloc::string::String> = (0..n)
+            .map(|i| [letters[i], letters[(i + 1) % n]].iter().collect())
+            .collect();
+        let notation = parse_einsum(&alloc::format!("{}->", terms.join(","))).unwrap();
+        let dims = [2, 3, 5, 7, 11];
+        let shapes = (0..n).map(|i| vec![dims[i % 5], dims[(i + 1) % n % 5]]).collect();
+        (notation, shapes)
+    }
+
+    #[test]
+    fn test_connected_matches_optimal() {
+        let (notation, shapes) = ring(8);
+        let shapes: Vec<&[usize]> = shapes.iter().map(|s| s.as_slice()).collect();
+        let cost_model = CostModel::default();
+
+        let optimal = optimal_path(&notation, &shapes, &cost_model);
+        let connected = connected_optimal_path(&notation, &shapes, &cost_model, false);
+        let exhaustive = connected_optimal_path(&notation, &shapes, &cost_model, true);
+
+        assert_eq!(connected.len(), 7);
+        assert_eq!(connected.total_cost(&cost_model), optimal.total_cost(&cost_model));
+        assert_eq!(exhaustive.total_cost(&cost_model), optimal.total_cost(&cost_model));
+    }
+
+    #[test]
+    fn test_connected_outer_products() {
+        // Joining the two vectors first is cheapest, but an outer product
+        let notation = parse_einsum("a,b,abc->c").unwrap();
+        let shapes: &[&[usize]] = &[&[2], &[2], &[2, 2, 1000]];
+        let cost_model = CostModel::default().with_objective(Objective::Flops);
+
+        let optimal = optimal_path(&notation, shapes, &cost_model);
+        let exhaustive = connected_optimal_path(&notation, shapes, &cost_model, true);
+        let connected = connected_optimal_path(&notation, shapes, &cost_model, false);
+
+        assert_eq!(optimal.steps()[0].inputs, (0, 1));
+        assert_eq!(exhaustive.total_flops(), optimal.total_flops());
+        assert_ne!(connected.steps()[0].inputs, (0, 1));
+        assert!(connected.total_flops() > optimal.total_flops());
+    }
+
+    #[test]
+    fn test_connected_large_ring() {
+        let (notation, shapes) = ring(24);
+        let shapes: Vec<&[usize]> = shapes.iter().map(|s| s.as_slice()).collect();
+        let cost_model = CostModel::default();
+
+        let path = connected_dp(&notation, &shapes, &cost_model, false, MAX_CONNECTED_DP_PAIRS).unwrap();
+
+        assert_eq!(path.len(), 23);
+        let greedy = greedy_path(&notation, &shapes, &cost_model);
+        assert!(path.total_cost(&cost_model) <= greedy.total_cost(&cost_model));
+    }
+
+    #[test]
+    fn test_connected_disconnected_network() {
+        let notation = parse_einsum("ab,bc,de,ef,g->acdfg").unwrap();
+        let shapes: &[&[usize]] = &[&[2, 3], &[3, 4], &[2, 3], &[3, 4], &[5]];
+
+        let path = connected_optimal_path(&notation, shapes, &CostModel::default(), false);
+
+        assert_eq!(path.len(), 4);
+        assert_eq!(path.steps().last().unwrap().result_size, 2 * 4 * 2 * 4 * 5);
+        let tree = ContractionPath::from_pairs(&notation, shapes, &path.to_pairs(), &CostModel::default());
+        assert!(tree.is_ok());
+    }
+
+    #[test]
+    fn test_connected_memory_limit() {
+        let notation = parse_einsum("a,b,ab,c,d,cd->").unwrap();
+        let shapes: &[&[usize]] = &[&[100], &[100], &[100, 100], &[100], &[100], &[100, 100]];
+        let cost_model = CostModel { alpha: 0, ..CostModel::default() }.with_memory_limit(1_000);
+
+        let path = connected_optimal_path(&notation, shapes, &cost_model, false);
+
+        assert!(path.largest_intermediate() <= 1_000);
+        assert_eq!(path.total_cost(&cost_model), optimal_path(&notation, shapes, &cost_model).total_cost(&cost_model));
+    }
+
+    #[test]
+    fn test_connected_gives_up_on_dense_networks() {
+        // Every tensor shares an index with every other one
+        let notation = parse_einsum("abc,ade,bdf,cef->").unwrap();
+        let shapes: &[&[usize]] = &[&[2, 2, 2], &[2, 2, 2], &[2, 2, 2], &[2, 2, 2]];
+
+        assert!(connected_dp(&notation, shapes, &CostModel::default(), false, 4).is_none());
+        assert!(connected_dp(&notation, shapes, &CostModel::default(), false, 100).is_some());
+    }
+
+    #[test]
+    fn test_connected_pairs_of_chain() {
+        // Sub-chains of length k have k - 1 splits: sum over k of (6 - k)(k - 1)
+        // pairs for a chain of 5, each evaluated once
+        let notation = parse_einsum("ab,bc,cd,de,ef->af").unwrap();
+        let shapes: &[&[usize]] = &[&[2, 2], &[2, 2], &[2, 2], &[2, 2], &[2, 2]];
+        let cost_model = CostModel::default();
+
+        assert!(connected_dp(&notation, shapes, &cost_model, false, 20).is_some());
+        assert!(connected_dp(&notation, shapes, &cost_model, false, 19).is_none());
+    }
+
     #[test]
     fn test_subsets_of_size() {
         let subs = subsets_of_size(4, 2);
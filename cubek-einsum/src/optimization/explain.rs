@@ -0,0 +1,476 @@
+//! Human-readable reports of execution plans.
+//!
+//! [`explain`] plans an expression and describes the result the way
+//! opt_einsum's `contract_path` does: the fast path that matched (or why
+//! none did), every step with its own notation, shape and cost, the largest
+//! intermediate and the speedup over evaluating the expression naively.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::plan::{create_plan, ContractionStrategy, ExecutionPlan, ExecutionStep};
+use crate::error::EinsumResult;
+use crate::notation::validation::{expanded_dims, validate_shapes};
+use crate::notation::{parse_einsum, validate_notation, EinsumNotation};
+use crate::pattern::{recognize_pattern, FastPath};
+
+/// Outcome of fast-path recognition for a report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FastPathMatch {
+    /// The expression is dispatched to this fast path.
+    Matched(FastPath),
+    /// No fast path applies, with the reason.
+    NotMatched(String),
+}
+
+/// Description of a single plan step.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepReport {
+    /// Positions of the operands in the current tensor list.
+    pub operands: Vec<usize>,
+    /// Notation of the step alone, e.g. `ij,jk->ik`.
+    pub notation: String,
+    /// Indices summed in this step.
+    pub contracted: Vec<char>,
+    /// Shape of the tensor produced by the step.
+    pub shape: Vec<usize>,
+    /// Estimated FLOPs.
+    pub flops: u64,
+    /// Elements read and written.
+    pub memory: u64,
+}
+
+/// Structured and printable description of an execution plan.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanReport {
+    /// Notation the report was created for.
+    pub notation: EinsumNotation,
+    /// Shapes of the inputs.
+    pub input_shapes: Vec<Vec<usize>>,
+    /// Shape of the output.
+    pub output_shape: Vec<usize>,
+    /// Fast path that matched, or why none did.
+    pub fast_path: FastPathMatch,
+    /// Steps in execution order.
+    pub steps: Vec<StepReport>,
+    /// Number of elements of the largest tensor produced by a step other
+    /// than the last one.
+    pub largest_intermediate: u64,
+    /// Number of elements written by all steps, output included.
+    #[serde(default)]
+    pub total_write: u64,
+    /// Indices the plan loops over, with their sizes. Step shapes and
+    /// memory are those of one slice.
+    #[serde(default)]
+    pub sliced_indices: Vec<(char, usize)>,
+    /// FLOPs of evaluating the expression in a single nested loop.
+    pub naive_flops: u64,
+    /// FLOPs of the plan.
+    pub optimized_flops: u64,
+    /// Whether the path search proved that no cheaper path exists, see
+    /// [`ExecutionPlan::proven_optimal`].
+    #[serde(default)]
+    pub proven_optimal: bool,
+}
+
+impl PlanReport {
+    /// Returns how many times fewer FLOPs the plan needs than the naive
+    /// evaluation.
+    pub fn speedup(&self) -> f64 {
+        self.naive_flops as f64 / self.optimized_flops.max(1) as f64
+    }
+}
+
+/// Plans an expression and describes the resulting plan.
+pub fn explain(
+    notation_str: &str,
+    shapes: &[&[usize]],
+    strategy: ContractionStrategy,
+) -> EinsumResult<PlanReport> {
+    let notation = parse_einsum(notation_str)?;
+    validate_notation(&notation)?;
+    validate_shapes(&notation, shapes)?;
+
+    let plan = create_plan(&notation, shapes, strategy)?;
+    explain_plan(&notation, shapes, &plan)
+}
+
+/// Describes an existing plan for `notation` and `shapes`.
+///
+/// Returns an error if the shapes are not valid for the notation.
+pub fn explain_plan(
+    notation: &EinsumNotation,
+    shapes: &[&[usize]],
+    plan: &ExecutionPlan,
+) -> EinsumResult<PlanReport> {
+    let (expanded, dims) = expanded_dims(notation, shapes)?;
+
+    // Steps of a sliced plan work on one slice at a time
+    let mut slice_dims = dims.clone();
+    for &(c, _) in plan.sliced_indices() {
+        slice_dims.insert(c, 1);
+    }
+    let size = |indices: &[char]| -> u64 {
+        indices
+            .iter()
+            .map(|c| slice_dims.get(c).copied().unwrap_or(1) as u64)
+            .product()
+    };
+    let shape_of = |indices: &[char]| -> Vec<usize> {
+        indices
+            .iter()
+            .map(|c| slice_dims.get(c).copied().unwrap_or(1))
+            .collect()
+    };
+
+    let mut tracked: Vec<Vec<char>> = expanded
+        .inputs()
+        .iter()
+        .map(|s| s.named_indices().collect())
+        .collect();
+    let mut steps = Vec::with_capacity(plan.num_steps());
+
+    for step in plan.steps() {
+        match step {
+            ExecutionStep::FastPath(_) => {
+                let output: Vec<char> = expanded.output().named_indices().collect();
+                let memory = tracked.iter().map(|t| size(t)).sum::<u64>() + size(&output);
+                steps.push(StepReport {
+                    operands: (0..tracked.len()).collect(),
+                    notation: format!("{}", expanded),
+                    contracted: expanded.contraction_indices().iter().copied().collect(),
+                    shape: shape_of(&output),
+                    flops: plan.total_flops(),
+                    memory,
+                });
+            }
+            ExecutionStep::Contraction { inputs: (i, j), contracted, result, flops } => {
+                let (i, j) = (*i, *j);
+                if i >= tracked.len() || j >= tracked.len() {
+                    break;
+                }
+
+                steps.push(StepReport {
+                    operands: alloc::vec![i, j],
+                    notation: format!(
+                        "{},{}->{}",
+                        String::from_iter(&tracked[i]),
+                        String::from_iter(&tracked[j]),
+                        String::from_iter(result),
+                    ),
+                    contracted: contracted.clone(),
+                    shape: shape_of(result),
+                    flops: *flops,
+                    memory: size(&tracked[i]) + size(&tracked[j]) + size(result),
+                });
+
+                let (min_idx, max_idx) = if i < j { (i, j) } else { (j, i) };
+                tracked.remove(max_idx);
+                tracked.remove(min_idx);
+                tracked.push(result.clone());
+            }
+            ExecutionStep::Reduction { input, axes, .. } => {
+                let Some(indices) = tracked.get(*input) else {
+                    break;
+                };
+                let kept: Vec<char> = indices
+                    .iter()
+                    .enumerate()
+                    .filter(|(pos, _)| !axes.contains(pos))
+                    .map(|(_, &c)| c)
+                    .collect();
+
+                steps.push(StepReport {
+                    operands: alloc::vec![*input],
+                    notation: format!("{}->{}", String::from_iter(indices), String::from_iter(&kept)),
+                    contracted: axes.iter().filter_map(|&a| indices.get(a).copied()).collect(),
+                    shape: shape_of(&kept),
+                    flops: size(indices),
+                    memory: size(indices) + size(&kept),
+                });
+                tracked[*input] = kept;
+            }
+            ExecutionStep::Permutation { input, perm } => {
+                let Some(indices) = tracked.get(*input) else {
+                    break;
+                };
+                let permuted: Vec<char> = perm.iter().map(|&p| indices[p]).collect();
+
+                steps.push(StepReport {
+                    operands: alloc::vec![*input],
+                    notation: format!("{}->{}", String::from_iter(indices), String::from_iter(&permuted)),
+                    contracted: Vec::new(),
+                    shape: shape_of(&permuted),
+                    flops: 0,
+                    memory: 2 * size(indices),
+                });
+                tracked[*input] = permuted;
+            }
+        }
+    }
+
+    let largest_intermediate = steps
+        .iter()
+        .rev()
+        .skip(1)
+        .map(|s| s.shape.iter().map(|&d| d as u64).product())
+        .max()
+        .unwrap_or(0);
+    let total_write = steps
+        .iter()
+        .map(|s| s.shape.iter().map(|&d| d as u64).product::<u64>())
+        .sum::<u64>()
+        .saturating_mul(plan.num_slices() as u64);
+
+    // Every combination of index values costs one multiply per extra
+    // operand plus the accumulation
+    let naive_flops = dims
+        .values()
+        .map(|&d| d as u64)
+        .product::<u64>()
+        .saturating_mul(expanded.num_inputs() as u64);
+
+    let fast_path = match recognize_pattern(&expanded) {
+        Some(fast_path) if plan.uses_fast_path() => FastPathMatch::Matched(fast_path),
+        _ => FastPathMatch::NotMatched(fast_path_miss_reason(&expanded)),
+    };
+
+    Ok(PlanReport {
+        notation: notation.clone(),
+        input_shapes: shapes.iter().map(|s| s.to_vec()).collect(),
+        output_shape: plan.output_shape().to_vec(),
+        fast_path,
+        steps,
+        largest_intermediate,
+        total_write,
+        sliced_indices: plan.sliced_indices().to_vec(),
+        naive_flops,
+        optimized_flops: plan.total_flops(),
+        proven_optimal: plan.proven_optimal(),
+    })
+}
+
+/// Explains why no fast path matches the (ellipsis-free) notation.
+fn fast_path_miss_reason(notation: &EinsumNotation) -> String {
+    let n = notation.num_inputs();
+    if n > 2 {
+        return format!("no fast path for {} operands", n);
+    }
+
+    let repeated = notation.inputs().iter().position(|input| {
+        let indices: Vec<char> = input.named_indices().collect();
+        indices
+            .iter()
+            .enumerate()
+            .any(|(pos, c)| indices[..pos].contains(c))
+    });
+
+    if n == 1 {
+        return match repeated {
+            Some(_) => String::from("repeated indices are not a trailing trace or diagonal"),
+            None => String::from("reduction also permutes the kept axes"),
+        };
+    }
+
+    if let Some(operand) = repeated {
+        return format!("operand {} has repeated indices", operand);
+    }
+
+    if let Some(c) = notation
+        .contraction_indices()
+        .iter()
+        .find(|&&c| notation.count_in_inputs(c) == 1)
+    {
+        return format!("index '{}' is summed within a single operand", c);
+    }
+
+    String::from("operand layout does not match matmul, batched matmul, Hadamard, outer or dot product")
+}
+
+impl fmt::Display for FastPathMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FastPathMatch::Matched(fast_path) => write!(f, "{:?}", fast_path),
+            FastPathMatch::NotMatched(reason) => write!(f, "none ({})", reason),
+        }
+    }
+}
+
+impl fmt::Display for PlanReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "  Complete contraction:  {}", self.notation)?;
+        writeln!(f, "             Fast path:  {}", self.fast_path)?;
+        writeln!(f, "      Naive FLOP count:  {}", self.naive_flops)?;
+        writeln!(f, "  Optimized FLOP count:  {}", self.optimized_flops)?;
+        writeln!(f, "   Theoretical speedup:  {:.3}", self.speedup())?;
+        writeln!(f, "        Proven optimal:  {}", if self.proven_optimal { "yes" } else { "no" })?;
+        writeln!(f, "  Largest intermediate:  {} elements", self.largest_intermediate)?;
+        writeln!(f, "      Elements written:  {}", self.total_write)?;
+        if !self.sliced_indices.is_empty() {
+            let slices: u64 = self.sliced_indices.iter().map(|&(_, size)| size as u64).product();
+            let indices: String = self.sliced_indices.iter().map(|&(c, _)| c).collect();
+            writeln!(f, "        Sliced indices:  {} ({} slices)", indices, slices)?;
+        }
+
+        let width = self
+            .steps
+            .iter()
+            .map(|s| s.notation.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(11);
+
+        writeln!(f, "{}", "-".repeat(width + 69))?;
+        writeln!(
+            f,
+            "{:>4}  {:<10}  {:<width$}  {:<10}  {:>14}  {:>14}  shape",
+            "step", "operands", "contraction", "contracted", "flops", "memory",
+        )?;
+        writeln!(f, "{}", "-".repeat(width + 69))?;
+
+        for (idx, step) in self.steps.iter().enumerate() {
+            let operands = format!("{:?}", step.operands);
+            writeln!(
+                f,
+                "{:>4}  {:<10}  {:<width$}  {:<10}  {:>14}  {:>14}  {:?}",
+                idx,
+                operands,
+                step.notation,
+                String::from_iter(&step.contracted),
+                step.flops,
+                step.memory,
+                step.shape,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_explain_chain() {
+        let shapes: &[&[usize]] = &[&[2, 30], &[30, 40], &[40, 5]];
+        let report = explain("ij,jk,kl->il", shapes, ContractionStrategy::Optimal).unwrap();
+
+        assert_eq!(report.steps.len(), 2);
+        assert_eq!(report.output_shape, [2, 5]);
+        assert_eq!(
+            report.fast_path,
+            FastPathMatch::NotMatched("no fast path for 3 operands".to_string())
+        );
+
+        // The last step produces the output in output order
+        let last = report.steps.last().unwrap();
+        assert!(last.notation.ends_with("->il"));
+        assert_eq!(last.shape, [2, 5]);
+
+        let first = &report.steps[0];
+        let elements: u64 = first.shape.iter().map(|&d| d as u64).product();
+        assert_eq!(report.largest_intermediate, elements);
+        assert_eq!(report.total_write, elements + 2 * 5);
+
+        // Naive: 2 * 30 * 40 * 5 index combinations, 3 operands
+        assert_eq!(report.naive_flops, 2 * 30 * 40 * 5 * 3);
+        assert_eq!(report.optimized_flops, report.steps.iter().map(|s| s.flops).sum::<u64>());
+        assert!(report.optimized_flops > 0);
+        assert!(report.speedup() > 1.0);
+        assert!(report.proven_optimal);
+    }
+
+    #[test]
+    fn test_explain_fast_path() {
+        let report = explain("ij,jk->ik", &[&[4, 8], &[8, 16]], ContractionStrategy::Auto).unwrap();
+
+        assert!(matches!(report.fast_path, FastPathMatch::Matched(FastPath::Matmul { .. })));
+        assert_eq!(report.steps.len(), 1);
+        assert_eq!(report.steps[0].notation, "ij,jk->ik");
+        assert_eq!(report.steps[0].contracted, ['j']);
+        assert_eq!(report.steps[0].memory, 4 * 8 + 8 * 16 + 4 * 16);
+        assert_eq!(report.largest_intermediate, 0);
+    }
+
+    #[test]
+    fn test_explain_unary_steps() {
+        let report = explain("ijk->ki", &[&[2, 3, 4]], ContractionStrategy::Auto).unwrap();
+
+        assert_eq!(
+            report.fast_path,
+            FastPathMatch::NotMatched("reduction also permutes the kept axes".to_string())
+        );
+        let notations: Vec<&str> = report.steps.iter().map(|s| s.notation.as_str()).collect();
+        assert_eq!(notations, ["ijk->ik", "ik->ki"]);
+        assert_eq!(report.steps[0].contracted, ['j']);
+        assert_eq!(report.largest_intermediate, 8);
+    }
+
+    #[test]
+    fn test_explain_binary_miss_reason() {
+        let report = explain("ij,jk->k", &[&[2, 3], &[3, 4]], ContractionStrategy::Auto).unwrap();
+
+        assert_eq!(
+            report.fast_path,
+            FastPathMatch::NotMatched("index 'i' is summed within a single operand".to_string())
+        );
+    }
+
+    #[test]
+    fn test_explain_ellipsis() {
+        let shapes: &[&[usize]] = &[&[2, 3, 4], &[2, 4, 5], &[2, 5, 6]];
+        let report = explain("...ij,...jk,...kl->...il", shapes, ContractionStrategy::Auto).unwrap();
+
+        assert_eq!(report.output_shape, [2, 3, 6]);
+        assert_eq!(report.steps.last().unwrap().shape, [2, 3, 6]);
+    }
+
+    #[test]
+    fn test_explain_plan_rejects_invalid_shapes() {
+        let notation = parse_einsum("...ij,...jk->...ik").unwrap();
+        let plan = create_plan(&notation, &[&[2, 3, 4], &[2, 4, 5]], ContractionStrategy::Auto).unwrap();
+
+        assert!(explain_plan(&notation, &[&[2, 3, 4], &[3, 4, 5]], &plan).is_err());
+    }
+
+    #[test]
+    fn test_explain_sliced() {
+        use crate::optimization::{create_plan_with_cost_model, CostModel};
+
+        let notation = parse_einsum("ia,ib,ab->").unwrap();
+        let shapes: &[&[usize]] = &[&[8, 10], &[8, 10], &[10, 10]];
+        let cost_model = CostModel::default().with_memory_limit(10);
+        let plan = create_plan_with_cost_model(&notation, shapes, ContractionStrategy::Optimal, &cost_model).unwrap();
+
+        let report = explain_plan(&notation, shapes, &plan).unwrap();
+
+        assert_eq!(report.sliced_indices, plan.sliced_indices());
+        assert!(report.largest_intermediate <= 10);
+        assert!(!report.proven_optimal);
+        assert!(report.to_string().contains("Sliced indices:"));
+    }
+
+    #[test]
+    fn test_explain_invalid_shapes() {
+        assert!(explain("ij,jk->ik", &[&[2, 3], &[4, 5]], ContractionStrategy::Auto).is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        let shapes: &[&[usize]] = &[&[2, 3], &[3, 4], &[4, 5]];
+        let report = explain("ij,jk,kl->il", shapes, ContractionStrategy::Auto).unwrap();
+        let text = report.to_string();
+
+        assert!(text.contains("Complete contraction:  ij,jk,kl->il"));
+        assert!(text.contains("Theoretical speedup"));
+        assert!(text.contains("Elements written:"));
+        assert!(text.contains("Proven optimal:  yes"));
+        assert_eq!(text.lines().count(), 8 + 3 + report.steps.len());
+    }
+}
@@ -1,19 +1,24 @@
 //! Greedy contraction path optimization.
 //!
-//! O(n³) algorithm that repeatedly contracts the cheapest pair.
+//! O(n³) algorithm that repeatedly contracts the cheapest pair, and a
+//! randomized variant that keeps the best of many sampled runs.
 
 use alloc::vec::Vec;
-use alloc::collections::BTreeSet;
+use alloc::collections::{BTreeMap, BTreeSet};
 
 use super::cost::{CostModel, ContractionCost};
-use super::path::{ContractionPath, ContractionStep, TensorState};
+use super::path::{ContractionPath, ContractionStep, TensorState, pair_result_size, split_pair_indices};
 use crate::notation::EinsumNotation;
+use crate::rng::SplitMix64;
 
 /// Finds a contraction path using the greedy algorithm.
 ///
 /// At each step, contracts the pair of tensors with lowest cost.
 /// Time complexity: O(n³) where n is the number of input tensors.
 ///
+/// Pairs whose result exceeds the cost model's memory limit are skipped.
+/// When every pair does, the one with the smallest result is contracted.
+///
 /// # Arguments
 /// * `notation` - The einsum notation
 /// * `shapes` - Shapes of input tensors
@@ -42,104 +47,228 @@ pub fn greedy_path(
         .map(|s| s.named_indices().collect())
         .collect();
 
-    let mut state = TensorState::new(initial_shapes, initial_indices);
-    let mut path = ContractionPath::with_capacity(n - 1);
+    let state = TensorState::new(initial_shapes, initial_indices);
 
     // Track which indices need to be kept for final output
     let output_set: BTreeSet<char> = notation.output().named_indices().collect();
 
     // Greedy loop: contract cheapest pair until one tensor remains
-    while state.len() > 1 {
-        let (best_i, best_j, step) = find_best_pair(&state, &output_set, cost_model);
+    greedy_loop(state, &output_set, cost_model, |_| 0).0
+}
+
+/// Number of cheapest pairs a randomized greedy step samples from.
+const RANDOM_GREEDY_BRANCHES: usize = 8;
+
+/// Finds a contraction path by running many randomized greedy searches and
+/// keeping the cheapest.
+///
+/// The first trial is the deterministic [`greedy_path`], so the result is
+/// never worse than it. Every other trial picks each pair among the
+/// cheapest few with Boltzmann probability
+/// `exp(-(cost - best) / (temperature * best))`: higher temperatures explore
+/// further from the greedy choice, and a temperature of zero repeats the
+/// greedy path. The same `seed` always gives the same path.
+///
+/// Paths that keep within the cost model's memory limit are preferred over
+/// cheaper ones that do not.
+pub fn random_greedy_path(
+    notation: &EinsumNotation,
+    shapes: &[&[usize]],
+    cost_model: &CostModel,
+    trials: usize,
+    temperature: f64,
+    seed: u64,
+) -> ContractionPath {
+    let n = notation.num_inputs();
+    if n <= 1 {
+        return ContractionPath::new();
+    }
+
+    let initial_shapes: Vec<Vec<usize>> = shapes.iter().map(|s| s.to_vec()).collect();
+    let initial_indices: Vec<Vec<char>> = notation
+        .inputs()
+        .iter()
+        .map(|s| s.named_indices().collect())
+        .collect();
+    let state = TensorState::new(initial_shapes, initial_indices);
+    let output_set: BTreeSet<char> = notation.output().named_indices().collect();
+
+    let rank = |(path, cost): &(ContractionPath, ContractionCost)| {
+        (!cost_model.fits_memory_limit(path.largest_intermediate()), *cost)
+    };
+
+    let mut best = greedy_loop(state.clone(), &output_set, cost_model, |_| 0);
+    if temperature <= 0.0 {
+        return best.0;
+    }
 
-        // Contract the pair
-        let new_state = state.contract(best_i, best_j, &step.result_indices);
-        state = new_state;
+    let mut rng = SplitMix64::new(seed);
+    for _ in 1..trials {
+        let trial = greedy_loop(state.clone(), &output_set, cost_model, |candidates| {
+            boltzmann_choice(candidates, temperature, &mut rng)
+        });
+        if rank(&trial) < rank(&best) {
+            best = trial;
+        }
+    }
+
+    best.0
+}
 
+/// A pair that can be contracted next.
+type Candidate = (usize, usize, ContractionStep, ContractionCost);
+
+/// Contracts pairs until one tensor remains, letting `choose` pick among
+/// the candidates of each step, sorted from cheapest.
+///
+/// Returns the path with its total cost.
+fn greedy_loop(
+    mut state: TensorState,
+    output_indices: &BTreeSet<char>,
+    cost_model: &CostModel,
+    mut choose: impl FnMut(&[Candidate]) -> usize,
+) -> (ContractionPath, ContractionCost) {
+    let mut path = ContractionPath::with_capacity(state.len().saturating_sub(1));
+    let mut total = ContractionCost::zero();
+
+    while state.len() > 1 {
+        let mut candidates = candidate_pairs(&state, output_indices, cost_model);
+        let (i, j, step, cost) = candidates.swap_remove(choose(&candidates));
+
+        state = state.contract(i, j, &step.result_indices);
+        total = total + cost;
         path.push(step);
     }
 
-    path
+    (path, total)
 }
 
-/// Finds the best pair to contract in the current state.
-fn find_best_pair(
+/// Lists the pairs that can be contracted in the current state, cheapest
+/// first (stable, so ties keep the pair order).
+///
+/// Pairs whose result exceeds the cost model's memory limit are left out.
+/// When every pair does, only the one with the smallest result is returned.
+fn candidate_pairs(
     state: &TensorState,
     output_indices: &BTreeSet<char>,
     cost_model: &CostModel,
-) -> (usize, usize, ContractionStep) {
-    let mut best_cost = ContractionCost::new(u64::MAX, u64::MAX, 1);
-    let mut best_pair = (0, 1);
-    let mut best_step = None;
+) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+
+    // Fallback when no pair fits the memory limit: (size, candidate)
+    let mut smallest: Option<(u64, Candidate)> = None;
 
     let n = state.len();
+    // The last contraction produces the output, which is not limited
+    let is_last = n == 2;
+
+    // Number of tensors each index appears in
+    let mut counts: BTreeMap<char, usize> = BTreeMap::new();
+    for indices in &state.indices {
+        for (pos, &c) in indices.iter().enumerate() {
+            if !indices[..pos].contains(&c) {
+                *counts.entry(c).or_default() += 1;
+            }
+        }
+    }
 
     for i in 0..n {
         for j in (i + 1)..n {
-            let (step, cost) = evaluate_pair(state, i, j, output_indices, cost_model);
-
-            if cost < best_cost {
-                best_cost = cost;
-                best_pair = (i, j);
-                best_step = Some(step);
+            let (step, cost) = evaluate_pair(state, i, j, output_indices, &counts, cost_model);
+
+            let size = pair_result_size(
+                &state.shapes[i],
+                &state.indices[i],
+                &state.shapes[j],
+                &state.indices[j],
+                &step.result_indices,
+            );
+            if !is_last && !cost_model.fits_memory_limit(size) {
+                if smallest.as_ref().is_none_or(|(smallest_size, _)| size < *smallest_size) {
+                    smallest = Some((size, (i, j, step, cost)));
+                }
+                continue;
             }
+
+            candidates.push((i, j, step, cost));
+        }
+    }
+
+    if candidates.is_empty() {
+        let (_, candidate) = smallest.expect("should have at least one pair");
+        candidates.push(candidate);
+    }
+    candidates.sort_by_key(|(_, _, _, cost)| *cost);
+
+    candidates
+}
+
+/// Samples one of the cheapest candidates with Boltzmann weights relative to
+/// the cheapest one.
+fn boltzmann_choice(candidates: &[Candidate], temperature: f64, rng: &mut SplitMix64) -> usize {
+    let branches = candidates.len().min(RANDOM_GREEDY_BRANCHES);
+    let best = candidates[0].3.total;
+    let scale = temperature * best.max(1) as f64;
+
+    let mut weights = [0.0; RANDOM_GREEDY_BRANCHES];
+    for (weight, (_, _, _, cost)) in weights.iter_mut().zip(&candidates[..branches]) {
+        *weight = exp_neg((cost.total - best) as f64 / scale);
+    }
+
+    let total: f64 = weights.iter().sum();
+    let mut target = rng.next_f64() * total;
+    for (idx, weight) in weights[..branches].iter().enumerate() {
+        if target < *weight {
+            return idx;
         }
+        target -= weight;
     }
+    0
+}
+
+/// `e^-x` for `x >= 0`, which `core` does not provide.
+fn exp_neg(x: f64) -> f64 {
+    if x.is_nan() || x >= 700.0 {
+        return 0.0;
+    }
+
+    // e^-x = 2^-n * e^-r with r in [0, ln 2)
+    let n = (x / core::f64::consts::LN_2) as u64;
+    let r = x - n as f64 * core::f64::consts::LN_2;
 
-    let step = best_step.expect("should have at least one pair");
-    (best_pair.0, best_pair.1, step)
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    for k in 1..16 {
+        term *= -r / k as f64;
+        sum += term;
+    }
+
+    sum * f64::from_bits((1023 - n) << 52)
 }
 
 /// Evaluates the cost of contracting a specific pair.
+///
+/// `counts` holds the number of tensors of the state each index appears in.
 fn evaluate_pair(
     state: &TensorState,
     i: usize,
     j: usize,
     final_output: &BTreeSet<char>,
+    counts: &BTreeMap<char, usize>,
     cost_model: &CostModel,
 ) -> (ContractionStep, ContractionCost) {
-    let indices_i: BTreeSet<char> = state.indices[i].iter().copied().collect();
-    let indices_j: BTreeSet<char> = state.indices[j].iter().copied().collect();
-
-    // Indices that appear in both inputs
-    let common: BTreeSet<char> = indices_i.intersection(&indices_j).copied().collect();
-
     // Indices that appear in other tensors or final output (must be kept)
     let mut kept_elsewhere = final_output.clone();
-    for (k, idx) in state.indices.iter().enumerate() {
-        if k != i && k != j {
-            for &c in idx {
-                kept_elsewhere.insert(c);
-            }
+    for &c in state.indices[i].iter().chain(&state.indices[j]) {
+        let in_pair = usize::from(state.indices[i].contains(&c)) + usize::from(state.indices[j].contains(&c));
+        if counts.get(&c).copied().unwrap_or(0) > in_pair {
+            kept_elsewhere.insert(c);
         }
     }
 
-    // Contracted indices: common indices not needed elsewhere
-    let contracted: Vec<char> = common
-        .iter()
-        .filter(|c| !kept_elsewhere.contains(c))
-        .copied()
-        .collect();
-
-    // Result indices: all indices from i and j except contracted
-    let contracted_set: BTreeSet<char> = contracted.iter().copied().collect();
-    let mut result_indices: Vec<char> = Vec::new();
-    let mut seen: BTreeSet<char> = BTreeSet::new();
-
-    // Preserve order from first tensor
-    for &c in &state.indices[i] {
-        if !contracted_set.contains(&c) && !seen.contains(&c) {
-            result_indices.push(c);
-            seen.insert(c);
-        }
-    }
-    // Add indices from second tensor that weren't in first
-    for &c in &state.indices[j] {
-        if !contracted_set.contains(&c) && !seen.contains(&c) {
-            result_indices.push(c);
-            seen.insert(c);
-        }
-    }
+    // Contracted indices: everything in the pair not needed elsewhere
+    let (contracted, result_indices) =
+        split_pair_indices(&state.indices[i], &state.indices[j], &kept_elsewhere);
 
     // Compute cost
     let cost = cost_model.compute_pairwise_cost(
@@ -155,7 +284,8 @@ fn evaluate_pair(
         contracted,
         result_indices,
         cost.flops,
-    );
+    )
+    .with_cost(&cost);
 
     (step, cost)
 }
@@ -182,7 +312,7 @@ pub fn greedy_flops_only(
     notation: &EinsumNotation,
     shapes: &[&[usize]],
 ) -> ContractionPath {
-    let cost_model = CostModel { alpha: 0 }; // Memory weight = 0
+    let cost_model = CostModel { alpha: 0, ..CostModel::default() }; // Memory weight = 0
     greedy_path(notation, shapes, &cost_model)
 }
 
@@ -231,6 +361,91 @@ mod tests {
         assert_eq!(path.len(), 2);
     }
 
+    #[test]
+    fn test_greedy_memory_limit() {
+        // Contracting the outer product first would create a 100x100 intermediate
+        let notation = parse_einsum("i,j,ij->").unwrap();
+        let shapes: &[&[usize]] = &[&[100], &[100], &[100, 100]];
+        let cost_model = CostModel { alpha: 0, ..CostModel::default() };
+
+        let unlimited = greedy_path(&notation, shapes, &cost_model);
+        assert_eq!(unlimited.steps()[0].inputs, (0, 1));
+
+        let limited = greedy_path(&notation, shapes, &cost_model.with_memory_limit(1_000));
+        assert_ne!(limited.steps()[0].inputs, (0, 1));
+        assert_eq!(limited.steps()[0].result_indices.len(), 1);
+    }
+
+    #[test]
+    fn test_greedy_memory_limit_unsatisfiable() {
+        // Every first step creates a 10x10 intermediate
+        let notation = parse_einsum("ij,jk,kl->il").unwrap();
+        let shapes: &[&[usize]] = &[&[10, 10], &[10, 10], &[10, 10]];
+        let cost_model = CostModel::default().with_memory_limit(10);
+
+        let path = greedy_path(&notation, shapes, &cost_model);
+
+        assert_eq!(path.len(), 2);
+    }
+
+    #[test]
+    fn test_random_greedy_improves_on_greedy() {
+        // Complete graph of four indices, where the greedy choices lead to
+        // a more expensive path
+        let notation = parse_einsum("ab,ac,ad,bc,bd,cd->").unwrap();
+        let shapes: &[&[usize]] = &[&[5, 6], &[5, 7], &[5, 8], &[6, 7], &[6, 8], &[7, 8]];
+        let cost_model = CostModel::default().with_objective(crate::optimization::Objective::Flops);
+
+        let greedy = greedy_path(&notation, shapes, &cost_model);
+        let optimal = crate::optimization::optimal_path(&notation, shapes, &cost_model);
+        let random = random_greedy_path(&notation, shapes, &cost_model, 64, 1.0, 0);
+
+        assert_eq!(random.len(), 5);
+        assert!(random.total_flops() < greedy.total_flops());
+        assert!(random.total_flops() >= optimal.total_flops());
+
+        // Seeded, so the path is reproducible
+        assert_eq!(random_greedy_path(&notation, shapes, &cost_model, 64, 1.0, 0), random);
+    }
+
+    #[test]
+    fn test_random_greedy_degenerate() {
+        let notation = parse_einsum("ab,ac,ad,bc,bd,cd->").unwrap();
+        let shapes: &[&[usize]] = &[&[5, 6], &[5, 7], &[5, 8], &[6, 7], &[6, 8], &[7, 8]];
+        let cost_model = CostModel::default();
+        let greedy = greedy_path(&notation, shapes, &cost_model);
+
+        // Without exploration or extra trials, only the greedy path is left
+        assert_eq!(random_greedy_path(&notation, shapes, &cost_model, 64, 0.0, 7), greedy);
+        assert_eq!(random_greedy_path(&notation, shapes, &cost_model, 1, 1.0, 7), greedy);
+        assert_eq!(random_greedy_path(&notation, shapes, &cost_model, 0, 1.0, 7), greedy);
+
+        let single = parse_einsum("ij->i").unwrap();
+        assert!(random_greedy_path(&single, &[&[2, 3]], &cost_model, 8, 1.0, 0).is_empty());
+    }
+
+    #[test]
+    fn test_random_greedy_memory_limit() {
+        let notation = parse_einsum("i,j,ij->").unwrap();
+        let shapes: &[&[usize]] = &[&[100], &[100], &[100, 100]];
+        let cost_model = CostModel { alpha: 0, ..CostModel::default() }.with_memory_limit(1_000);
+
+        for seed in 0..4 {
+            let path = random_greedy_path(&notation, shapes, &cost_model, 16, 10.0, seed);
+            assert!(path.largest_intermediate() <= 1_000);
+        }
+    }
+
+    #[test]
+    fn test_exp_neg() {
+        for x in [0.0f64, 0.5, 1.0, 2.0, 10.0, 100.0] {
+            let expected = (-x).exp();
+            assert!((exp_neg(x) - expected).abs() <= expected * 1e-12, "e^-{x}");
+        }
+        assert_eq!(exp_neg(1e6), 0.0);
+        assert_eq!(exp_neg(f64::NAN), 0.0);
+    }
+
     #[test]
     fn test_greedy_batch_matmul() {
         let notation = parse_einsum("bij,bjk->bik").unwrap();
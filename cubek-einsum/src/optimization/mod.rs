@@ -2,19 +2,40 @@
 //!
 //! Implements multiple strategies for finding optimal contraction orderings:
 //! - Greedy: O(n³) fast heuristic
+//! - Random greedy: best of many randomized greedy runs, for large n
 //! - Dynamic Programming: Optimal for small n
+//! - Connected DP: optimal over connected sub-networks, for sparse networks past 12 tensors
 //! - Branch and Bound: Good balance for medium n
+//! - Partitioning: recursive hypergraph bisection, for hundreds of tensors
+//! - Subtree reconfiguration: refining any path with DP over its small subtrees
+//! - Pareto front: paths trading FLOPs against peak memory
+//! - Slicing: looping over indices so intermediates fit a memory limit
 
 mod cost;
 mod greedy;
 mod dynamic;
 mod branch_bound;
 mod path;
+mod path_format;
+mod pareto;
+mod partition;
+mod reconfigure;
 mod plan;
+mod slicing;
+mod explain;
+mod dot;
 
-pub use cost::{CostModel, ContractionCost};
-pub use greedy::greedy_path;
-pub use dynamic::optimal_path;
-pub use branch_bound::branch_bound_path;
+pub use cost::{CostModel, ContractionCost, Objective};
+pub use greedy::{greedy_path, random_greedy_path};
+pub use dynamic::{connected_optimal_path, optimal_path};
+pub use branch_bound::{
+    branch_bound_path, branch_bound_path_with_limits, BranchBoundResult, DEFAULT_MAX_DEPTH, DEFAULT_MAX_NODES,
+};
 pub use path::{ContractionPath, ContractionStep};
-pub use plan::{ExecutionPlan, ExecutionStep, ContractionStrategy, ReductionOp, create_plan};
+pub use path_format::ContractionTree;
+pub use pareto::pareto_paths;
+pub use partition::partition_path;
+pub use reconfigure::reconfigure_path;
+pub use slicing::find_slicing;
+pub use plan::{ExecutionPlan, ExecutionStep, ContractionStrategy, ReductionOp, WorkspaceSizing, create_plan, create_plan_with_cost_model, plan_from_path};
+pub use explain::{explain, explain_plan, FastPathMatch, PlanReport, StepReport};
@@ -0,0 +1,145 @@
+//! Pareto front of FLOPs versus peak memory.
+//!
+//! Runs the FLOP-minimizing search repeatedly, each time with a memory limit
+//! just below the largest intermediate of the previous path, until no path
+//! fits. With the exact DP this yields the exact front.
+
+use alloc::vec::Vec;
+
+use super::branch_bound::branch_bound_path;
+use super::cost::{CostModel, Objective};
+use super::dynamic::{optimal_path, MAX_DP_TENSORS};
+use super::path::ContractionPath;
+use crate::notation::EinsumNotation;
+
+/// Finds the paths that trade total FLOPs against the size of the largest
+/// intermediate.
+///
+/// No returned path has both more FLOPs and a larger intermediate than
+/// another one. Paths are sorted by increasing FLOPs, so the largest
+/// intermediate decreases along the list, and the fastest path that fits a
+/// budget is the first one whose [`largest_intermediate`] is within it.
+///
+/// The front is exact for up to [`MAX_DP_TENSORS`] tensors and found with
+/// branch and bound above. A memory limit in `cost_model` caps the front;
+/// its objective is ignored.
+///
+/// [`largest_intermediate`]: ContractionPath::largest_intermediate
+pub fn pareto_paths(
+    notation: &EinsumNotation,
+    shapes: &[&[usize]],
+    cost_model: &CostModel,
+) -> Vec<ContractionPath> {
+    let search = |model: &CostModel| {
+        if notation.num_inputs() <= MAX_DP_TENSORS {
+            optimal_path(notation, shapes, model)
+        } else {
+            branch_bound_path(notation, shapes, model)
+        }
+    };
+
+    let mut model = cost_model.clone().with_objective(Objective::Flops);
+    let mut front: Vec<ContractionPath> = Vec::new();
+
+    loop {
+        let path = search(&model);
+        let largest = path.largest_intermediate();
+
+        // The optimizers fall back to a path over the limit when none fits
+        if !model.fits_memory_limit(largest) {
+            break;
+        }
+
+        front.push(path);
+        if largest == 0 {
+            break;
+        }
+        model.memory_limit = Some(largest - 1);
+    }
+
+    // Lower limits never decrease the FLOPs of an exact search, but equal
+    // FLOPs and heuristic searches can leave dominated paths behind
+    let dominated = |path: &ContractionPath| {
+        front.iter().any(|other| {
+            other.total_flops() <= path.total_flops()
+                && other.largest_intermediate() <= path.largest_intermediate()
+                && (other.total_flops(), other.largest_intermediate())
+                    != (path.total_flops(), path.largest_intermediate())
+        })
+    };
+    let mut front: Vec<ContractionPath> = front.iter().filter(|p| !dominated(p)).cloned().collect();
+    front.sort_by_key(|p| (p.total_flops(), core::cmp::Reverse(p.largest_intermediate())));
+    front.dedup_by_key(|p| (p.total_flops(), p.largest_intermediate()));
+
+    front
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_einsum;
+
+    #[test]
+    fn test_chain_front() {
+        let notation = parse_einsum("ab,bc,cd->ad").unwrap();
+        let shapes: &[&[usize]] = &[&[5, 20], &[20, 5], &[5, 2]];
+
+        let front = pareto_paths(&notation, shapes, &CostModel::default());
+
+        let points: Vec<(u64, u64)> = front
+            .iter()
+            .map(|p| (p.total_flops(), p.largest_intermediate()))
+            .collect();
+        assert_eq!(points, [(800, 40), (1100, 25)]);
+    }
+
+    #[test]
+    fn test_single_point() {
+        // (ij,jk) first needs fewer FLOPs and a smaller intermediate (2x40 against 30x5)
+        let notation = parse_einsum("ij,jk,kl->il").unwrap();
+        let shapes: &[&[usize]] = &[&[2, 30], &[30, 40], &[40, 5]];
+
+        let front = pareto_paths(&notation, shapes, &CostModel::default());
+
+        assert_eq!(front.len(), 1);
+        assert_eq!(front[0].total_flops(), 5_600);
+    }
+
+    #[test]
+    fn test_memory_limit_caps_front() {
+        let notation = parse_einsum("ab,bc,cd->ad").unwrap();
+        let shapes: &[&[usize]] = &[&[5, 20], &[20, 5], &[5, 2]];
+
+        let front = pareto_paths(&notation, shapes, &CostModel::default().with_memory_limit(30));
+        assert_eq!(front.len(), 1);
+        assert_eq!(front[0].largest_intermediate(), 25);
+
+        let front = pareto_paths(&notation, shapes, &CostModel::default().with_memory_limit(10));
+        assert!(front.is_empty());
+    }
+
+    #[test]
+    fn test_front_is_non_dominated() {
+        let notation = parse_einsum("ab,bc,cd,de,ef,fa->").unwrap();
+        let shapes: &[&[usize]] = &[&[4, 16], &[16, 2], &[2, 32], &[32, 3], &[3, 8], &[8, 4]];
+
+        let front = pareto_paths(&notation, shapes, &CostModel::default());
+
+        assert!(front.len() >= 2);
+        for pair in front.windows(2) {
+            assert!(pair[0].total_flops() < pair[1].total_flops());
+            assert!(pair[0].largest_intermediate() > pair[1].largest_intermediate());
+        }
+        let fastest = optimal_path(&notation, shapes, &CostModel::default().with_objective(Objective::Flops));
+        assert_eq!(front[0].total_flops(), fastest.total_flops());
+    }
+
+    #[test]
+    fn test_trivial_notations() {
+        let notation = parse_einsum("ij,jk->ik").unwrap();
+        let front = pareto_paths(&notation, &[&[2, 3], &[3, 4]], &CostModel::default());
+
+        assert_eq!(front.len(), 1);
+        assert_eq!(front[0].largest_intermediate(), 0);
+    }
+}
@@ -0,0 +1,447 @@
+//! Contraction trees from recursive hypergraph bisection.
+//!
+//! Tensors are the vertices of a hypergraph whose hyperedges are the indices,
+//! weighted by the log of their dimension. The network is split into two
+//! parts sharing as few (and as small) indices as possible, each part is
+//! split again, and the parts that are small enough are contracted with the
+//! DP optimizer. This is the divisive approach of cotengra's partitioning
+//! optimizers, with Fiduccia-Mattheyses bisection instead of KaHyPar.
+
+use alloc::collections::BTreeSet;
+use alloc::vec;
+use alloc::vec::Vec;
+use hashbrown::HashMap;
+
+use super::cost::CostModel;
+use super::dynamic::optimal_path;
+use super::greedy::greedy_path;
+use super::path::ContractionPath;
+use crate::error::EinsumResult;
+use crate::notation::validation::expanded_dims;
+use crate::notation::{EinsumNotation, Subscript};
+use crate::rng::SplitMix64;
+
+/// Parts of at most this many tensors are contracted with the DP optimizer.
+const PARTITION_LEAF_TENSORS: usize = 8;
+
+/// How far each part may exceed half of the tensors, cycled through trials.
+const PARTITION_IMBALANCES: [f64; 4] = [0.05, 0.15, 0.3, 0.5];
+
+/// Maximum Fiduccia-Mattheyses passes per bisection.
+const MAX_REFINE_PASSES: usize = 8;
+
+/// Finds a contraction path by recursively bisecting the tensor network.
+///
+/// Each of the `trials` runs bisects with a different random start and
+/// balance constraint, and the cheapest resulting path is kept, preferring
+/// paths that fit the cost model's memory limit. The same `seed` always
+/// gives the same path.
+///
+/// Meant for networks of hundreds of tensors, where only greedy searches
+/// are fast enough otherwise. Returns an error if the shapes are not valid
+/// for the notation.
+pub fn partition_path(
+    notation: &EinsumNotation,
+    shapes: &[&[usize]],
+    cost_model: &CostModel,
+    trials: usize,
+    seed: u64,
+) -> EinsumResult<ContractionPath> {
+    let n = notation.num_inputs();
+    if n <= 1 {
+        return Ok(ContractionPath::new());
+    }
+
+    let (expanded, dims) = expanded_dims(notation, shapes)?;
+    let hypergraph = Hypergraph::new(&expanded, &dims);
+    let mut rng = SplitMix64::new(seed);
+
+    let rank = |path: &ContractionPath| {
+        (!cost_model.fits_memory_limit(path.largest_intermediate()), path.total_cost(cost_model))
+    };
+
+    let mut best: Option<ContractionPath> = None;
+    for trial in 0..trials.max(1) {
+        let mut builder = TreeBuilder {
+            hypergraph: &hypergraph,
+            notation: &expanded,
+            shapes,
+            cost_model,
+            imbalance: PARTITION_IMBALANCES[trial % PARTITION_IMBALANCES.len()],
+            rng: &mut rng,
+            ssa: Vec::with_capacity(n - 1),
+            next_id: n,
+        };
+        builder.contract((0..n).collect());
+
+        let Ok(path) = ContractionPath::from_ssa(notation, shapes, &builder.ssa, cost_model) else {
+            continue;
+        };
+        if best.as_ref().is_none_or(|best| rank(&path) < rank(best)) {
+            best = Some(path);
+        }
+    }
+
+    Ok(best.unwrap_or_else(|| greedy_path(notation, shapes, cost_model)))
+}
+
+/// Tensors as vertices and indices as weighted hyperedges.
+struct Hypergraph {
+    /// Hyperedges of each tensor, without repeats.
+    tensor_edges: Vec<Vec<usize>>,
+    /// Tensors of each hyperedge.
+    edge_pins: Vec<Vec<usize>>,
+    /// Log2 of the dimension of each hyperedge.
+    edge_weights: Vec<f64>,
+    /// Whether each hyperedge is an output index.
+    edge_in_output: Vec<bool>,
+    /// Index of each hyperedge.
+    edge_chars: Vec<char>,
+}
+
+impl Hypergraph {
+    fn new(notation: &EinsumNotation, dims: &HashMap<char, usize>) -> Self {
+        let output: BTreeSet<char> = notation.output().named_indices().collect();
+        let mut ids: HashMap<char, usize> = HashMap::new();
+        let mut graph = Hypergraph {
+            tensor_edges: Vec::with_capacity(notation.num_inputs()),
+            edge_pins: Vec::new(),
+            edge_weights: Vec::new(),
+            edge_in_output: Vec::new(),
+            edge_chars: Vec::new(),
+        };
+
+        for (tensor, input) in notation.inputs().iter().enumerate() {
+            let mut edges = Vec::new();
+            for c in input.named_indices() {
+                let edge = *ids.entry(c).or_insert_with(|| {
+                    graph.edge_pins.push(Vec::new());
+                    graph.edge_weights.push((dims.get(&c).copied().unwrap_or(1).max(1) as f64).log2());
+                    graph.edge_in_output.push(output.contains(&c));
+                    graph.edge_chars.push(c);
+                    graph.edge_pins.len() - 1
+                });
+                if !edges.contains(&edge) {
+                    edges.push(edge);
+                    graph.edge_pins[edge].push(tensor);
+                }
+            }
+            graph.tensor_edges.push(edges);
+        }
+
+        graph
+    }
+}
+
+/// Builds an SSA path by contracting parts of the network recursively.
+struct TreeBuilder<'a> {
+    hypergraph: &'a Hypergraph,
+    notation: &'a EinsumNotation,
+    shapes: &'a [&'a [usize]],
+    cost_model: &'a CostModel,
+    imbalance: f64,
+    rng: &'a mut SplitMix64,
+    ssa: Vec<(usize, usize)>,
+    next_id: usize,
+}
+
+impl TreeBuilder<'_> {
+    /// Contracts `tensors` into one, returning the SSA id of the result.
+    fn contract(&mut self, tensors: Vec<usize>) -> usize {
+        if tensors.len() == 1 {
+            return tensors[0];
+        }
+        if tensors.len() <= PARTITION_LEAF_TENSORS {
+            return self.contract_leaf(&tensors);
+        }
+
+        let (left, right) = bisect(self.hypergraph, &tensors, self.imbalance, self.rng);
+        let left = self.contract(left);
+        let right = self.contract(right);
+        self.push(left, right)
+    }
+
+    /// Contracts a small part optimally, keeping the indices it shares with
+    /// the rest of the network.
+    fn contract_leaf(&mut self, tensors: &[usize]) -> usize {
+        let graph = self.hypergraph;
+
+        let mut kept = Vec::new();
+        for &tensor in tensors {
+            for &edge in &graph.tensor_edges[tensor] {
+                let shared = graph.edge_in_output[edge]
+                    || graph.edge_pins[edge].iter().any(|pin| !tensors.contains(pin));
+                if shared && !kept.contains(&graph.edge_chars[edge]) {
+                    kept.push(graph.edge_chars[edge]);
+                }
+            }
+        }
+
+        let inputs = tensors.iter().map(|&t| self.notation.inputs()[t].clone()).collect();
+        let part = EinsumNotation::new(inputs, Subscript::from_chars(kept));
+        let shapes: Vec<&[usize]> = tensors.iter().map(|&t| self.shapes[t]).collect();
+        let path = optimal_path(&part, &shapes, self.cost_model);
+
+        // Local SSA ids are the part's tensors, then its own contractions
+        let mut ids: Vec<usize> = tensors.to_vec();
+        for (a, b) in path.to_ssa(tensors.len()) {
+            let id = self.push(ids[a], ids[b]);
+            ids.push(id);
+        }
+        ids[ids.len() - 1]
+    }
+
+    fn push(&mut self, a: usize, b: usize) -> usize {
+        self.ssa.push((a, b));
+        self.next_id += 1;
+        self.next_id - 1
+    }
+}
+
+/// Splits `tensors` in two parts, minimizing the weight of the hyperedges
+/// between them.
+///
+/// Each part holds at least `(1 - imbalance) / 2` of the tensors. The start
+/// is grown by breadth-first search from a random tensor, then refined with
+/// Fiduccia-Mattheyses passes.
+fn bisect(
+    graph: &Hypergraph,
+    tensors: &[usize],
+    imbalance: f64,
+    rng: &mut SplitMix64,
+) -> (Vec<usize>, Vec<usize>) {
+    let m = tensors.len();
+
+    // Hyperedges in local ids, with their global id
+    let mut edge_ids: HashMap<usize, usize> = HashMap::new();
+    let mut pins: Vec<Vec<usize>> = Vec::new();
+    let mut globals: Vec<usize> = Vec::new();
+    for (v, &tensor) in tensors.iter().enumerate() {
+        for &edge in &graph.tensor_edges[tensor] {
+            let id = *edge_ids.entry(edge).or_insert_with(|| {
+                pins.push(Vec::new());
+                globals.push(edge);
+                pins.len() - 1
+            });
+            pins[id].push(v);
+        }
+    }
+    let weights: Vec<f64> = globals.iter().map(|&edge| graph.edge_weights[edge]).collect();
+
+    // Only hyperedges internal to the part can be cut: output indices and
+    // indices shared with the rest of the network are kept either way
+    let mut vertex_edges: Vec<Vec<usize>> = vec![Vec::new(); m];
+    for (e, &edge) in globals.iter().enumerate() {
+        if pins[e].len() > 1 && pins[e].len() == graph.edge_pins[edge].len() && !graph.edge_in_output[edge] {
+            for &v in &pins[e] {
+                vertex_edges[v].push(e);
+            }
+        }
+    }
+
+    let min_part = (((1.0 - imbalance) * m as f64 / 2.0) as usize).clamp(1, m / 2);
+
+    // Initial part 0: breadth-first from random tensors until half is reached
+    let mut side = vec![1u8; m];
+    let mut part_size = [0, m];
+    let mut queue = alloc::collections::VecDeque::new();
+    while part_size[0] < m / 2 {
+        if queue.is_empty() {
+            let start = (0..m).filter(|&v| side[v] == 1).nth(rng.below(part_size[1])).unwrap();
+            queue.push_back(start);
+        }
+        let Some(v) = queue.pop_front() else { break };
+        if side[v] == 0 {
+            continue;
+        }
+        side[v] = 0;
+        part_size = [part_size[0] + 1, part_size[1] - 1];
+        for &e in &vertex_edges[v] {
+            queue.extend(pins[e].iter().copied().filter(|&u| side[u] == 1));
+        }
+    }
+
+    let mut counts: Vec<[usize; 2]> = pins
+        .iter()
+        .map(|edge_pins| {
+            let zeros = edge_pins.iter().filter(|&&v| side[v] == 0).count();
+            [zeros, edge_pins.len() - zeros]
+        })
+        .collect();
+
+    // Weight of the hyperedges that moving `v` uncuts, minus those it cuts
+    let gain = |v: usize, side: &[u8], counts: &[[usize; 2]]| -> f64 {
+        let (from, to) = (side[v] as usize, 1 - side[v] as usize);
+        vertex_edges[v]
+            .iter()
+            .map(|&e| {
+                if counts[e][from] == 1 && counts[e][to] > 0 {
+                    weights[e]
+                } else if counts[e][to] == 0 && counts[e][from] > 1 {
+                    -weights[e]
+                } else {
+                    0.0
+                }
+            })
+            .sum()
+    };
+
+    for _ in 0..MAX_REFINE_PASSES {
+        let mut gains: Vec<f64> = (0..m).map(|v| gain(v, &side, &counts)).collect();
+        let mut locked = vec![false; m];
+        let mut moves = Vec::new();
+        let (mut total, mut best_total, mut best_len) = (0.0, 0.0, 0);
+
+        loop {
+            let candidate = (0..m)
+                .filter(|&v| !locked[v] && part_size[side[v] as usize] > min_part)
+                .max_by(|&a, &b| gains[a].total_cmp(&gains[b]));
+            let Some(v) = candidate else { break };
+
+            let (from, to) = (side[v] as usize, 1 - side[v] as usize);
+            total += gains[v];
+            side[v] = to as u8;
+            part_size[from] -= 1;
+            part_size[to] += 1;
+            locked[v] = true;
+            moves.push(v);
+            for &e in &vertex_edges[v] {
+                counts[e][from] -= 1;
+                counts[e][to] += 1;
+            }
+            for &e in &vertex_edges[v] {
+                for &u in &pins[e] {
+                    gains[u] = gain(u, &side, &counts);
+                }
+            }
+
+            if total > best_total + 1e-9 {
+                best_total = total;
+                best_len = moves.len();
+            }
+        }
+
+        // Undo the moves past the best prefix
+        for &v in moves[best_len..].iter().rev() {
+            let (from, to) = (side[v] as usize, 1 - side[v] as usize);
+            side[v] = to as u8;
+            part_size[from] -= 1;
+            part_size[to] += 1;
+            for &e in &vertex_edges[v] {
+                counts[e][from] -= 1;
+                counts[e][to] += 1;
+            }
+        }
+
+        if best_len == 0 {
+            break;
+        }
+    }
+
+    let (mut left, mut right) = (Vec::with_capacity(part_size[0]), Vec::with_capacity(part_size[1]));
+    for (v, &tensor) in tensors.iter().enumerate() {
+        if side[v] == 0 {
+            left.push(tensor);
+        } else {
+            right.push(tensor);
+        }
+    }
+    (left, right)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_einsum;
+
+    /// `rows` x `cols` lattice with bonds of dimension `dim` and no output.
+    fn lattice(rows: usize, cols: usize, dim: usize) -> (EinsumNotation, Vec<Vec<usize>>) {
+        let mut indices = vec![Vec::new(); rows * cols];
+        let mut next = 0x100;
+        let mut bond = |indices: &mut Vec<Vec<char>>, a: usize, b: usize| {
+            let c = char::from_u32(next).unwrap();
+            next += 1;
+            indices[a].push(c);
+            indices[b].push(c);
+        };
+        for r in 0..rows {
+            for c in 0..cols {
+                if c + 1 < cols {
+                    bond(&mut indices, r * cols + c, r * cols + c + 1);
+                }
+                if r + 1 < rows {
+                    bond(&mut indices, r * cols + c, (r + 1) * cols + c);
+                }
+            }
+        }
+
+        let shapes = indices.iter().map(|i| vec![dim; i.len()]).collect();
+        let inputs = indices.into_iter().map(Subscript::from_chars).collect();
+        (EinsumNotation::new(inputs, Subscript::new()), shapes)
+    }
+
+    #[test]
+    fn test_small_network_is_optimal() {
+        let notation = parse_einsum("ab,ac,ad,bc,bd,cd->").unwrap();
+        let shapes: &[&[usize]] = &[&[5, 6], &[5, 7], &[5, 8], &[6, 7], &[6, 8], &[7, 8]];
+        let cost_model = CostModel::default();
+
+        let path = partition_path(&notation, shapes, &cost_model, 1, 0).unwrap();
+
+        assert_eq!(path, optimal_path(&notation, shapes, &cost_model));
+    }
+
+    #[test]
+    fn test_bisect_ring() {
+        // 16 tensors in a ring: the best cuts remove two bonds
+        let letters: Vec<char> = ('a'..='p').collect();
+        let terms: Vec<String> = (0..16)
+            .map(|i| alloc::format!("{}{}", letters[i], letters[(i + 1) % 16]))
+            .collect();
+        let notation = parse_einsum(&alloc::format!("{}->", terms.join(","))).unwrap();
+        let shapes = vec![vec![4, 4]; 16];
+        let shapes: Vec<&[usize]> = shapes.iter().map(|s| s.as_slice()).collect();
+        let (_, dims) = expanded_dims(&notation, &shapes).unwrap();
+        let graph = Hypergraph::new(&notation, &dims);
+
+        for seed in 0..4 {
+            let (left, right) = bisect(&graph, &(0..16).collect::<Vec<_>>(), 0.05, &mut SplitMix64::new(seed));
+
+            assert_eq!(left.len() + right.len(), 16);
+            assert!((7..=9).contains(&left.len()), "{:?}", left);
+            let cut = (0..16).filter(|&i| left.contains(&i) != left.contains(&((i + 1) % 16))).count();
+            assert_eq!(cut, 2, "{:?}", left);
+        }
+    }
+
+    #[test]
+    fn test_lattice() {
+        let (notation, shapes) = lattice(6, 6, 2);
+        let shapes: Vec<&[usize]> = shapes.iter().map(|s| s.as_slice()).collect();
+        let cost_model = CostModel::default().with_objective(crate::optimization::Objective::Flops);
+
+        let path = partition_path(&notation, &shapes, &cost_model, 2, 0).unwrap();
+
+        assert_eq!(path.len(), 35);
+        let pairs = ContractionPath::from_pairs(&notation, &shapes, &path.to_pairs(), &cost_model).unwrap();
+        assert_eq!(pairs.total_flops(), path.total_flops());
+        // Greedy contracts along the lattice and ends up with wide intermediates
+        assert!(path.total_flops() < greedy_path(&notation, &shapes, &cost_model).total_flops());
+
+        assert_eq!(partition_path(&notation, &shapes, &cost_model, 2, 0).unwrap(), path);
+    }
+
+    #[test]
+    fn test_chain_keeps_output() {
+        let notation = parse_einsum("ab,bc,cd,de,ef,fg,gh,hi,ij,jk,kl,lm,mn,no->ao").unwrap();
+        let mut shapes = vec![vec![3, 3]; 14];
+        shapes[4] = vec![3, 2];
+        shapes[5] = vec![2, 3];
+        let shapes: Vec<&[usize]> = shapes.iter().map(|s| s.as_slice()).collect();
+
+        let path = partition_path(&notation, &shapes, &CostModel::default(), 4, 1).unwrap();
+
+        assert_eq!(path.len(), 13);
+        assert_eq!(path.steps().last().unwrap().result_indices.len(), 2);
+    }
+}
@@ -1,9 +1,14 @@
 //! Contraction path representation.
 
+use alloc::collections::BTreeSet;
 use alloc::vec::Vec;
 
+use serde::{Deserialize, Serialize};
+
+use super::cost::{ContractionCost, CostModel};
+
 /// A single step in a contraction path.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ContractionStep {
     /// Indices of tensors to contract (in current tensor list).
     pub inputs: (usize, usize),
@@ -13,9 +18,21 @@ pub struct ContractionStep {
     pub result_indices: Vec<char>,
     /// Estimated cost of this step.
     pub estimated_flops: u64,
+    /// Estimated memory traffic of this step in elements (both operands
+    /// read, result written).
+    #[serde(default)]
+    pub estimated_memory: u64,
+    /// Number of elements of the result tensor.
+    #[serde(default)]
+    pub result_size: u64,
 }
 
 impl ContractionStep {
+    /// Creates a step with the given FLOPs.
+    ///
+    /// Memory traffic and result size start at 0, set them with
+    /// [`with_cost`](Self::with_cost) or rebuild the path with
+    /// [`ContractionPath::recompute_costs`].
     pub fn new(
         inputs: (usize, usize),
         contracted_indices: Vec<char>,
@@ -27,12 +44,29 @@ impl ContractionStep {
             contracted_indices,
             result_indices,
             estimated_flops,
+            estimated_memory: 0,
+            result_size: 0,
         }
     }
+
+    /// Sets the estimated memory traffic of this step.
+    pub fn with_memory(mut self, estimated_memory: u64) -> Self {
+        self.estimated_memory = estimated_memory;
+        self
+    }
+
+    /// Sets the estimated FLOPs, memory traffic and result size from the
+    /// cost of the step.
+    pub fn with_cost(mut self, cost: &ContractionCost) -> Self {
+        self.estimated_flops = cost.flops;
+        self.estimated_memory = cost.memory;
+        self.result_size = cost.write;
+        self
+    }
 }
 
 /// A complete contraction path.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ContractionPath {
     /// Steps to execute in order.
     steps: Vec<ContractionStep>,
@@ -61,6 +95,7 @@ impl ContractionPath {
 
     pub fn push(&mut self, step: ContractionStep) {
         self.total_flops = self.total_flops.saturating_add(step.estimated_flops);
+        self.total_memory = self.total_memory.saturating_add(step.estimated_memory);
         self.steps.push(step);
     }
 
@@ -76,6 +111,34 @@ impl ContractionPath {
         self.total_memory
     }
 
+    /// Number of elements of the largest tensor created before the last
+    /// step, which produces the output.
+    pub fn largest_intermediate(&self) -> u64 {
+        self.steps
+            .iter()
+            .rev()
+            .skip(1)
+            .map(|s| s.result_size)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Total number of elements written by all steps, output included.
+    pub fn total_write(&self) -> u64 {
+        self.steps
+            .iter()
+            .fold(0u64, |acc, s| acc.saturating_add(s.result_size))
+    }
+
+    /// Total cost of all steps under `cost_model`, which is what the
+    /// optimizers minimize.
+    pub fn total_cost(&self, cost_model: &CostModel) -> ContractionCost {
+        self.steps
+            .iter()
+            .map(|s| cost_model.cost(s.estimated_flops, s.estimated_memory, s.result_size))
+            .fold(ContractionCost::zero(), |acc, cost| acc + cost)
+    }
+
     pub fn len(&self) -> usize {
         self.steps.len()
     }
@@ -131,17 +194,16 @@ impl TensorState {
         let mut new_original = Vec::with_capacity(self.len() - 1);
 
         // Compute result shape
-        let mut dim_map: hashbrown::HashMap<char, usize> = hashbrown::HashMap::new();
-        for (&c, &d) in self.indices[i].iter().zip(self.shapes[i].iter()) {
-            dim_map.insert(c, d);
-        }
-        for (&c, &d) in self.indices[j].iter().zip(self.shapes[j].iter()) {
-            dim_map.insert(c, d);
-        }
-
         let result_shape: Vec<usize> = output_indices
             .iter()
-            .map(|c| dim_map.get(c).copied().unwrap_or(1))
+            .map(|c| {
+                self.indices[j]
+                    .iter()
+                    .position(|x| x == c)
+                    .map(|pos| self.shapes[j][pos])
+                    .or_else(|| self.indices[i].iter().position(|x| x == c).map(|pos| self.shapes[i][pos]))
+                    .unwrap_or(1)
+            })
             .collect();
 
         // Build new tensor list
@@ -167,9 +229,71 @@ impl TensorState {
     }
 }
 
+/// Number of elements of the result of contracting two tensors into
+/// `result_indices`.
+pub(crate) fn pair_result_size(
+    shape_a: &[usize],
+    indices_a: &[char],
+    shape_b: &[usize],
+    indices_b: &[char],
+    result_indices: &[char],
+) -> u64 {
+    result_indices
+        .iter()
+        .map(|c| {
+            let dim = indices_a
+                .iter()
+                .position(|x| x == c)
+                .map(|pos| shape_a[pos])
+                .or_else(|| indices_b.iter().position(|x| x == c).map(|pos| shape_b[pos]));
+            dim.unwrap_or(1) as u64
+        })
+        .fold(1u64, |acc, d| acc.saturating_mul(d))
+}
+
+/// Splits the indices of a pairwise contraction into contracted and result
+/// indices.
+///
+/// An index is summed out as soon as it is not in `keep` (the final output
+/// and every other remaining tensor). This includes indices that appear in
+/// only one of the two operands. Result indices follow the order of the
+/// first operand, then the second.
+pub(crate) fn split_pair_indices(
+    indices_a: &[char],
+    indices_b: &[char],
+    keep: &BTreeSet<char>,
+) -> (Vec<char>, Vec<char>) {
+    split_pair_indices_by(indices_a, indices_b, |c| keep.contains(&c))
+}
+
+/// Like [`split_pair_indices`], keeping the indices for which `keep`
+/// returns true. Contracted indices are sorted.
+pub(crate) fn split_pair_indices_by(
+    indices_a: &[char],
+    indices_b: &[char],
+    keep: impl Fn(char) -> bool,
+) -> (Vec<char>, Vec<char>) {
+    let mut contracted: Vec<char> = Vec::new();
+    let mut result_indices: Vec<char> = Vec::new();
+    for &c in indices_a.iter().chain(indices_b.iter()) {
+        if contracted.contains(&c) || result_indices.contains(&c) {
+            continue;
+        }
+        if keep(c) {
+            result_indices.push(c);
+        } else {
+            contracted.push(c);
+        }
+    }
+    contracted.sort_unstable();
+
+    (contracted, result_indices)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::optimization::CostModel;
 
     #[test]
     fn test_contraction_path() {
@@ -182,8 +306,20 @@ mod tests {
             1000,
         ));
 
-        assert_eq!(path.len(), 1);
-        assert_eq!(path.total_flops(), 1000);
+        let cost = CostModel::default().cost(200, 50, 20);
+        path.push(ContractionStep::new((0, 1), vec!['k'], vec!['i'], 0).with_cost(&cost));
+
+        assert_eq!(path.len(), 2);
+        assert_eq!(path.total_flops(), 1200);
+        assert_eq!(path.total_memory(), 50);
+        assert_eq!(path.total_write(), 20);
+        assert_eq!(path.largest_intermediate(), 0);
+
+        let step = ContractionStep::new((0, 1), vec!['j'], vec!['i', 'k'], 10).with_memory(30);
+        assert_eq!((step.estimated_flops, step.estimated_memory, step.result_size), (10, 30, 0));
+
+        let json = serde_json::to_string(&path).unwrap();
+        assert_eq!(serde_json::from_str::<ContractionPath>(&json).unwrap(), path);
     }
 
     #[test]
@@ -200,4 +336,15 @@ mod tests {
         assert_eq!(new_state.shapes[0], vec![5, 6]); // tensor 2, now at position 0
         assert_eq!(new_state.shapes[1], vec![3, 5]); // result of 0*1
     }
+
+    #[test]
+    fn test_split_pair_indices() {
+        // k is shared but still needed by another tensor, l only appears in
+        // the second operand and is summed out right away
+        let keep: BTreeSet<char> = ['i', 'k'].into_iter().collect();
+        let (contracted, result) = split_pair_indices(&['i', 'j', 'k'], &['j', 'k', 'l'], &keep);
+
+        assert_eq!(contracted, vec!['j', 'l']);
+        assert_eq!(result, vec!['i', 'k']);
+    }
 }
@@ -0,0 +1,422 @@
+//! Conversion between contraction paths and the formats of opt_einsum and
+//! cotengra.
+//!
+//! - Linear paths (`[(0, 1), (0, 1)]`): each pair refers to positions in the
+//!   current tensor list; both operands are removed and the result is
+//!   appended. This is also the convention of [`ContractionPath`] and the
+//!   executor, see [`ContractionPath::to_pairs`].
+//! - SSA paths (`[(0, 1), (3, 2)]`): inputs have ids `0..n` and the result of
+//!   the `k`-th contraction gets id `n + k`. Ids are never reused.
+//! - Contraction trees (`[[0, 1], 2]`): nested pairs whose leaves are input
+//!   positions, as returned by cotengra's `ContractionTree.flat_tree()`.
+//!   [`ContractionTree`] serializes to this JSON form.
+//!
+//! Only pairwise contractions are supported.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::vec::Vec;
+
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+use super::cost::CostModel;
+use super::path::{split_pair_indices, ContractionPath, ContractionStep};
+use crate::error::{EinsumError, EinsumResult};
+use crate::notation::validation::expanded_dims;
+use crate::notation::EinsumNotation;
+
+/// Binary contraction tree over the input operands.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ContractionTree {
+    /// Input operand at this position.
+    Leaf(usize),
+    /// Contraction of two subtrees.
+    Node(Box<ContractionTree>, Box<ContractionTree>),
+}
+
+impl ContractionTree {
+    /// Creates an internal node.
+    pub fn node(left: ContractionTree, right: ContractionTree) -> Self {
+        ContractionTree::Node(Box::new(left), Box::new(right))
+    }
+
+    /// Appends the contractions of this tree to `ssa` in post-order and
+    /// returns the id of its root.
+    fn collect_ssa(&self, num_inputs: usize, ssa: &mut Vec<(usize, usize)>) -> usize {
+        match self {
+            ContractionTree::Leaf(id) => *id,
+            ContractionTree::Node(left, right) => {
+                let left = left.collect_ssa(num_inputs, ssa);
+                let right = right.collect_ssa(num_inputs, ssa);
+                ssa.push((left, right));
+                num_inputs + ssa.len() - 1
+            }
+        }
+    }
+}
+
+impl ContractionPath {
+    /// Converts the path to an SSA path for `num_inputs` operands.
+    pub fn to_ssa(&self, num_inputs: usize) -> Vec<(usize, usize)> {
+        let mut ids: Vec<usize> = (0..num_inputs).collect();
+        let mut next_id = num_inputs;
+
+        self.steps()
+            .iter()
+            .map(|step| {
+                let (i, j) = step.inputs;
+                let pair = (ids[i], ids[j]);
+
+                let (min_idx, max_idx) = if i < j { (i, j) } else { (j, i) };
+                ids.remove(max_idx);
+                ids.remove(min_idx);
+                ids.push(next_id);
+                next_id += 1;
+
+                pair
+            })
+            .collect()
+    }
+
+    /// Converts the path to a contraction tree for `num_inputs` operands.
+    ///
+    /// Returns `None` unless the path contracts all operands into one.
+    pub fn to_tree(&self, num_inputs: usize) -> Option<ContractionTree> {
+        let mut tracked: Vec<ContractionTree> = (0..num_inputs).map(ContractionTree::Leaf).collect();
+
+        for step in self.steps() {
+            let (i, j) = step.inputs;
+            let (min_idx, max_idx) = if i < j { (i, j) } else { (j, i) };
+            if max_idx >= tracked.len() || i == j {
+                return None;
+            }
+
+            let second = tracked.remove(max_idx);
+            let first = tracked.remove(min_idx);
+            let node = if i < j {
+                ContractionTree::node(first, second)
+            } else {
+                ContractionTree::node(second, first)
+            };
+            tracked.push(node);
+        }
+
+        match tracked.len() {
+            1 => tracked.pop(),
+            _ => None,
+        }
+    }
+
+    /// Builds a path from a linear path (opt_einsum's default format).
+    ///
+    /// Contracted and result indices are derived from the notation and FLOPs
+    /// are estimated with `cost_model`. The path must contract all operands
+    /// into one.
+    pub fn from_pairs(
+        notation: &EinsumNotation,
+        shapes: &[&[usize]],
+        pairs: &[(usize, usize)],
+        cost_model: &CostModel,
+    ) -> EinsumResult<Self> {
+        let (expanded, dims) = expanded_dims(notation, shapes)?;
+        let output: BTreeSet<char> = expanded.output().named_indices().collect();
+        let shape_of = |indices: &[char]| -> Vec<usize> {
+            indices
+                .iter()
+                .map(|c| dims.get(c).copied().unwrap_or(1))
+                .collect()
+        };
+
+        let mut tracked: Vec<Vec<char>> = expanded
+            .inputs()
+            .iter()
+            .map(|s| s.named_indices().collect())
+            .collect();
+        let mut path = ContractionPath::with_capacity(pairs.len());
+
+        for (step_idx, &(i, j)) in pairs.iter().enumerate() {
+            if i == j || i >= tracked.len() || j >= tracked.len() {
+                return Err(EinsumError::parse(format!(
+                    "path step {} contracts ({}, {}) but only {} tensors remain",
+                    step_idx,
+                    i,
+                    j,
+                    tracked.len()
+                )));
+            }
+
+            // Indices still needed by the output or by the other tensors
+            let mut keep = output.clone();
+            for (k, indices) in tracked.iter().enumerate() {
+                if k != i && k != j {
+                    keep.extend(indices.iter().copied());
+                }
+            }
+
+            let (contracted, result) = split_pair_indices(&tracked[i], &tracked[j], &keep);
+            let cost = cost_model.compute_pairwise_cost(
+                &shape_of(&tracked[i]),
+                &shape_of(&tracked[j]),
+                &tracked[i],
+                &tracked[j],
+                &contracted,
+            );
+            path.push(
+                ContractionStep::new((i, j), contracted, result.clone(), cost.flops)
+                    .with_cost(&cost),
+            );
+
+            let (min_idx, max_idx) = if i < j { (i, j) } else { (j, i) };
+            tracked.remove(max_idx);
+            tracked.remove(min_idx);
+            tracked.push(result);
+        }
+
+        if tracked.len() > 1 {
+            return Err(EinsumError::parse(format!(
+                "path leaves {} tensors uncontracted",
+                tracked.len()
+            )));
+        }
+
+        Ok(path)
+    }
+
+    /// Rebuilds the path for `notation` and `shapes`, recomputing the
+    /// indices, FLOPs, memory traffic and result size of every step.
+    ///
+    /// Steps made with [`ContractionStep::new`], or deserialized from paths
+    /// saved without sizes, have no memory traffic or result size, which
+    /// makes every intermediate look free.
+    pub fn recompute_costs(
+        &self,
+        notation: &EinsumNotation,
+        shapes: &[&[usize]],
+        cost_model: &CostModel,
+    ) -> EinsumResult<Self> {
+        Self::from_pairs(notation, shapes, &self.to_pairs(), cost_model)
+    }
+
+    /// Builds a path from an SSA path.
+    ///
+    /// See [`ContractionPath::from_pairs`].
+    pub fn from_ssa(
+        notation: &EinsumNotation,
+        shapes: &[&[usize]],
+        ssa: &[(usize, usize)],
+        cost_model: &CostModel,
+    ) -> EinsumResult<Self> {
+        let pairs = ssa_to_pairs(notation.num_inputs(), ssa)?;
+        Self::from_pairs(notation, shapes, &pairs, cost_model)
+    }
+
+    /// Builds a path from a contraction tree.
+    ///
+    /// Every operand must appear exactly once as a leaf. Subtrees are
+    /// contracted left to right, children before their parent.
+    pub fn from_tree(
+        notation: &EinsumNotation,
+        shapes: &[&[usize]],
+        tree: &ContractionTree,
+        cost_model: &CostModel,
+    ) -> EinsumResult<Self> {
+        let num_inputs = notation.num_inputs();
+        let mut ssa = Vec::with_capacity(num_inputs.saturating_sub(1));
+        tree.collect_ssa(num_inputs, &mut ssa);
+
+        // Leaves are only checked here: in SSA form, ids past the inputs
+        // refer to contractions
+        let mut leaves = Vec::new();
+        collect_leaves(tree, &mut leaves);
+        leaves.sort_unstable();
+        if leaves != (0..num_inputs).collect::<Vec<_>>() {
+            return Err(EinsumError::parse(format!(
+                "tree leaves {:?} are not the operands 0..{}",
+                leaves, num_inputs
+            )));
+        }
+
+        Self::from_ssa(notation, shapes, &ssa, cost_model)
+    }
+}
+
+fn collect_leaves(tree: &ContractionTree, leaves: &mut Vec<usize>) {
+    match tree {
+        ContractionTree::Leaf(id) => leaves.push(*id),
+        ContractionTree::Node(left, right) => {
+            collect_leaves(left, leaves);
+            collect_leaves(right, leaves);
+        }
+    }
+}
+
+/// Converts an SSA path to a linear path.
+fn ssa_to_pairs(num_inputs: usize, ssa: &[(usize, usize)]) -> EinsumResult<Vec<(usize, usize)>> {
+    // Position of every live id in the current tensor list
+    let mut ids: Vec<usize> = (0..num_inputs).collect();
+    let mut positions: HashMap<usize, usize> = ids.iter().map(|&id| (id, id)).collect();
+
+    ssa.iter()
+        .enumerate()
+        .map(|(step_idx, &(a, b))| {
+            let (Some(&i), Some(&j)) = (positions.get(&a), positions.get(&b)) else {
+                return Err(EinsumError::parse(format!(
+                    "path step {} refers to unknown or consumed tensor in ({}, {})",
+                    step_idx, a, b
+                )));
+            };
+            if a == b {
+                return Err(EinsumError::parse(format!(
+                    "path step {} contracts tensor {} with itself",
+                    step_idx, a
+                )));
+            }
+
+            let (min_idx, max_idx) = if i < j { (i, j) } else { (j, i) };
+            ids.remove(max_idx);
+            ids.remove(min_idx);
+            ids.push(num_inputs + step_idx);
+
+            positions.clear();
+            positions.extend(ids.iter().enumerate().map(|(pos, &id)| (id, pos)));
+
+            Ok((i, j))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_einsum;
+    use crate::optimization::{greedy_path, optimal_path};
+
+    fn chain() -> (EinsumNotation, Vec<Vec<usize>>) {
+        let notation = parse_einsum("ij,jk,kl,lm->im").unwrap();
+        let shapes = alloc::vec![alloc::vec![2, 3], alloc::vec![3, 4], alloc::vec![4, 5], alloc::vec![5, 6]];
+        (notation, shapes)
+    }
+
+    fn as_slices(shapes: &[Vec<usize>]) -> Vec<&[usize]> {
+        shapes.iter().map(|s| s.as_slice()).collect()
+    }
+
+    #[test]
+    fn test_pairs_roundtrip() {
+        let (notation, shapes) = chain();
+        let shapes = as_slices(&shapes);
+        let cost_model = CostModel::default();
+        let path = optimal_path(&notation, &shapes, &cost_model);
+
+        let imported = ContractionPath::from_pairs(&notation, &shapes, &path.to_pairs(), &cost_model).unwrap();
+
+        assert_eq!(imported, path);
+    }
+
+    #[test]
+    fn test_ssa_roundtrip() {
+        let (notation, shapes) = chain();
+        let shapes = as_slices(&shapes);
+        let cost_model = CostModel::default();
+        let path = greedy_path(&notation, &shapes, &cost_model);
+
+        let ssa = path.to_ssa(notation.num_inputs());
+        let imported = ContractionPath::from_ssa(&notation, &shapes, &ssa, &cost_model).unwrap();
+
+        assert_eq!(imported.to_pairs(), path.to_pairs());
+    }
+
+    #[test]
+    fn test_ssa_ids() {
+        let (notation, shapes) = chain();
+        let shapes = as_slices(&shapes);
+
+        // ((0, 1), (2, 3)) as a linear path
+        let path = ContractionPath::from_pairs(&notation, &shapes, &[(0, 1), (0, 1), (0, 1)], &CostModel::default()).unwrap();
+
+        assert_eq!(path.to_ssa(4), [(0, 1), (2, 3), (4, 5)]);
+    }
+
+    #[test]
+    fn test_tree_roundtrip() {
+        let (notation, shapes) = chain();
+        let shapes = as_slices(&shapes);
+        let cost_model = CostModel::default();
+
+        let tree = ContractionTree::node(
+            ContractionTree::node(ContractionTree::Leaf(0), ContractionTree::Leaf(1)),
+            ContractionTree::node(ContractionTree::Leaf(2), ContractionTree::Leaf(3)),
+        );
+        let path = ContractionPath::from_tree(&notation, &shapes, &tree, &cost_model).unwrap();
+
+        assert_eq!(path.to_ssa(4), [(0, 1), (2, 3), (4, 5)]);
+        assert_eq!(path.to_tree(4), Some(tree));
+    }
+
+    #[test]
+    fn test_tree_json() {
+        let tree: ContractionTree = serde_json::from_str("[[0, [1, 2]], 3]").unwrap();
+        assert_eq!(serde_json::to_string(&tree).unwrap(), "[[0,[1,2]],3]");
+
+        let (notation, shapes) = chain();
+        let shapes = as_slices(&shapes);
+        let path = ContractionPath::from_tree(&notation, &shapes, &tree, &CostModel::default()).unwrap();
+        assert_eq!(path.to_ssa(4), [(1, 2), (0, 4), (5, 3)]);
+    }
+
+    #[test]
+    fn test_invalid_paths() {
+        let (notation, shapes) = chain();
+        let shapes = as_slices(&shapes);
+        let cost_model = CostModel::default();
+
+        // Out of range, self contraction, incomplete
+        assert!(ContractionPath::from_pairs(&notation, &shapes, &[(0, 4)], &cost_model).is_err());
+        assert!(ContractionPath::from_pairs(&notation, &shapes, &[(1, 1)], &cost_model).is_err());
+        assert!(ContractionPath::from_pairs(&notation, &shapes, &[(0, 1)], &cost_model).is_err());
+
+        // Consumed SSA id
+        assert!(ContractionPath::from_ssa(&notation, &shapes, &[(0, 1), (0, 2), (3, 4)], &cost_model).is_err());
+
+        // Missing and repeated leaves
+        let tree: ContractionTree = serde_json::from_str("[[0, 1], [2, 2]]").unwrap();
+        assert!(ContractionPath::from_tree(&notation, &shapes, &tree, &cost_model).is_err());
+    }
+
+    #[test]
+    fn test_recompute_costs() {
+        let (notation, shapes) = chain();
+        let shapes = as_slices(&shapes);
+        let cost_model = CostModel::default();
+        let path = ContractionPath::from_pairs(&notation, &shapes, &[(0, 1), (0, 1), (0, 1)], &cost_model).unwrap();
+
+        // Paths saved before result sizes were recorded
+        let mut json: serde_json::Value = serde_json::to_value(&path).unwrap();
+        for step in json["steps"].as_array_mut().unwrap() {
+            let step = step.as_object_mut().unwrap();
+            step.remove("estimated_memory");
+            step.remove("result_size");
+        }
+        let loaded: ContractionPath = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded.largest_intermediate(), 0);
+
+        let recomputed = loaded.recompute_costs(&notation, &shapes, &cost_model).unwrap();
+        assert_eq!(recomputed, path);
+        assert!(recomputed.largest_intermediate() > 0);
+    }
+
+    #[test]
+    fn test_imported_path_plan() {
+        let notation = parse_einsum("...ij,...jk,...kl->...il").unwrap();
+        let shapes: &[&[usize]] = &[&[7, 2, 3], &[7, 3, 4], &[7, 4, 5]];
+
+        let path = ContractionPath::from_pairs(&notation, shapes, &[(1, 2), (0, 1)], &CostModel::default()).unwrap();
+        let plan = crate::optimization::plan_from_path(&notation, shapes, path).unwrap();
+
+        assert_eq!(plan.num_steps(), 2);
+        assert_eq!(plan.output_shape(), &[7, 2, 5]);
+    }
+}
@@ -2,34 +2,216 @@
 
 use alloc::vec;
 use alloc::vec::Vec;
+use core::time::Duration;
+
+use serde::{Deserialize, Serialize};
 
 use super::cost::CostModel;
-use super::greedy::greedy_path;
-use super::dynamic::{optimal_path, MAX_DP_TENSORS};
-use super::branch_bound::branch_bound_path;
+use super::greedy::{greedy_path, random_greedy_path};
+use super::dynamic::{
+    connected_dp, optimal_path, MAX_CONNECTED_DP_PAIRS, MAX_CONNECTED_DP_TENSORS,
+    MAX_DP_TENSORS,
+};
+use super::branch_bound::{branch_bound_path_with_limits, DEFAULT_MAX_DEPTH, DEFAULT_MAX_NODES};
+use super::partition::partition_path;
+use super::reconfigure::reconfigure_path;
 use super::path::ContractionPath;
+use super::slicing::{find_slicing, slice_shapes};
+use crate::error::{EinsumError, EinsumResult};
+use crate::notation::validation::validate_shapes;
 use crate::notation::EinsumNotation;
 use crate::pattern::FastPath;
 
 /// Maximum tensors for branch and bound before fallback to greedy.
 const MAX_BB_TENSORS: usize = 20;
 
+/// Randomized greedy trials `Auto` runs above [`MAX_BB_TENSORS`].
+const AUTO_RANDOM_GREEDY_TRIALS: usize = 128;
+
+/// Maximum tensors for which `Auto` also tries randomized greedy, which
+/// gets slow past that, next to partitioning.
+const MAX_AUTO_RANDOM_GREEDY_TENSORS: usize = 32;
+
+/// Bisection runs `Auto` tries above [`MAX_BB_TENSORS`].
+const AUTO_PARTITION_TRIALS: usize = 8;
+
+/// Tensors per subtree `Auto` re-optimizes above [`MAX_BB_TENSORS`].
+const AUTO_SUBTREE_SIZE: usize = 6;
+
 /// Strategy for finding contraction paths.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub enum ContractionStrategy {
     /// Greedy algorithm - fast O(n³) heuristic.
     Greedy,
+    /// Keep the cheapest of `trials` greedy paths whose pairs are sampled
+    /// with Boltzmann probabilities, see [`random_greedy_path`].
+    ///
+    /// [`random_greedy_path`]: super::random_greedy_path
+    RandomGreedy {
+        /// Number of paths tried, including the deterministic greedy one.
+        trials: usize,
+        /// Relative cost difference at which a pair becomes e times less
+        /// likely than the cheapest one.
+        temperature: f64,
+        /// Seed of the random generator.
+        seed: u64,
+    },
+    /// Build the contraction tree by recursively bisecting the tensor
+    /// network, keeping the cheapest of `trials` runs, see
+    /// [`partition_path`]. Scales to networks of hundreds of tensors.
+    ///
+    /// [`partition_path`]: super::partition_path
+    Partition {
+        /// Number of bisection runs.
+        trials: usize,
+        /// Seed of the random generator.
+        seed: u64,
+    },
     /// Optimal dynamic programming - exponential but optimal for small n.
+    ///
+    /// Above 12 tensors the subsets can no longer be enumerated and the
+    /// greedy path is returned. `ConnectedOptimal` stays exact further for
+    /// sparse networks, but skips outer products by default, so it is not
+    /// substituted silently.
     Optimal,
-    /// Branch and bound - good balance for medium n.
-    BranchBound,
+    /// Dynamic programming over connected sub-networks only, see
+    /// [`connected_optimal_path`]. Optimal for sparse networks of 15 to 25
+    /// tensors, among the paths without outer products unless
+    /// `outer_products` is set.
+    ///
+    /// [`connected_optimal_path`]: super::connected_optimal_path
+    ConnectedOptimal {
+        /// Whether sub-networks sharing no index may be contracted.
+        outer_products: bool,
+    },
+    /// Branch and bound - good balance for medium n, see
+    /// [`branch_bound_path_with_limits`].
+    ///
+    /// The search starts from the greedy path and returns the best one
+    /// found when a budget runs out.
+    ///
+    /// [`branch_bound_path_with_limits`]: super::branch_bound_path_with_limits
+    BranchBound {
+        /// Number of search nodes explored at most.
+        max_nodes: u64,
+        /// Number of contractions searched before the remaining tensors are
+        /// contracted greedily.
+        max_depth: usize,
+        /// Wall-clock budget of the search. Needs the `std` feature.
+        time_limit: Option<Duration>,
+    },
     /// Automatically choose based on problem size.
     #[default]
     Auto,
+    /// Follow a given linear path instead of searching one.
+    ///
+    /// Each pair refers to positions in the current tensor list; both
+    /// operands are removed and the result is appended, as in
+    /// [`ContractionPath::to_pairs`]. [`create_plan`] returns an error for
+    /// a path that does not contract all operands into one, even when a fast
+    /// path is used instead.
+    Explicit(Vec<(usize, usize)>),
+}
+
+// Strategies are plan cache keys, so temperatures are compared and hashed by
+// their bits: `Eq` and `Hash` then agree for every float, including NaN,
+// which `validate` rejects before a plan is created
+impl PartialEq for ContractionStrategy {
+    fn eq(&self, other: &Self) -> bool {
+        use ContractionStrategy::*;
+
+        match (self, other) {
+            (
+                RandomGreedy { trials, temperature, seed },
+                RandomGreedy { trials: other_trials, temperature: other_temperature, seed: other_seed },
+            ) => trials == other_trials && temperature.to_bits() == other_temperature.to_bits() && seed == other_seed,
+            (Partition { trials, seed }, Partition { trials: other_trials, seed: other_seed }) => {
+                trials == other_trials && seed == other_seed
+            }
+            (ConnectedOptimal { outer_products }, ConnectedOptimal { outer_products: other }) => {
+                outer_products == other
+            }
+            (
+                BranchBound { max_nodes, max_depth, time_limit },
+                BranchBound { max_nodes: other_nodes, max_depth: other_depth, time_limit: other_limit },
+            ) => max_nodes == other_nodes && max_depth == other_depth && time_limit == other_limit,
+            (Explicit(pairs), Explicit(other)) => pairs == other,
+            _ => core::mem::discriminant(self) == core::mem::discriminant(other),
+        }
+    }
+}
+
+impl Eq for ContractionStrategy {}
+
+impl core::hash::Hash for ContractionStrategy {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+        match self {
+            ContractionStrategy::RandomGreedy { trials, temperature, seed } => {
+                trials.hash(state);
+                temperature.to_bits().hash(state);
+                seed.hash(state);
+            }
+            ContractionStrategy::Partition { trials, seed } => {
+                trials.hash(state);
+                seed.hash(state);
+            }
+            ContractionStrategy::ConnectedOptimal { outer_products } => outer_products.hash(state),
+            ContractionStrategy::BranchBound { max_nodes, max_depth, time_limit } => {
+                max_nodes.hash(state);
+                max_depth.hash(state);
+                time_limit.hash(state);
+            }
+            ContractionStrategy::Explicit(pairs) => pairs.hash(state),
+            _ => {}
+        }
+    }
+}
+
+impl ContractionStrategy {
+    /// Branch and bound with the default budgets of [`DEFAULT_MAX_NODES`]
+    /// nodes and [`DEFAULT_MAX_DEPTH`] levels, and no time limit.
+    ///
+    /// [`DEFAULT_MAX_NODES`]: super::DEFAULT_MAX_NODES
+    /// [`DEFAULT_MAX_DEPTH`]: super::DEFAULT_MAX_DEPTH
+    pub const fn branch_bound() -> Self {
+        ContractionStrategy::BranchBound {
+            max_nodes: DEFAULT_MAX_NODES,
+            max_depth: DEFAULT_MAX_DEPTH,
+            time_limit: None,
+        }
+    }
+
+    /// Checks that an [`Explicit`](ContractionStrategy::Explicit) path
+    /// contracts all operands of `notation` into one, and that a
+    /// [`RandomGreedy`](ContractionStrategy::RandomGreedy) temperature is
+    /// finite and non-negative.
+    ///
+    /// Other strategies are always valid.
+    pub fn validate(&self, notation: &EinsumNotation, shapes: &[&[usize]]) -> EinsumResult<()> {
+        match self {
+            ContractionStrategy::Explicit(pairs) => {
+                ContractionPath::from_pairs(notation, shapes, pairs, &CostModel::default()).map(|_| ())
+            }
+            ContractionStrategy::RandomGreedy { temperature, .. } => check_temperature(*temperature),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn check_temperature(temperature: f64) -> EinsumResult<()> {
+    if temperature.is_finite() && temperature >= 0.0 {
+        Ok(())
+    } else {
+        Err(EinsumError::parse(alloc::format!(
+            "random greedy temperature must be finite and non-negative, got {}",
+            temperature
+        )))
+    }
 }
 
 /// A single step in the execution plan.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ExecutionStep {
     /// Use a fast path (optimized primitive).
     FastPath(FastPath),
@@ -63,7 +245,7 @@ pub enum ExecutionStep {
 }
 
 /// Type of reduction operation.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ReductionOp {
     Sum,
     Prod,
@@ -72,7 +254,7 @@ pub enum ReductionOp {
 }
 
 /// Complete execution plan for an einsum operation.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExecutionPlan {
     /// Steps to execute.
     steps: Vec<ExecutionStep>,
@@ -84,6 +266,13 @@ pub struct ExecutionPlan {
     uses_fast_path: bool,
     /// Initial input indices from notation (for multi-step contractions).
     input_indices: Vec<Vec<char>>,
+    /// Indices the executor loops over, with their sizes. Steps contract one
+    /// slice at a time and the slices are summed into the output.
+    #[serde(default)]
+    sliced_indices: Vec<(char, usize)>,
+    /// Whether the path search proved that no cheaper path exists.
+    #[serde(default)]
+    proven_optimal: bool,
 }
 
 impl ExecutionPlan {
@@ -95,6 +284,8 @@ impl ExecutionPlan {
             output_shape,
             uses_fast_path: true,
             input_indices: Vec::new(),
+            sliced_indices: Vec::new(),
+            proven_optimal: false,
         }
     }
 
@@ -122,6 +313,8 @@ impl ExecutionPlan {
             output_shape,
             uses_fast_path: false,
             input_indices,
+            sliced_indices: Vec::new(),
+            proven_optimal: false,
         }
     }
 
@@ -154,6 +347,245 @@ impl ExecutionPlan {
     pub fn input_indices(&self) -> &[Vec<char>] {
         &self.input_indices
     }
+
+    /// Returns whether the path search proved that no cheaper path exists.
+    ///
+    /// This holds for plans of at most two inputs, for [`Optimal`] within
+    /// its tensor limit, for [`ConnectedOptimal`] with outer products and for
+    /// [`BranchBound`] searches that ran to completion, unless the path does
+    /// not fit the memory limit or the plan is sliced. The dynamic
+    /// programming searches are not exact for [`Weighted`] objectives
+    /// that add the largest size to FLOPs or writes. It is false
+    /// for plans not created by [`create_plan`].
+    ///
+    /// [`Optimal`]: ContractionStrategy::Optimal
+    /// [`ConnectedOptimal`]: ContractionStrategy::ConnectedOptimal
+    /// [`BranchBound`]: ContractionStrategy::BranchBound
+    /// [`Weighted`]: super::Objective::Weighted
+    pub fn proven_optimal(&self) -> bool {
+        self.proven_optimal
+    }
+
+    /// Returns the sliced indices with their sizes, empty if the plan is not
+    /// sliced.
+    pub fn sliced_indices(&self) -> &[(char, usize)] {
+        &self.sliced_indices
+    }
+
+    /// Returns the number of slices the executor runs, 1 if the plan is not
+    /// sliced.
+    pub fn num_slices(&self) -> usize {
+        self.sliced_indices.iter().map(|&(_, size)| size).product()
+    }
+
+    /// Checks that the sliced indices can be looped over for `shapes`.
+    ///
+    /// Each sliced index must be listed once, be held by an input with its
+    /// listed size (other inputs may broadcast it with size 1) and not be an
+    /// output index. Sizes must be non-zero, otherwise no slice would write
+    /// the output.
+    pub fn validate_slicing(&self, shapes: &[&[usize]]) -> EinsumResult<()> {
+        if self.sliced_indices.is_empty() {
+            return Ok(());
+        }
+        if shapes.len() != self.input_indices.len() {
+            return Err(EinsumError::shape(alloc::format!(
+                "sliced plan has {} inputs, got {} shapes",
+                self.input_indices.len(),
+                shapes.len()
+            )));
+        }
+
+        let output: &[char] = match self.steps.last() {
+            Some(ExecutionStep::Contraction { result, .. }) => result,
+            _ => &[],
+        };
+
+        for (pos, &(c, size)) in self.sliced_indices.iter().enumerate() {
+            if size == 0 {
+                return Err(EinsumError::shape(alloc::format!("sliced index '{}' has size 0", c)));
+            }
+            if self.sliced_indices[..pos].iter().any(|&(s, _)| s == c) {
+                return Err(EinsumError::shape(alloc::format!("index '{}' is sliced twice", c)));
+            }
+            if output.contains(&c) {
+                return Err(EinsumError::shape(alloc::format!("output index '{}' cannot be sliced", c)));
+            }
+
+            let mut held = false;
+            for (indices, shape) in self.input_indices.iter().zip(shapes) {
+                for (&x, &d) in indices.iter().zip(shape.iter()) {
+                    if x != c {
+                        continue;
+                    }
+                    if d != size && d != 1 {
+                        return Err(EinsumError::shape(alloc::format!(
+                            "index '{}' is sliced with size {} but has size {}",
+                            c, size, d
+                        )));
+                    }
+                    held |= d == size;
+                }
+            }
+            if !held {
+                return Err(EinsumError::shape(alloc::format!(
+                    "sliced index '{}' of size {} is not held by any input",
+                    c, size
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Computes the intermediate tensors the executor allocates for this plan.
+    ///
+    /// Tensors are tracked the same way as in the executor: contractions
+    /// remove both operands and append their result, and the last step writes
+    /// the output directly. Permutations are views and allocate nothing.
+    /// Sliced plans allocate the intermediates of one slice at a time, so
+    /// sliced indices count with size 1, and the per-slice input copies and
+    /// the partial output the slices are summed through add to the peak.
+    pub fn workspace_sizing(&self, input_shapes: &[&[usize]]) -> WorkspaceSizing {
+        let mut sizing = WorkspaceSizing::default();
+
+        // (shape, indices, whether the tensor is an intermediate)
+        let mut tracked: Vec<(Vec<usize>, Vec<char>, bool)> = self
+            .input_indices
+            .iter()
+            .zip(input_shapes.iter())
+            .map(|(indices, shape)| {
+                let shape = indices
+                    .iter()
+                    .zip(shape.iter())
+                    .map(|(c, &d)| if self.sliced_indices.iter().any(|&(s, _)| s == *c) { 1 } else { d })
+                    .collect();
+                (shape, indices.clone(), false)
+            })
+            .collect();
+        let mut live = 0usize;
+
+        for (step_idx, step) in self.steps.iter().enumerate() {
+            let is_last = step_idx + 1 == self.steps.len();
+
+            match step {
+                ExecutionStep::FastPath(_) => {}
+                ExecutionStep::Contraction { inputs: (i, j), result, .. } => {
+                    let (i, j) = (*i, *j);
+                    if is_last || i >= tracked.len() || j >= tracked.len() {
+                        continue;
+                    }
+
+                    let shape: Vec<usize> = result
+                        .iter()
+                        .map(|c| {
+                            [i, j]
+                                .iter()
+                                .find_map(|&t| {
+                                    let (shape, indices, _) = &tracked[t];
+                                    indices.iter().position(|x| x == c).map(|pos| shape[pos])
+                                })
+                                .unwrap_or(1)
+                        })
+                        .collect();
+
+                    let elements: usize = shape.iter().product();
+                    sizing.peak_elements = sizing.peak_elements.max(live + elements);
+                    sizing.intermediate_shapes.push(shape.clone());
+
+                    let (min_idx, max_idx) = if i < j { (i, j) } else { (j, i) };
+                    for idx in [max_idx, min_idx] {
+                        let (shape, _, intermediate) = tracked.remove(idx);
+                        if intermediate {
+                            live -= shape.iter().product::<usize>();
+                        }
+                    }
+                    live += elements;
+                    tracked.push((shape, result.clone(), true));
+                }
+                ExecutionStep::Permutation { input, perm } => {
+                    if is_last || *input >= tracked.len() {
+                        continue;
+                    }
+                    let (shape, indices, _) = &mut tracked[*input];
+                    *shape = perm.iter().map(|&p| shape[p]).collect();
+                    *indices = perm.iter().map(|&p| indices[p]).collect();
+                }
+                ExecutionStep::Reduction { input, axes, .. } => {
+                    if is_last || *input >= tracked.len() {
+                        continue;
+                    }
+                    let (shape, indices, intermediate) = &tracked[*input];
+
+                    let mut reduced_shape: Vec<usize> = shape
+                        .iter()
+                        .enumerate()
+                        .filter(|(idx, _)| !axes.contains(idx))
+                        .map(|(_, &d)| d)
+                        .collect();
+                    let reduced_indices: Vec<char> = indices
+                        .iter()
+                        .enumerate()
+                        .filter(|(idx, _)| !axes.contains(idx))
+                        .map(|(_, &c)| c)
+                        .collect();
+                    if reduced_shape.is_empty() {
+                        reduced_shape.push(1);
+                    }
+
+                    let elements: usize = reduced_shape.iter().product();
+                    sizing.peak_elements = sizing.peak_elements.max(live + elements);
+                    sizing.intermediate_shapes.push(reduced_shape.clone());
+
+                    if *intermediate {
+                        live -= shape.iter().product::<usize>();
+                    }
+                    live += elements;
+                    tracked[*input] = (reduced_shape, reduced_indices, true);
+                }
+            }
+        }
+
+        if !self.sliced_indices.is_empty() {
+            // Inputs with a sliced dimension are copied for every slice, and
+            // the slices after the first are written to a partial output
+            for (indices, shape) in self.input_indices.iter().zip(input_shapes.iter()) {
+                let mut slice_shape = shape.to_vec();
+                for (dim, c) in indices.iter().enumerate() {
+                    if dim < slice_shape.len() && self.sliced_indices.iter().any(|&(s, _)| s == *c) {
+                        slice_shape[dim] = 1;
+                    }
+                }
+                if slice_shape != *shape {
+                    sizing.slice_input_shapes.push(slice_shape);
+                }
+            }
+            if self.num_slices() > 1 {
+                sizing.partial_elements = self.output_shape.iter().product();
+            }
+
+            let copies: usize = sizing.slice_input_shapes.iter().map(|s| s.iter().product::<usize>()).sum();
+            sizing.peak_elements += copies + sizing.partial_elements;
+        }
+
+        sizing
+    }
+}
+
+/// Intermediate allocations of an execution plan.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkspaceSizing {
+    /// Shape of each intermediate tensor, in the order they are allocated.
+    pub intermediate_shapes: Vec<Vec<usize>>,
+    /// Shapes of the copies of the inputs holding a sliced index, allocated
+    /// for every slice. Empty unless the plan is sliced.
+    pub slice_input_shapes: Vec<Vec<usize>>,
+    /// Elements of the buffer every slice after the first is written to
+    /// before being added to the output. 0 unless the plan is sliced.
+    pub partial_elements: usize,
+    /// Maximum number of elements alive at the same time, input copies and
+    /// partial output included.
+    pub peak_elements: usize,
 }
 
 /// Creates an execution plan for an einsum operation.
@@ -162,55 +594,201 @@ impl ExecutionPlan {
 /// 1. Checks for fast paths (matmul, reduce, etc.)
 /// 2. If no fast path, finds optimal contraction order
 /// 3. Returns a complete execution plan
+///
+/// Returns an error if the shapes do not fit an ellipsis of `notation`, or
+/// if an [`Explicit`](ContractionStrategy::Explicit) path is invalid.
 pub fn create_plan(
     notation: &EinsumNotation,
     shapes: &[&[usize]],
     strategy: ContractionStrategy,
-) -> ExecutionPlan {
+) -> EinsumResult<ExecutionPlan> {
+    create_plan_with_cost_model(notation, shapes, strategy, &CostModel::default())
+}
+
+/// Creates an execution plan, ranking contraction orders with `cost_model`.
+///
+/// See [`create_plan`].
+pub fn create_plan_with_cost_model(
+    notation: &EinsumNotation,
+    shapes: &[&[usize]],
+    strategy: ContractionStrategy,
+    cost_model: &CostModel,
+) -> EinsumResult<ExecutionPlan> {
+    // Path search and the executor only look at named indices, so the
+    // ellipsis is replaced by explicit batch indices first
+    if notation.has_ellipsis() {
+        let ellipsis_dims = validate_shapes(notation, shapes)?.ellipsis_dims;
+        return create_plan_with_cost_model(
+            &notation.expand_ellipsis(ellipsis_dims),
+            shapes,
+            strategy,
+            cost_model,
+        );
+    }
+
     // First, check for fast paths
     if let Some(fast_path) = crate::pattern::recognize_pattern(notation) {
+        // An explicit path must be valid even though it is not followed
+        strategy.validate(notation, shapes)?;
+
         // Compute output shape
         let output_shape = compute_output_shape(notation, shapes);
         let flops = estimate_fast_path_flops(&fast_path, shapes);
-        return ExecutionPlan::fast_path(fast_path, output_shape, flops);
+        let mut plan = ExecutionPlan::fast_path(fast_path, output_shape, flops);
+        // At most two operands leave a single order
+        plan.proven_optimal = notation.num_inputs() <= 2;
+        return Ok(plan);
     }
 
     // No fast path - use contraction path optimization
-    let cost_model = CostModel::default();
     let n = notation.num_inputs();
+    // Set by the searches that rule out every other path
+    let mut proven_optimal = n <= 2;
     let path = match strategy {
-        ContractionStrategy::Greedy => greedy_path(notation, shapes, &cost_model),
+        ContractionStrategy::Explicit(pairs) => ContractionPath::from_pairs(notation, shapes, &pairs, cost_model)?,
+        ContractionStrategy::Greedy => greedy_path(notation, shapes, cost_model),
+        ContractionStrategy::RandomGreedy { trials, temperature, seed } => {
+            check_temperature(temperature)?;
+            random_greedy_path(notation, shapes, cost_model, trials, temperature, seed)
+        }
+        ContractionStrategy::Partition { trials, seed } => {
+            partition_path(notation, shapes, cost_model, trials, seed)?
+        }
         ContractionStrategy::Optimal => {
             if n <= MAX_DP_TENSORS {
-                optimal_path(notation, shapes, &cost_model)
+                proven_optimal |= !cost_model.objective.mixes_size();
+                optimal_path(notation, shapes, cost_model)
+            } else {
+                greedy_path(notation, shapes, cost_model)
+            }
+        }
+        ContractionStrategy::ConnectedOptimal { outer_products } => {
+            if n <= MAX_CONNECTED_DP_TENSORS {
+                match connected_dp(notation, shapes, cost_model, outer_products, MAX_CONNECTED_DP_PAIRS) {
+                    Some(path) => {
+                        proven_optimal |= outer_products && !cost_model.objective.mixes_size();
+                        path
+                    }
+                    None => greedy_path(notation, shapes, cost_model),
+                }
             } else {
-                greedy_path(notation, shapes, &cost_model)
+                greedy_path(notation, shapes, cost_model)
             }
         }
-        ContractionStrategy::BranchBound => {
+        ContractionStrategy::BranchBound { max_nodes, max_depth, time_limit } => {
             if n <= MAX_BB_TENSORS {
-                branch_bound_path(notation, shapes, &cost_model)
+                let result =
+                    branch_bound_path_with_limits(notation, shapes, cost_model, max_nodes, max_depth, time_limit);
+                proven_optimal |= result.proven_optimal;
+                result.path
             } else {
-                greedy_path(notation, shapes, &cost_model)
+                greedy_path(notation, shapes, cost_model)
             }
         }
         ContractionStrategy::Auto => {
+            let mut branch_bound = || {
+                let result = branch_bound_path_with_limits(
+                    notation, shapes, cost_model, DEFAULT_MAX_NODES, DEFAULT_MAX_DEPTH, None,
+                );
+                proven_optimal |= result.proven_optimal;
+                result.path
+            };
             if n <= 4 {
                 // Small problems: use DP for optimal solution
-                optimal_path(notation, shapes, &cost_model)
+                proven_optimal |= !cost_model.objective.mixes_size();
+                optimal_path(notation, shapes, cost_model)
             } else if n <= MAX_DP_TENSORS {
                 // Medium problems: use branch and bound
-                branch_bound_path(notation, shapes, &cost_model)
+                branch_bound()
             } else if n <= MAX_BB_TENSORS {
-                // Larger problems: still use branch and bound with pruning
-                branch_bound_path(notation, shapes, &cost_model)
+                // Larger problems: exact over connected sub-networks while
+                // they can be enumerated, else branch and bound with pruning
+                connected_dp(notation, shapes, cost_model, false, MAX_CONNECTED_DP_PAIRS)
+                    .unwrap_or_else(branch_bound)
             } else {
-                // Very large: fall back to greedy
-                greedy_path(notation, shapes, &cost_model)
+                // Very large: partition the network, and compare with the best
+                // of many randomized greedy paths while those are affordable,
+                // then refine the winner's subtrees
+                let partitioned = partition_path(notation, shapes, cost_model, AUTO_PARTITION_TRIALS, 0)?;
+                let start = if n > MAX_AUTO_RANDOM_GREEDY_TENSORS {
+                    partitioned
+                } else {
+                    let random = random_greedy_path(notation, shapes, cost_model, AUTO_RANDOM_GREEDY_TRIALS, 1.0, 0);
+                    let rank = |path: &ContractionPath| {
+                        (!cost_model.fits_memory_limit(path.largest_intermediate()), path.total_cost(cost_model))
+                    };
+                    if rank(&random) <= rank(&partitioned) { random } else { partitioned }
+                };
+                reconfigure_path(notation, shapes, &start, cost_model, AUTO_SUBTREE_SIZE)?
             }
         }
     };
 
+    // Paths over the memory limit are sliced when that makes them fit
+    if !cost_model.fits_memory_limit(path.largest_intermediate()) {
+        if let Some(sliced) = find_slicing(notation, shapes, &path, cost_model)?
+            && !sliced.is_empty()
+        {
+            return sliced_plan(notation, shapes, &path, sliced, cost_model);
+        }
+        // The searches fall back to greedy when no path fits
+        proven_optimal = false;
+    }
+
+    let mut plan = plan_from_path(notation, shapes, path)?;
+    plan.proven_optimal = proven_optimal;
+    Ok(plan)
+}
+
+/// Creates a plan that runs `path` once per value of the `sliced` indices.
+///
+/// Step shapes are those of one slice, and step FLOPs are summed over all
+/// slices.
+fn sliced_plan(
+    notation: &EinsumNotation,
+    shapes: &[&[usize]],
+    path: &ContractionPath,
+    sliced: Vec<(char, usize)>,
+    cost_model: &CostModel,
+) -> EinsumResult<ExecutionPlan> {
+    let slice_shapes = slice_shapes(notation, shapes, &sliced);
+    let slice_shapes: Vec<&[usize]> = slice_shapes.iter().map(|s| s.as_slice()).collect();
+    let Ok(slice_path) = ContractionPath::from_pairs(notation, &slice_shapes, &path.to_pairs(), cost_model) else {
+        return plan_from_path(notation, shapes, path.clone());
+    };
+
+    let num_slices: u64 = sliced.iter().map(|&(_, size)| size as u64).product();
+    let mut scaled = ContractionPath::with_capacity(slice_path.len());
+    for step in slice_path.steps() {
+        let mut step = step.clone();
+        step.estimated_flops = step.estimated_flops.saturating_mul(num_slices);
+        scaled.push(step);
+    }
+
+    let mut plan = plan_from_path(notation, shapes, scaled)?;
+    plan.sliced_indices = sliced;
+    Ok(plan)
+}
+
+/// Creates an execution plan that follows `path` instead of searching one.
+///
+/// This is how paths found elsewhere (see [`ContractionPath::from_pairs`])
+/// are executed. The path must be for the notation with its ellipsis
+/// expanded, as produced by the path search or the importers. Fast paths are
+/// not considered.
+///
+/// Returns an error if the shapes do not fit an ellipsis of `notation`.
+pub fn plan_from_path(
+    notation: &EinsumNotation,
+    shapes: &[&[usize]],
+    path: ContractionPath,
+) -> EinsumResult<ExecutionPlan> {
+    if notation.has_ellipsis() {
+        let ellipsis_dims = validate_shapes(notation, shapes)?.ellipsis_dims;
+        return plan_from_path(&notation.expand_ellipsis(ellipsis_dims), shapes, path);
+    }
+
+    let n = notation.num_inputs();
     let output_shape = compute_output_shape(notation, shapes);
 
     // Extract input indices from notation for the executor
@@ -220,7 +798,79 @@ pub fn create_plan(
         .map(|s| s.named_indices().collect())
         .collect();
 
-    ExecutionPlan::from_contraction_path(path, output_shape, input_indices)
+    let output_indices: Vec<char> = notation.output().named_indices().collect();
+
+    if n == 1 {
+        let steps = unary_steps(&input_indices[0], &output_indices);
+        return Ok(ExecutionPlan {
+            steps,
+            total_flops: shapes[0].iter().product::<usize>() as u64,
+            output_shape,
+            uses_fast_path: false,
+            input_indices,
+            sliced_indices: Vec::new(),
+            proven_optimal: false,
+        });
+    }
+
+    let mut plan = ExecutionPlan::from_contraction_path(path, output_shape, input_indices);
+
+    // The last contraction writes the einsum output, so its result must be
+    // laid out in output order rather than the order the path search chose
+    if let Some(ExecutionStep::Contraction { result, .. }) = plan.steps.last_mut()
+        && result.len() == output_indices.len()
+        && result.iter().all(|c| output_indices.contains(c))
+    {
+        *result = output_indices;
+    }
+
+    Ok(plan)
+}
+
+/// Builds the steps of a single-input expression without a fast path:
+/// a sum over the indices missing from the output, then a permutation.
+///
+/// Repeated indices (diagonals) cannot be expressed with these steps, so
+/// no steps are returned for them.
+fn unary_steps(input_indices: &[char], output_indices: &[char]) -> Vec<ExecutionStep> {
+    let mut steps = Vec::new();
+
+    let has_repeats = input_indices
+        .iter()
+        .enumerate()
+        .any(|(pos, c)| input_indices[..pos].contains(c));
+    if has_repeats {
+        return steps;
+    }
+
+    let axes: Vec<usize> = input_indices
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| !output_indices.contains(c))
+        .map(|(pos, _)| pos)
+        .collect();
+    if !axes.is_empty() {
+        steps.push(ExecutionStep::Reduction {
+            input: 0,
+            axes,
+            op: ReductionOp::Sum,
+        });
+    }
+
+    let remaining: Vec<char> = input_indices
+        .iter()
+        .copied()
+        .filter(|c| output_indices.contains(c))
+        .collect();
+    if remaining != output_indices {
+        let perm = output_indices
+            .iter()
+            .map(|c| remaining.iter().position(|r| r == c).expect("output index in input"))
+            .collect();
+        steps.push(ExecutionStep::Permutation { input: 0, perm });
+    }
+
+    steps
 }
 
 /// Computes the output shape from notation and input shapes.
@@ -327,13 +977,38 @@ fn estimate_fast_path_flops(fast_path: &FastPath, shapes: &[&[usize]]) -> u64 {
 mod tests {
     use super::*;
     use crate::notation::parse_einsum;
+    use crate::optimization::Objective;
+
+    #[test]
+    fn test_workspace_sizing_chain() {
+        let notation = parse_einsum("ij,jk,kl->il").unwrap();
+        let shapes: &[&[usize]] = &[&[2, 30], &[30, 40], &[40, 5]];
+
+        let plan = create_plan(&notation, shapes, ContractionStrategy::Optimal).unwrap();
+        let sizing = plan.workspace_sizing(shapes);
+
+        // One intermediate between the two contractions; the last step
+        // writes the output directly
+        assert_eq!(sizing.intermediate_shapes.len(), 1);
+        let elements: usize = sizing.intermediate_shapes[0].iter().product();
+        assert_eq!(sizing.peak_elements, elements);
+    }
+
+    #[test]
+    fn test_workspace_sizing_fast_path() {
+        let notation = parse_einsum("ij,jk->ik").unwrap();
+        let shapes: &[&[usize]] = &[&[10, 20], &[20, 30]];
+
+        let plan = create_plan(&notation, shapes, ContractionStrategy::Auto).unwrap();
+        assert_eq!(plan.workspace_sizing(shapes), WorkspaceSizing::default());
+    }
 
     #[test]
     fn test_create_plan_matmul() {
         let notation = parse_einsum("ij,jk->ik").unwrap();
         let shapes: &[&[usize]] = &[&[100, 200], &[200, 300]];
 
-        let plan = create_plan(&notation, shapes, ContractionStrategy::Auto);
+        let plan = create_plan(&notation, shapes, ContractionStrategy::Auto).unwrap();
 
         assert!(plan.uses_fast_path());
         assert_eq!(plan.output_shape(), &[100, 300]);
@@ -344,10 +1019,322 @@ mod tests {
         let notation = parse_einsum("ij,jk,kl->il").unwrap();
         let shapes: &[&[usize]] = &[&[10, 20], &[20, 30], &[30, 40]];
 
-        let plan = create_plan(&notation, shapes, ContractionStrategy::Auto);
+        let plan = create_plan(&notation, shapes, ContractionStrategy::Auto).unwrap();
 
         // Chain of 3 doesn't match fast path, should use contraction
         assert!(!plan.uses_fast_path());
         assert_eq!(plan.num_steps(), 2);
     }
+
+    #[test]
+    fn test_create_plan_final_step_in_output_order() {
+        let notation = parse_einsum("ij,jk,kl->il").unwrap();
+        let shapes: &[&[usize]] = &[&[2, 3], &[3, 4], &[4, 5]];
+
+        for strategy in [
+            ContractionStrategy::Greedy,
+            ContractionStrategy::Optimal,
+            ContractionStrategy::branch_bound(),
+            ContractionStrategy::Explicit(vec![(1, 2), (0, 1)]),
+        ] {
+            let plan = create_plan(&notation, shapes, strategy.clone()).unwrap();
+
+            match plan.steps().last() {
+                Some(ExecutionStep::Contraction { result, .. }) => {
+                    assert_eq!(result, &vec!['i', 'l'], "{:?}", strategy);
+                }
+                other => panic!("expected contraction, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_create_plan_expands_ellipsis() {
+        let notation = parse_einsum("...ij,...jk,...kl->...il").unwrap();
+        let shapes: &[&[usize]] = &[&[2, 2, 3], &[2, 3, 4], &[2, 4, 5]];
+
+        let plan = create_plan(&notation, shapes, ContractionStrategy::Greedy).unwrap();
+
+        assert_eq!(plan.input_indices()[0], vec!['\u{2460}', 'i', 'j']);
+        assert_eq!(plan.output_shape(), &[2, 2, 5]);
+    }
+
+    #[test]
+    fn test_create_plan_rejects_ellipsis_mismatch() {
+        let notation = parse_einsum("...ij,...jk,...kl->...il").unwrap();
+        let shapes: &[&[usize]] = &[&[2, 3, 4], &[5, 4, 6], &[2, 6, 3]];
+        let valid: &[&[usize]] = &[&[2, 3, 4], &[2, 4, 6], &[2, 6, 3]];
+        let path = ContractionPath::from_pairs(
+            &notation.expand_ellipsis(1),
+            valid,
+            &[(0, 1), (0, 1)],
+            &CostModel::default(),
+        )
+        .unwrap();
+
+        // Paths are not built for shapes that do not fit the notation
+        assert!(
+            ContractionPath::from_pairs(&notation.expand_ellipsis(1), shapes, &[(0, 1), (0, 1)], &CostModel::default())
+                .is_err()
+        );
+
+        assert!(create_plan(&notation, shapes, ContractionStrategy::Auto).is_err());
+        assert!(plan_from_path(&notation, shapes, path).is_err());
+    }
+
+    #[test]
+    fn test_create_plan_single_input() {
+        let notation = parse_einsum("ijk->ki").unwrap();
+        let plan = create_plan(&notation, &[&[2, 3, 4]], ContractionStrategy::Auto).unwrap();
+
+        assert!(!plan.uses_fast_path());
+        match plan.steps() {
+            [
+                ExecutionStep::Reduction { input: 0, axes, op: ReductionOp::Sum },
+                ExecutionStep::Permutation { input: 0, perm },
+            ] => {
+                assert_eq!(axes, &vec![1]);
+                assert_eq!(perm, &vec![1, 0]);
+            }
+            other => panic!("unexpected steps {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_create_plan_explicit() {
+        let notation = parse_einsum("ij,jk,kl->il").unwrap();
+        let shapes: &[&[usize]] = &[&[2, 30], &[30, 40], &[40, 5]];
+
+        // The more expensive order: (jk,kl) first
+        let strategy = ContractionStrategy::Explicit(vec![(1, 2), (0, 1)]);
+        assert!(strategy.validate(&notation, shapes).is_ok());
+        let plan = create_plan(&notation, shapes, strategy).unwrap();
+
+        match plan.steps() {
+            [
+                ExecutionStep::Contraction { inputs: (1, 2), contracted: first, result: jl, flops: first_flops },
+                ExecutionStep::Contraction { inputs: (0, 1), flops: second_flops, .. },
+            ] => {
+                assert_eq!(first, &vec!['k']);
+                assert_eq!(jl, &vec!['j', 'l']);
+                assert_eq!(*first_flops, 2 * 30 * 40 * 5);
+                assert_eq!(*second_flops, 2 * 2 * 30 * 5);
+            }
+            other => panic!("unexpected steps {:?}", other),
+        }
+        assert!(plan.total_flops() > create_plan(&notation, shapes, ContractionStrategy::Optimal).unwrap().total_flops());
+    }
+
+    #[test]
+    fn test_explicit_invalid_path() {
+        let notation = parse_einsum("ij,jk,kl->il").unwrap();
+        let shapes: &[&[usize]] = &[&[2, 3], &[3, 4], &[4, 5]];
+
+        for pairs in [vec![(0, 0), (0, 1)], vec![(0, 3)], vec![(0, 1)], vec![(0, 1), (0, 1), (0, 1)]] {
+            let strategy = ContractionStrategy::Explicit(pairs);
+            assert!(strategy.validate(&notation, shapes).is_err(), "{:?}", strategy);
+            assert!(create_plan(&notation, shapes, strategy).is_err());
+        }
+
+        // Also when a fast path would replace the path
+        let notation = parse_einsum("ij,jk->ik").unwrap();
+        let strategy = ContractionStrategy::Explicit(vec![(0, 2)]);
+        assert!(create_plan(&notation, &[&[2, 3], &[3, 4]], strategy).is_err());
+    }
+
+    #[test]
+    fn test_create_plan_random_greedy() {
+        let notation = parse_einsum("ij,jk,kl,lm->im").unwrap();
+        let shapes: &[&[usize]] = &[&[2, 30], &[30, 40], &[40, 5], &[5, 7]];
+        let strategy = ContractionStrategy::RandomGreedy { trials: 16, temperature: 0.5, seed: 3 };
+
+        let plan = create_plan(&notation, shapes, strategy.clone()).unwrap();
+
+        assert_eq!(plan.num_steps(), 3);
+        assert_eq!(plan.output_shape(), &[2, 7]);
+        assert!(plan.total_flops() <= create_plan(&notation, shapes, ContractionStrategy::Greedy).unwrap().total_flops());
+        assert_eq!(create_plan(&notation, shapes, strategy).unwrap(), plan);
+    }
+
+    #[test]
+    fn test_create_plan_large_network() {
+        // A ring of 40 tensors, where `Auto` partitions and refines the result
+        let letters: Vec<char> = ('a'..='z').chain('A'..='N').collect();
+        let terms: Vec<alloc::string::String> = (0..40)
+            .map(|i| [letters[i], letters[(i + 1) % 40]].iter().collect())
+            .collect();
+        let notation = parse_einsum(&alloc::format!("{}->", terms.join(","))).unwrap();
+        let shapes = vec![vec![3, 3]; 40];
+        let shapes: Vec<&[usize]> = shapes.iter().map(|s| s.as_slice()).collect();
+
+        let greedy = create_plan(&notation, &shapes, ContractionStrategy::Greedy).unwrap();
+        for strategy in [ContractionStrategy::Partition { trials: 2, seed: 0 }, ContractionStrategy::Auto] {
+            let plan = create_plan(&notation, &shapes, strategy).unwrap();
+            assert_eq!(plan.num_steps(), 39);
+            assert!(plan.total_flops() <= greedy.total_flops());
+        }
+    }
+
+    #[test]
+    fn test_create_plan_connected_optimal() {
+        // A ring of 16 tensors, past the reach of `optimal_path`
+        let letters: Vec<char> = ('a'..='p').collect();
+        let terms: Vec<alloc::string::String> = (0..16)
+            .map(|i| [letters[i], letters[(i + 1) % 16]].iter().collect())
+            .collect();
+        let notation = parse_einsum(&alloc::format!("{}->", terms.join(","))).unwrap();
+        let shapes: Vec<Vec<usize>> = (0..16).map(|i| vec![2 + i % 3, 2 + (i + 1) % 16 % 3]).collect();
+        let shapes: Vec<&[usize]> = shapes.iter().map(|s| s.as_slice()).collect();
+
+        let greedy = create_plan(&notation, &shapes, ContractionStrategy::Greedy).unwrap();
+        let connected = create_plan(&notation, &shapes, ContractionStrategy::ConnectedOptimal { outer_products: false }).unwrap();
+
+        assert_eq!(connected.num_steps(), 15);
+        assert!(connected.total_flops() <= greedy.total_flops());
+        // `Optimal` does not switch to the connected search on its own
+        assert_eq!(create_plan(&notation, &shapes, ContractionStrategy::Optimal).unwrap(), greedy);
+        assert!(!connected.proven_optimal());
+    }
+
+    #[test]
+    fn test_create_plan_proven_optimal() {
+        let notation = parse_einsum("ij,jk,kl,lm->im").unwrap();
+        let shapes: &[&[usize]] = &[&[2, 30], &[30, 40], &[40, 5], &[5, 7]];
+        let proven = |strategy| create_plan(&notation, shapes, strategy).unwrap().proven_optimal();
+
+        assert!(proven(ContractionStrategy::Optimal));
+        assert!(proven(ContractionStrategy::ConnectedOptimal { outer_products: true }));
+        assert!(proven(ContractionStrategy::branch_bound()));
+        assert!(proven(ContractionStrategy::Auto));
+        assert!(!proven(ContractionStrategy::Greedy));
+        assert!(!proven(ContractionStrategy::ConnectedOptimal { outer_products: false }));
+        assert!(!proven(ContractionStrategy::BranchBound { max_nodes: 1, max_depth: DEFAULT_MAX_DEPTH, time_limit: None }));
+
+        // One best sub-path per subset is not enough when sizes and FLOPs mix
+        let weighted = CostModel::default().with_objective(Objective::Weighted { flops: 1, size: 10, write: 0 });
+        let proven_weighted =
+            |strategy| create_plan_with_cost_model(&notation, shapes, strategy, &weighted).unwrap().proven_optimal();
+        assert!(!proven_weighted(ContractionStrategy::Optimal));
+        assert!(!proven_weighted(ContractionStrategy::ConnectedOptimal { outer_products: true }));
+        assert!(!proven_weighted(ContractionStrategy::Auto));
+
+        // Two operands can only be contracted one way
+        let matmul = parse_einsum("ij,jk->ik").unwrap();
+        assert!(create_plan(&matmul, &[&[2, 3], &[3, 4]], ContractionStrategy::Greedy).unwrap().proven_optimal());
+    }
+
+    #[test]
+    fn test_create_plan_sliced() {
+        // Every order creates an intermediate of at least 80 elements
+        let notation = parse_einsum("ia,ib,ab->").unwrap();
+        let shapes: &[&[usize]] = &[&[8, 10], &[8, 10], &[10, 10]];
+        let cost_model = CostModel::default().with_memory_limit(10);
+
+        let unsliced = create_plan(&notation, shapes, ContractionStrategy::Optimal).unwrap();
+        let plan = create_plan_with_cost_model(&notation, shapes, ContractionStrategy::Optimal, &cost_model).unwrap();
+
+        assert!(unsliced.sliced_indices().is_empty());
+        assert_eq!(unsliced.num_slices(), 1);
+        assert_eq!(plan.sliced_indices().len(), 1);
+        assert_eq!(plan.num_slices(), plan.sliced_indices()[0].1);
+        assert_eq!(plan.output_shape(), unsliced.output_shape());
+        assert!(plan.total_flops() >= unsliced.total_flops());
+
+        let sizing = plan.workspace_sizing(shapes);
+        assert!(sizing.intermediate_shapes.iter().all(|s| s.iter().product::<usize>() <= 10));
+
+        // Slices copy the inputs holding the sliced index and sum through a
+        // scalar partial output
+        let (index, _) = plan.sliced_indices()[0];
+        let holders = notation.inputs().iter().filter(|s| s.contains(index)).count();
+        assert_eq!(sizing.slice_input_shapes.len(), holders);
+        assert_eq!(sizing.partial_elements, 1);
+        let copies: usize = sizing.slice_input_shapes.iter().map(|s| s.iter().product::<usize>()).sum();
+        assert!(sizing.peak_elements > copies);
+        assert!(sizing.peak_elements <= 10 + copies + 1);
+        assert!(plan.validate_slicing(shapes).is_ok());
+    }
+
+    #[test]
+    fn test_validate_slicing() {
+        let notation = parse_einsum("ia,ib,ab->").unwrap();
+        let shapes: &[&[usize]] = &[&[8, 10], &[8, 10], &[10, 10]];
+        let plan = create_plan(&notation, shapes, ContractionStrategy::Optimal).unwrap();
+        let sliced = |sliced_indices: Vec<(char, usize)>| ExecutionPlan { sliced_indices, ..plan.clone() };
+
+        assert!(sliced(vec![('a', 10)]).validate_slicing(shapes).is_ok());
+        assert!(sliced(vec![('a', 10), ('i', 8)]).validate_slicing(shapes).is_ok());
+
+        // Size 0 would run no slice at all
+        let empty: &[&[usize]] = &[&[8, 0], &[8, 10], &[0, 10]];
+        assert!(sliced(vec![('a', 0)]).validate_slicing(empty).is_err());
+        // Wrong size, unknown index, repeated index, wrong number of inputs
+        assert!(sliced(vec![('a', 4)]).validate_slicing(shapes).is_err());
+        assert!(sliced(vec![('z', 4)]).validate_slicing(shapes).is_err());
+        assert!(sliced(vec![('a', 10), ('a', 10)]).validate_slicing(shapes).is_err());
+        assert!(sliced(vec![('a', 10)]).validate_slicing(&shapes[..2]).is_err());
+    }
+
+    #[test]
+    fn test_strategy_hash_matches_eq() {
+        use core::hash::BuildHasher;
+
+        let builder = hashbrown::DefaultHashBuilder::default();
+        let hash = |strategy: &ContractionStrategy| builder.hash_one(strategy);
+        let a = ContractionStrategy::RandomGreedy { trials: 8, temperature: 0.5, seed: 1 };
+        let b = ContractionStrategy::RandomGreedy { trials: 8, temperature: 0.5, seed: 1 };
+
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+        assert_ne!(a, ContractionStrategy::RandomGreedy { trials: 8, temperature: 0.25, seed: 1 });
+        assert_ne!(a, ContractionStrategy::RandomGreedy { trials: 8, temperature: 0.5, seed: 2 });
+        assert_ne!(a, ContractionStrategy::Greedy);
+        assert_eq!(ContractionStrategy::Greedy, ContractionStrategy::Greedy);
+
+        // Temperatures are compared by their bits
+        let zero = ContractionStrategy::RandomGreedy { trials: 8, temperature: 0.0, seed: 1 };
+        let negative_zero = ContractionStrategy::RandomGreedy { trials: 8, temperature: -0.0, seed: 1 };
+        assert_ne!(zero, negative_zero);
+
+        let nan = ContractionStrategy::RandomGreedy { trials: 8, temperature: f64::NAN, seed: 1 };
+        assert_eq!(nan, nan.clone());
+        assert_eq!(hash(&nan), hash(&nan.clone()));
+    }
+
+    #[test]
+    fn test_invalid_temperature() {
+        let notation = parse_einsum("ij,jk,kl->il").unwrap();
+        let shapes: &[&[usize]] = &[&[2, 3], &[3, 4], &[4, 5]];
+
+        for temperature in [f64::NAN, f64::INFINITY, -1.0] {
+            let strategy = ContractionStrategy::RandomGreedy { trials: 8, temperature, seed: 0 };
+            let err = strategy.validate(&notation, shapes).unwrap_err();
+            assert!(matches!(err, EinsumError::ParseError { .. }), "{}", temperature);
+            assert!(create_plan(&notation, shapes, strategy).is_err(), "{}", temperature);
+        }
+
+        // Also rejected when a fast path is used
+        let strategy = ContractionStrategy::RandomGreedy { trials: 8, temperature: f64::NAN, seed: 0 };
+        assert!(create_plan(&parse_einsum("ij,jk->ik").unwrap(), &[&[2, 3], &[3, 4]], strategy).is_err());
+
+        let strategy = ContractionStrategy::RandomGreedy { trials: 8, temperature: 0.0, seed: 0 };
+        assert!(strategy.validate(&notation, shapes).is_ok());
+    }
+
+    #[test]
+    fn test_serde_roundtrip() {
+        let cases: [(&str, &[&[usize]]); 2] = [
+            ("ij,jk,kl->il", &[&[2, 3], &[3, 4], &[4, 5]]),
+            ("bij,bjk->bik", &[&[2, 3, 4], &[2, 4, 5]]),
+        ];
+
+        for (notation, shapes) in cases {
+            let notation = parse_einsum(notation).unwrap();
+            let plan = create_plan(&notation, shapes, ContractionStrategy::Auto).unwrap();
+
+            let json = serde_json::to_string(&plan).unwrap();
+            let decoded: ExecutionPlan = serde_json::from_str(&json).unwrap();
+            assert_eq!(decoded, plan);
+        }
+    }
 }
@@ -0,0 +1,379 @@
+//! Local refinement of contraction trees by subtree reconfiguration.
+//!
+//! Any contraction of the tree, with the subtrees below it cut at a few
+//! tensors, is a small network of its own: its inputs are the tensors at
+//! the cut and its output is the result of the contraction. Contracting it
+//! in another order leaves the rest of the tree unchanged, so each such
+//! network can be re-contracted with the DP optimizer and replaced when that
+//! is cheaper. This is cotengra's `subtree_reconfigure`.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use hashbrown::HashMap;
+
+use super::cost::{ContractionCost, CostModel};
+use super::dynamic::{optimal_path, MAX_DP_TENSORS};
+use super::path::{ContractionPath, ContractionStep};
+use crate::error::EinsumResult;
+use crate::notation::validation::expanded_dims;
+use crate::notation::{EinsumNotation, Subscript};
+
+/// Maximum passes over the tree.
+const MAX_RECONFIGURE_PASSES: usize = 8;
+
+/// Improves `path` by re-optimizing its subtrees of up to `subtree_size`
+/// tensors.
+///
+/// Every pass visits the contractions from the most to the least
+/// expensive, grows a subtree below each by expanding its most expensive
+/// contraction until `subtree_size` tensors are at the cut, and replaces the
+/// subtree with its optimal contraction when that is cheaper, preferring
+/// intermediates that fit the cost model's memory limit. Passes repeat until
+/// one finds no improvement.
+///
+/// With the `Size` and `Weighted` objectives, a subtree with a smaller
+/// largest intermediate can still raise the cost of the whole path, so
+/// `path` is returned when the refined path costs more.
+///
+/// `subtree_size` is capped at 12: each subtree takes O(3^subtree_size).
+/// A `path` that does not contract all operands into one is returned as is.
+/// Returns an error if the shapes are not valid for the notation.
+pub fn reconfigure_path(
+    notation: &EinsumNotation,
+    shapes: &[&[usize]],
+    path: &ContractionPath,
+    cost_model: &CostModel,
+    subtree_size: usize,
+) -> EinsumResult<ContractionPath> {
+    let n = notation.num_inputs();
+    let subtree_size = subtree_size.min(MAX_DP_TENSORS);
+    if n < 3 || subtree_size < 3 || path.to_tree(n).is_none() {
+        return Ok(path.clone());
+    }
+    // Imported paths may have no result sizes
+    let path = &path.recompute_costs(notation, shapes, cost_model)?;
+
+    let mut tree = Tree::new(notation, shapes, path, cost_model)?;
+    let mut improved = false;
+    for _ in 0..MAX_RECONFIGURE_PASSES {
+        if !tree.pass(subtree_size) {
+            break;
+        }
+        improved = true;
+    }
+
+    if !improved {
+        return Ok(path.clone());
+    }
+    let refined = ContractionPath::from_ssa(notation, shapes, &tree.to_ssa(n), cost_model).unwrap_or_else(|_| path.clone());
+
+    // Subtrees are compared on their own, but sizes combine by maximum over
+    // the whole path, so a smaller local intermediate can cost more FLOPs
+    // without lowering the largest one
+    let rank = |path: &ContractionPath| {
+        (!cost_model.fits_memory_limit(path.largest_intermediate()), path.total_cost(cost_model))
+    };
+    if rank(&refined) > rank(path) {
+        return Ok(path.clone());
+    }
+    Ok(refined)
+}
+
+/// Contraction tree with the indices and cost of every node.
+///
+/// Nodes are the inputs, then the contractions. Replaced contractions stay
+/// in the arrays, unreachable from the root.
+struct Tree<'a> {
+    shapes: &'a [&'a [usize]],
+    cost_model: &'a CostModel,
+    dims: HashMap<char, usize>,
+    indices: Vec<Vec<char>>,
+    children: Vec<Option<(usize, usize)>>,
+    /// Cost of the contraction creating each node.
+    costs: Vec<ContractionCost>,
+    alive: Vec<bool>,
+    /// Whether the subtree below each node is unchanged since it last failed
+    /// to improve.
+    settled: Vec<bool>,
+    parents: Vec<Option<usize>>,
+    root: usize,
+}
+
+impl<'a> Tree<'a> {
+    fn new(
+        notation: &EinsumNotation,
+        shapes: &'a [&'a [usize]],
+        path: &ContractionPath,
+        cost_model: &'a CostModel,
+    ) -> EinsumResult<Self> {
+        let n = notation.num_inputs();
+        let (expanded, dims) = expanded_dims(notation, shapes)?;
+        let mut tree = Tree {
+            shapes,
+            cost_model,
+            dims,
+            indices: expanded.inputs().iter().map(|s| s.named_indices().collect()).collect(),
+            children: vec![None; n],
+            costs: vec![ContractionCost::zero(); n],
+            alive: vec![true; n],
+            settled: vec![false; n],
+            parents: vec![None; n],
+            root: 0,
+        };
+
+        for ((a, b), step) in path.to_ssa(n).into_iter().zip(path.steps()) {
+            tree.root = tree.push(a, b, step);
+        }
+
+        Ok(tree)
+    }
+
+    fn push(&mut self, a: usize, b: usize, step: &ContractionStep) -> usize {
+        self.indices.push(step.result_indices.clone());
+        self.children.push(Some((a, b)));
+        self.costs.push(self.step_cost(step));
+        self.alive.push(true);
+        self.settled.push(false);
+        self.parents.push(None);
+
+        let id = self.indices.len() - 1;
+        self.parents[a] = Some(id);
+        self.parents[b] = Some(id);
+        id
+    }
+
+    fn step_cost(&self, step: &ContractionStep) -> ContractionCost {
+        self.cost_model.cost(step.estimated_flops, step.estimated_memory, step.result_size)
+    }
+
+    fn size(&self, node: usize) -> u64 {
+        self.indices[node]
+            .iter()
+            .map(|c| self.dims.get(c).copied().unwrap_or(1) as u64)
+            .product()
+    }
+
+    /// Reconfigures every contraction once, returning whether any improved.
+    fn pass(&mut self, subtree_size: usize) -> bool {
+        let mut order: Vec<usize> = (0..self.children.len())
+            .filter(|&node| self.alive[node] && !self.settled[node] && self.children[node].is_some())
+            .collect();
+        order.sort_by(|&a, &b| self.costs[b].cmp(&self.costs[a]));
+
+        let mut improved = false;
+        for node in order {
+            // Earlier replacements may have removed the node
+            if self.alive[node] {
+                let changed = self.reconfigure(node, subtree_size);
+                self.settled[node] = !changed;
+                improved |= changed;
+            }
+        }
+        improved
+    }
+
+    /// Replaces the subtree below `node` by its optimal contraction when
+    /// that is cheaper.
+    fn reconfigure(&mut self, node: usize, subtree_size: usize) -> bool {
+        let Some((left, right)) = self.children[node] else {
+            return false;
+        };
+
+        // Cut below the most expensive contractions first
+        let mut cut = vec![left, right];
+        let mut interior = vec![node];
+        while cut.len() < subtree_size {
+            let expand = (0..cut.len())
+                .filter(|&i| self.children[cut[i]].is_some())
+                .max_by(|&i, &j| self.costs[cut[i]].cmp(&self.costs[cut[j]]));
+            let Some(i) = expand else { break };
+
+            let expanded = cut.swap_remove(i);
+            let (a, b) = self.children[expanded].unwrap();
+            cut.extend([a, b]);
+            interior.push(expanded);
+        }
+        if cut.len() < 3 {
+            return false;
+        }
+
+        let rank = |largest: u64, cost: ContractionCost| (!self.cost_model.fits_memory_limit(largest), cost);
+        let old_largest = interior[1..].iter().map(|&u| self.size(u)).max().unwrap_or(0);
+        let old_cost = interior
+            .iter()
+            .fold(ContractionCost::zero(), |acc, &u| acc + self.costs[u]);
+
+        let inputs = cut.iter().map(|&u| Subscript::from_chars(self.indices[u].iter().copied())).collect();
+        let part = EinsumNotation::new(inputs, Subscript::from_chars(self.indices[node].iter().copied()));
+        let shapes: Vec<Vec<usize>> = cut
+            .iter()
+            .map(|&u| match self.children[u] {
+                None => self.shapes[u].to_vec(),
+                Some(_) => self.indices[u].iter().map(|c| self.dims[c]).collect(),
+            })
+            .collect();
+        let shapes: Vec<&[usize]> = shapes.iter().map(|s| s.as_slice()).collect();
+
+        let path = optimal_path(&part, &shapes, self.cost_model);
+        if path.len() != cut.len() - 1
+            || rank(path.largest_intermediate(), path.total_cost(self.cost_model)) >= rank(old_largest, old_cost)
+        {
+            return false;
+        }
+
+        for &u in &interior[1..] {
+            self.alive[u] = false;
+        }
+
+        // Local SSA ids are the tensors at the cut, then the new contractions;
+        // the last one is `node` itself, whose indices are unchanged
+        let mut ids = cut;
+        let ssa = path.to_ssa(ids.len());
+        let (last, rest) = path.steps().split_last().unwrap();
+        for (&(a, b), step) in ssa.iter().zip(rest) {
+            let id = self.push(ids[a], ids[b], step);
+            ids.push(id);
+        }
+        let (a, b) = ssa[ssa.len() - 1];
+        self.children[node] = Some((ids[a], ids[b]));
+        self.parents[ids[a]] = Some(node);
+        self.parents[ids[b]] = Some(node);
+        self.costs[node] = self.step_cost(last);
+
+        // Subtrees of the ancestors changed too
+        let mut ancestor = self.parents[node];
+        while let Some(u) = ancestor {
+            self.settled[u] = false;
+            ancestor = self.parents[u];
+        }
+
+        true
+    }
+
+    /// Converts the tree to an SSA path, children before their parent.
+    fn to_ssa(&self, num_inputs: usize) -> Vec<(usize, usize)> {
+        let mut ssa = Vec::with_capacity(num_inputs - 1);
+        let mut ids: HashMap<usize, usize> = HashMap::new();
+        let mut stack = vec![(self.root, false)];
+
+        while let Some((node, visited)) = stack.pop() {
+            let Some((a, b)) = self.children[node] else {
+                ids.insert(node, node);
+                continue;
+            };
+            if visited {
+                ssa.push((ids[&a], ids[&b]));
+                ids.insert(node, num_inputs + ssa.len() - 1);
+            } else {
+                stack.extend([(node, true), (b, false), (a, false)]);
+            }
+        }
+
+        ssa
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_einsum;
+    use crate::optimization::greedy_path;
+
+    fn chain(n: usize) -> (EinsumNotation, Vec<Vec<usize>>) {
+        let letters: Vec<char> = ('a'..='z').collect();
+        let inputs: Vec<String> = (0..n).map(|i| format!("{}{}", letters[i], letters[i + 1])).collect();
+        let expr = format!("{}->{}{}", inputs.join(","), letters[0], letters[n]);
+        let shapes = (0..n).map(|i| vec![2 + (i * 7) % 13, 2 + ((i + 1) * 7) % 13]).collect();
+        (parse_einsum(&expr).unwrap(), shapes)
+    }
+
+    #[test]
+    fn test_reconfigure_finds_optimal_small_network() {
+        let (notation, shapes) = chain(6);
+        let shapes: Vec<&[usize]> = shapes.iter().map(|s| s.as_slice()).collect();
+        let model = CostModel::default();
+
+        // Right to left is a poor order for this chain
+        let pairs = [(4, 5), (3, 4), (2, 3), (1, 2), (0, 1)];
+        let start = ContractionPath::from_pairs(&notation, &shapes, &pairs, &model).unwrap();
+        let refined = reconfigure_path(&notation, &shapes, &start, &model, 8).unwrap();
+        let optimal = optimal_path(&notation, &shapes, &model);
+
+        assert!(start.total_cost(&model) > optimal.total_cost(&model));
+        assert_eq!(refined.total_cost(&model), optimal.total_cost(&model));
+        assert_eq!(refined.len(), 5);
+    }
+
+    #[test]
+    fn test_reconfigure_never_worsens() {
+        let (notation, shapes) = chain(20);
+        let shapes: Vec<&[usize]> = shapes.iter().map(|s| s.as_slice()).collect();
+        let model = CostModel::default();
+
+        let greedy = greedy_path(&notation, &shapes, &model);
+        for size in [3, 4, 6, 8] {
+            let refined = reconfigure_path(&notation, &shapes, &greedy, &model, size).unwrap();
+            assert!(refined.total_cost(&model) <= greedy.total_cost(&model));
+            assert_eq!(refined.len(), 19);
+        }
+
+        // Refining a refined path changes nothing
+        let refined = reconfigure_path(&notation, &shapes, &greedy, &model, 8).unwrap();
+        let again = reconfigure_path(&notation, &shapes, &refined, &model, 8).unwrap();
+        assert_eq!(again.total_cost(&model), refined.total_cost(&model));
+    }
+
+    #[test]
+    fn test_reconfigure_never_worsens_size_objectives() {
+        use crate::optimization::Objective;
+
+        // Shrinking a subtree's largest intermediate costs FLOPs, but a larger
+        // one is created elsewhere
+        let cases: [(&str, &[&[usize]], Objective); 2] = [
+            (
+                "ab,bc,cd,de,ef->af",
+                &[&[13, 40], &[40, 11], &[11, 40], &[40, 11], &[11, 6]],
+                Objective::Weighted { flops: 1, size: 10, write: 0 },
+            ),
+            (
+                "ab,bc,cd,de,ef,fg->ag",
+                &[&[90, 70], &[70, 2], &[2, 90], &[90, 11], &[11, 2], &[2, 1]],
+                Objective::Size,
+            ),
+        ];
+
+        for (expr, shapes, objective) in cases {
+            let notation = parse_einsum(expr).unwrap();
+            let model = CostModel::default().with_objective(objective);
+            // Right to left
+            let pairs: Vec<(usize, usize)> = (1..shapes.len()).rev().map(|i| (i - 1, i)).collect();
+            let start = ContractionPath::from_pairs(&notation, shapes, &pairs, &model).unwrap();
+
+            let refined = reconfigure_path(&notation, shapes, &start, &model, 3).unwrap();
+            assert!(refined.total_cost(&model) <= start.total_cost(&model), "{:?}", objective);
+        }
+    }
+
+    #[test]
+    fn test_reconfigure_improves_large_chain() {
+        let (notation, shapes) = chain(24);
+        let shapes: Vec<&[usize]> = shapes.iter().map(|s| s.as_slice()).collect();
+        let model = CostModel::default();
+
+        let pairs = vec![(0, 1); 23];
+        let start = ContractionPath::from_pairs(&notation, &shapes, &pairs, &model).unwrap();
+        let refined = reconfigure_path(&notation, &shapes, &start, &model, 6).unwrap();
+
+        assert!(refined.total_cost(&model) < start.total_cost(&model));
+    }
+
+    #[test]
+    fn test_reconfigure_keeps_incomplete_path() {
+        let notation = parse_einsum("ij,jk,kl->il").unwrap();
+        let shapes: &[&[usize]] = &[&[2, 3], &[3, 4], &[4, 5]];
+        let model = CostModel::default();
+
+        let path = ContractionPath::new();
+        assert!(reconfigure_path(&notation, shapes, &path, &model, 8).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,190 @@
+//! Index slicing to fit a memory limit.
+//!
+//! Fixing an index to one of its values and looping over them ("slicing",
+//! as in cotengra) divides every intermediate holding that index by its size.
+//! The sub-contractions of all slices are summed into the output, at the
+//! price of repeating the steps that do not hold the index.
+
+use alloc::vec::Vec;
+use hashbrown::HashMap;
+
+use super::cost::CostModel;
+use super::path::ContractionPath;
+use crate::error::EinsumResult;
+use crate::notation::validation::expanded_dims;
+use crate::notation::EinsumNotation;
+
+/// Chooses indices to slice so that every intermediate of `path` fits the
+/// memory limit of `cost_model`.
+///
+/// Indices are added one at a time from the largest intermediate, each time
+/// picking the one that adds the least cost over all slices. Output indices
+/// are never sliced, so the result of every slice is summed into the whole
+/// output. Returns the sliced indices with their sizes, empty if the path
+/// already fits, or `None` if no slicing makes it fit. Returns an error if
+/// the shapes are not valid for the notation.
+pub fn find_slicing(
+    notation: &EinsumNotation,
+    shapes: &[&[usize]],
+    path: &ContractionPath,
+    cost_model: &CostModel,
+) -> EinsumResult<Option<Vec<(char, usize)>>> {
+    let (expanded, dims) = expanded_dims(notation, shapes)?;
+    // Imported paths may have no result sizes
+    let path = path.recompute_costs(notation, shapes, cost_model)?;
+    Ok(slice_until_fits(&expanded, &dims, shapes, &path, cost_model))
+}
+
+/// Greedy search of [`find_slicing`] on the expanded notation.
+fn slice_until_fits(
+    expanded: &EinsumNotation,
+    dims: &HashMap<char, usize>,
+    shapes: &[&[usize]],
+    path: &ContractionPath,
+    cost_model: &CostModel,
+) -> Option<Vec<(char, usize)>> {
+    let output: Vec<char> = expanded.output().named_indices().collect();
+    let pairs = path.to_pairs();
+
+    let mut sliced: Vec<(char, usize)> = Vec::new();
+    let mut current = path.clone();
+
+    while !cost_model.fits_memory_limit(current.largest_intermediate()) {
+        let largest = current
+            .steps()
+            .iter()
+            .rev()
+            .skip(1)
+            .max_by_key(|step| step.result_size)?;
+
+        let (_, index, slice_path) = largest
+            .result_indices
+            .iter()
+            .filter_map(|&c| {
+                let size = dims.get(&c).copied().unwrap_or(1);
+                if size <= 1 || output.contains(&c) || sliced.iter().any(|&(s, _)| s == c) {
+                    return None;
+                }
+
+                let mut candidate = sliced.clone();
+                candidate.push((c, size));
+                let slice_shapes = slice_shapes(expanded, shapes, &candidate);
+                let slice_shapes: Vec<&[usize]> = slice_shapes.iter().map(|s| s.as_slice()).collect();
+                let slice_path = ContractionPath::from_pairs(expanded, &slice_shapes, &pairs, cost_model).ok()?;
+
+                let slices: u128 = candidate.iter().map(|&(_, size)| size as u128).product();
+                let score = (
+                    slices * slice_path.total_cost(cost_model).total as u128,
+                    slice_path.largest_intermediate(),
+                );
+                Some((score, (c, size), slice_path))
+            })
+            .min_by_key(|(score, _, _)| *score)?;
+        sliced.push(index);
+        current = slice_path;
+    }
+
+    Some(sliced)
+}
+
+/// Shapes of the inputs of one slice: sliced indices have size 1.
+///
+/// `notation` must have its ellipsis expanded.
+pub(crate) fn slice_shapes(
+    notation: &EinsumNotation,
+    shapes: &[&[usize]],
+    sliced: &[(char, usize)],
+) -> Vec<Vec<usize>> {
+    notation
+        .inputs()
+        .iter()
+        .zip(shapes)
+        .map(|(input, shape)| {
+            input
+                .named_indices()
+                .zip(shape.iter())
+                .map(|(c, &d)| if sliced.iter().any(|&(s, _)| s == c) { 1 } else { d })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_einsum;
+    use crate::optimization::{optimal_path, ContractionStep};
+
+    #[test]
+    fn test_fits_without_slicing() {
+        let notation = parse_einsum("ij,jk,kl->il").unwrap();
+        let shapes: &[&[usize]] = &[&[2, 30], &[30, 40], &[40, 5]];
+        let path = optimal_path(&notation, shapes, &CostModel::default());
+
+        assert_eq!(find_slicing(&notation, shapes, &path, &CostModel::default()).unwrap(), Some(Vec::new()));
+        let cost_model = CostModel::default().with_memory_limit(path.largest_intermediate());
+        assert_eq!(find_slicing(&notation, shapes, &path, &cost_model).unwrap(), Some(Vec::new()));
+    }
+
+    #[test]
+    fn test_slice_contracted_index() {
+        // Every order creates an intermediate of at least 80 elements
+        let notation = parse_einsum("ia,ib,ab->").unwrap();
+        let shapes: &[&[usize]] = &[&[8, 10], &[8, 10], &[10, 10]];
+        let path = optimal_path(&notation, shapes, &CostModel::default());
+        assert_eq!(path.largest_intermediate(), 80);
+
+        let cost_model = CostModel::default().with_memory_limit(10);
+        let sliced = find_slicing(&notation, shapes, &path, &cost_model).unwrap().unwrap();
+
+        assert_eq!(sliced.len(), 1);
+        let slice_shapes = slice_shapes(&notation, shapes, &sliced);
+        let slice_shapes: Vec<&[usize]> = slice_shapes.iter().map(|s| s.as_slice()).collect();
+        let slice_path = ContractionPath::from_pairs(&notation, &slice_shapes, &path.to_pairs(), &cost_model).unwrap();
+        assert!(slice_path.largest_intermediate() <= 10);
+    }
+
+    #[test]
+    fn test_path_without_sizes() {
+        let notation = parse_einsum("ia,ib,ab->").unwrap();
+        let shapes: &[&[usize]] = &[&[8, 10], &[8, 10], &[10, 10]];
+        let path = optimal_path(&notation, shapes, &CostModel::default());
+
+        let mut bare = ContractionPath::new();
+        for step in path.steps() {
+            bare.push(ContractionStep::new(
+                step.inputs,
+                step.contracted_indices.clone(),
+                step.result_indices.clone(),
+                step.estimated_flops,
+            ));
+        }
+        assert_eq!(bare.largest_intermediate(), 0);
+
+        let cost_model = CostModel::default().with_memory_limit(10);
+        assert_eq!(
+            find_slicing(&notation, shapes, &bare, &cost_model).unwrap(),
+            find_slicing(&notation, shapes, &path, &cost_model).unwrap()
+        );
+        assert_eq!(find_slicing(&notation, shapes, &bare, &cost_model).unwrap().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_output_indices_are_not_sliced() {
+        // The only intermediate, ij, holds output indices only
+        let notation = parse_einsum("i,j,ij->ij").unwrap();
+        let shapes: &[&[usize]] = &[&[10], &[10], &[10, 10]];
+        let path = ContractionPath::from_pairs(&notation, shapes, &[(0, 1), (0, 1)], &CostModel::default()).unwrap();
+
+        let cost_model = CostModel::default().with_memory_limit(50);
+        assert_eq!(find_slicing(&notation, shapes, &path, &cost_model).unwrap(), None);
+    }
+
+    #[test]
+    fn test_slice_shapes() {
+        let notation = parse_einsum("ij,jk->ik").unwrap();
+        let shapes: &[&[usize]] = &[&[2, 3], &[3, 4]];
+
+        assert_eq!(slice_shapes(&notation, shapes, &[('j', 3)]), [vec![2, 1], vec![1, 4]]);
+    }
+}
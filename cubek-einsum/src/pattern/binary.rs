@@ -1,6 +1,7 @@
 //! Binary operation pattern detection.
 
 use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
 
 use crate::notation::EinsumNotation;
 
@@ -20,12 +21,13 @@ pub fn is_hadamard(notation: &EinsumNotation) -> bool {
     let inputs = notation.inputs();
     let output = notation.output();
 
-    let indices_a: BTreeSet<char> = inputs[0].named_indices().collect();
-    let indices_b: BTreeSet<char> = inputs[1].named_indices().collect();
-    let indices_out: BTreeSet<char> = output.named_indices().collect();
+    let indices_a: Vec<char> = inputs[0].named_indices().collect();
+    let indices_b: Vec<char> = inputs[1].named_indices().collect();
+    let indices_out: Vec<char> = output.named_indices().collect();
 
-    // All three must have the same indices
-    indices_a == indices_b && indices_a == indices_out
+    // All three must have the same indices in the same order, since the
+    // kernel multiplies elements at the same flat position
+    !has_repeated_index(&indices_a) && indices_a == indices_b && indices_a == indices_out
 }
 
 /// Checks if the notation represents an outer product.
@@ -46,16 +48,16 @@ pub fn is_outer_product(notation: &EinsumNotation) -> bool {
 
     let indices_a: BTreeSet<char> = inputs[0].named_indices().collect();
     let indices_b: BTreeSet<char> = inputs[1].named_indices().collect();
-    let indices_out: BTreeSet<char> = output.named_indices().collect();
 
     // Inputs must be disjoint
     if !indices_a.is_disjoint(&indices_b) {
         return false;
     }
 
-    // Output must be union of inputs
-    let union: BTreeSet<char> = indices_a.union(&indices_b).copied().collect();
-    union == indices_out
+    // Output must be the concatenation of the inputs, in order
+    let concat: Vec<char> = inputs[0].named_indices().chain(inputs[1].named_indices()).collect();
+    let indices_out: Vec<char> = output.named_indices().collect();
+    !has_repeated_index(&concat) && concat == indices_out
 }
 
 /// Checks if the notation represents a dot product.
@@ -74,11 +76,12 @@ pub fn is_dot_product(notation: &EinsumNotation) -> bool {
 
     let inputs = notation.inputs();
 
-    let indices_a: BTreeSet<char> = inputs[0].named_indices().collect();
-    let indices_b: BTreeSet<char> = inputs[1].named_indices().collect();
+    let indices_a: Vec<char> = inputs[0].named_indices().collect();
+    let indices_b: Vec<char> = inputs[1].named_indices().collect();
 
-    // Both inputs must have the same indices
-    if indices_a != indices_b {
+    // Both inputs must have the same indices in the same order, since the
+    // kernel multiplies elements at the same flat position
+    if has_repeated_index(&indices_a) || indices_a != indices_b {
         return false;
     }
 
@@ -86,6 +89,11 @@ pub fn is_dot_product(notation: &EinsumNotation) -> bool {
     true
 }
 
+/// Returns true if an index appears more than once.
+fn has_repeated_index(indices: &[char]) -> bool {
+    indices.iter().enumerate().any(|(pos, c)| indices[..pos].contains(c))
+}
+
 /// Checks if the notation represents a bilinear form.
 ///
 /// `i,ij,j->` - vector-matrix-vector product.
@@ -122,6 +130,12 @@ mod tests {
         assert!(!is_hadamard(&notation));
     }
 
+    #[test]
+    fn test_not_hadamard_transposed_input() {
+        let notation = parse_einsum("ij,ji->ij").unwrap();
+        assert!(!is_hadamard(&notation));
+    }
+
     #[test]
     fn test_outer_product_1d() {
         let notation = parse_einsum("i,j->ij").unwrap();
@@ -140,6 +154,12 @@ mod tests {
         assert!(!is_outer_product(&notation));
     }
 
+    #[test]
+    fn test_not_outer_product_reordered_output() {
+        let notation = parse_einsum("i,j->ji").unwrap();
+        assert!(!is_outer_product(&notation));
+    }
+
     #[test]
     fn test_dot_product_1d() {
         let notation = parse_einsum("i,i->").unwrap();
@@ -157,4 +177,10 @@ mod tests {
         let notation = parse_einsum("i,i->i").unwrap();
         assert!(!is_dot_product(&notation));
     }
+
+    #[test]
+    fn test_not_dot_product_transposed_input() {
+        let notation = parse_einsum("ij,ji->").unwrap();
+        assert!(!is_dot_product(&notation));
+    }
 }
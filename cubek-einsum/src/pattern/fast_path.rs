@@ -2,8 +2,10 @@
 
 use alloc::vec::Vec;
 
+use serde::{Deserialize, Serialize};
+
 /// A recognized fast-path operation.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FastPath {
     /// Standard matrix multiplication.
     /// `ij,jk->ik` or variants with transposition.
@@ -63,7 +65,7 @@ pub enum FastPath {
 }
 
 /// Type of reduction operation.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ReduceOp {
     Sum,
     Prod,
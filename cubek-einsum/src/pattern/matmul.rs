@@ -259,6 +259,86 @@ pub fn extract_matmul_config(notation: &EinsumNotation) -> Option<MatmulConfig>
     is_batched_matmul(notation).or_else(|| is_matmul(notation))
 }
 
+/// Index layout of a general two-operand contraction run as a batched GEMM.
+///
+/// The executor permutes the operands to `[batch, M, K]` and `[batch, K, N]`
+/// and writes the product as `[batch, M, N]`, i.e. in the order of
+/// [`GemmLayout::output`]. Each group keeps the order of its indices in the
+/// left operand, or in the right operand for `n`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GemmLayout {
+    /// Indices in both operands that are kept.
+    pub batch: Vec<char>,
+    /// Kept indices of the left operand only.
+    pub m: Vec<char>,
+    /// Contracted indices, in left operand order.
+    pub k: Vec<char>,
+    /// Kept indices of the right operand only.
+    pub n: Vec<char>,
+}
+
+impl GemmLayout {
+    /// Splits the operand indices of a contraction into GEMM dimensions.
+    ///
+    /// An index repeated within an operand is one dimension, its diagonal.
+    pub fn new(lhs_indices: &[char], rhs_indices: &[char], contracted: &[char]) -> Self {
+        let is_contracted = |c: &char| contracted.contains(c);
+        let lhs_indices = unique_indices(lhs_indices);
+        let rhs_indices = unique_indices(rhs_indices);
+
+        let batch: Vec<char> = lhs_indices
+            .iter()
+            .filter(|c| !is_contracted(c) && rhs_indices.contains(c))
+            .copied()
+            .collect();
+        let m = lhs_indices
+            .iter()
+            .filter(|c| !is_contracted(c) && !batch.contains(c))
+            .copied()
+            .collect();
+        let k = lhs_indices.iter().filter(|c| is_contracted(c)).copied().collect();
+        let n = rhs_indices
+            .iter()
+            .filter(|c| !is_contracted(c) && !batch.contains(c))
+            .copied()
+            .collect();
+
+        Self { batch, m, k, n }
+    }
+
+    /// Indices of the GEMM result, `[batch, M, N]`.
+    pub fn output(&self) -> Vec<char> {
+        self.batch.iter().chain(&self.m).chain(&self.n).copied().collect()
+    }
+
+    /// Permutation taking the GEMM result to `result` order.
+    ///
+    /// `permutation[d]` is the position in [`GemmLayout::output`] of
+    /// `result[d]`. Returns `None` if `result` is not a reordering of the
+    /// GEMM result.
+    pub fn permutation_to(&self, result: &[char]) -> Option<Vec<usize>> {
+        let output = self.output();
+        if output.len() != result.len() {
+            return None;
+        }
+        result
+            .iter()
+            .map(|c| output.iter().position(|o| o == c))
+            .collect()
+    }
+}
+
+/// Indices in order of first occurrence, without repeats.
+fn unique_indices(indices: &[char]) -> Vec<char> {
+    let mut unique = Vec::with_capacity(indices.len());
+    for &c in indices {
+        if !unique.contains(&c) {
+            unique.push(c);
+        }
+    }
+    unique
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -332,4 +412,23 @@ mod tests {
         assert!(is_matmul(&notation).is_none());
         assert!(is_batched_matmul(&notation).is_none());
     }
+
+    #[test]
+    fn test_gemm_layout() {
+        // Batch order follows the left operand, not the right one
+        let layout = GemmLayout::new(&['b', 'a', 'i', 'k'], &['a', 'k', 'j', 'b'], &['k']);
+        assert_eq!(layout.batch, vec!['b', 'a']);
+        assert_eq!(layout.m, vec!['i']);
+        assert_eq!(layout.k, vec!['k']);
+        assert_eq!(layout.n, vec!['j']);
+        assert_eq!(layout.output(), vec!['b', 'a', 'i', 'j']);
+
+        assert_eq!(layout.permutation_to(&['j', 'a', 'i', 'b']), Some(vec![3, 1, 2, 0]));
+        assert_eq!(layout.permutation_to(&['j', 'a', 'i']), None);
+        assert_eq!(layout.permutation_to(&['j', 'a', 'i', 'k']), None);
+
+        // A repeated index is a single (diagonal) dimension
+        let layout = GemmLayout::new(&['i', 'i', 'j'], &['j', 'k', 'k'], &['j']);
+        assert_eq!(layout.output(), vec!['i', 'k']);
+    }
 }
@@ -16,8 +16,8 @@ mod unary;
 mod binary;
 
 pub use fast_path::FastPath;
-pub use matmul::{is_matmul, is_batched_matmul, extract_matmul_config, MatmulConfig};
-pub use unary::{is_transpose, is_reduction, is_trace, is_diagonal_extract};
+pub use matmul::{is_matmul, is_batched_matmul, extract_matmul_config, GemmLayout, MatmulConfig};
+pub use unary::{is_transpose, is_reduction, is_trace, is_diagonal_extract, has_trailing_diagonal};
 pub use binary::{is_hadamard, is_outer_product, is_dot_product};
 
 use crate::notation::EinsumNotation;
@@ -44,12 +44,12 @@ pub fn recognize_pattern(notation: &EinsumNotation) -> Option<FastPath> {
         }
 
         // Check trace
-        if is_trace(notation) {
+        if is_trace(notation) && has_trailing_diagonal(notation, false) {
             return Some(FastPath::Trace);
         }
 
         // Check diagonal extraction
-        if is_diagonal_extract(notation).is_some() {
+        if is_diagonal_extract(notation).is_some() && has_trailing_diagonal(notation, true) {
             return Some(FastPath::DiagonalExtract);
         }
 
@@ -67,7 +67,24 @@ pub fn is_reduction(notation: &EinsumNotation) -> Option<(Vec<usize>, ReductionT
     let output = notation.output();
 
     let input_indices: Vec<char> = input.named_indices().collect();
-    let output_indices: BTreeSet<char> = output.named_indices().collect();
+    let output_order: Vec<char> = output.named_indices().collect();
+    let output_indices: BTreeSet<char> = output_order.iter().copied().collect();
+
+    // Repeated indices are diagonals, not plain reductions
+    let unique: BTreeSet<char> = input_indices.iter().copied().collect();
+    if unique.len() != input_indices.len() {
+        return None;
+    }
+
+    // Kept axes must stay in input order, the reduce kernel does not permute
+    let kept: Vec<char> = input_indices
+        .iter()
+        .copied()
+        .filter(|c| output_indices.contains(c))
+        .collect();
+    if kept != output_order {
+        return None;
+    }
 
     // Find reduced axes
     let reduced_axes: Vec<usize> = input_indices
@@ -194,6 +211,39 @@ pub fn is_diagonal_extract(notation: &EinsumNotation) -> Option<DiagonalConfig>
     })
 }
 
+/// Checks that the notation has the layout of the trace and diagonal kernels.
+///
+/// The repeated index must occupy the last two input dimensions, and the
+/// output must list the leading indices in input order, followed by the
+/// repeated index when `keep_diagonal` is set (`bii->bi`) or not at all
+/// (`bii->b`).
+pub fn has_trailing_diagonal(notation: &EinsumNotation, keep_diagonal: bool) -> bool {
+    if !notation.is_unary() {
+        return false;
+    }
+
+    let input_indices: Vec<char> = notation.inputs()[0].named_indices().collect();
+    let output_indices: Vec<char> = notation.output().named_indices().collect();
+
+    let rank = input_indices.len();
+    if rank < 2 || input_indices[rank - 2] != input_indices[rank - 1] {
+        return false;
+    }
+
+    let diagonal = input_indices[rank - 1];
+    let leading = &input_indices[..rank - 2];
+    let unique: BTreeSet<char> = leading.iter().copied().collect();
+    if unique.len() != leading.len() || unique.contains(&diagonal) {
+        return false;
+    }
+
+    let mut expected: Vec<char> = leading.to_vec();
+    if keep_diagonal {
+        expected.push(diagonal);
+    }
+    output_indices == expected
+}
+
 /// Configuration for diagonal extraction.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DiagonalConfig {
@@ -262,6 +312,12 @@ mod tests {
         assert_eq!(axes, vec![0, 1, 2]);
     }
 
+    #[test]
+    fn test_not_reduction_reordered_output() {
+        let notation = parse_einsum("ijk->ki").unwrap();
+        assert!(is_reduction(&notation).is_none());
+    }
+
     #[test]
     fn test_trace() {
         let notation = parse_einsum("ii->").unwrap();
@@ -298,4 +354,12 @@ mod tests {
 
         assert_eq!(config.diagonal_index, 'i');
     }
+
+    #[test]
+    fn test_trailing_diagonal_layout() {
+        assert!(has_trailing_diagonal(&parse_einsum("bii->b").unwrap(), false));
+        assert!(has_trailing_diagonal(&parse_einsum("bii->bi").unwrap(), true));
+        assert!(!has_trailing_diagonal(&parse_einsum("iib->b").unwrap(), false));
+        assert!(!has_trailing_diagonal(&parse_einsum("bii->").unwrap(), false));
+    }
 }
@@ -0,0 +1,663 @@
+//! Host interpreter for execution plans.
+//!
+//! Runs every [`ExecutionStep`] of an [`ExecutionPlan`] on host buffers,
+//! tracking the index labels of intermediates the same way the GPU executor
+//! does. Comparing its result with [`evaluate_notation`](super::evaluate_notation)
+//! checks that a plan computes the requested output, independent of kernels.
+
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+
+use super::naive::evaluate_indexed;
+use super::tensor::{HostElement, HostTensor, HostTensorRef};
+use crate::error::{EinsumError, EinsumResult};
+use crate::notation::validation::validate_shapes;
+use crate::notation::{EinsumNotation, generate_batch_indices};
+use crate::optimization::{ExecutionPlan, ExecutionStep, ReductionOp};
+use crate::pattern::{FastPath, GemmLayout};
+
+/// A host tensor with its index labels.
+struct TrackedTensor<E> {
+    tensor: HostTensor<E>,
+    indices: Vec<char>,
+}
+
+/// Executes an execution plan on host tensors.
+///
+/// Fast-path steps are evaluated with the semantics of their GPU kernels
+/// (e.g. `Matmul` multiplies the two inputs as `[M, K] @ [K, N]` after
+/// applying the transpose flags). Contraction, permutation and reduction
+/// steps operate on the tracked tensor list exactly like the executor:
+/// contracted operands are removed and the result is appended at the end.
+/// Contractions are computed in the [`GemmLayout`] the executor writes and
+/// permuted into the step's result order, as the executor does. Sliced plans
+/// run their steps once per slice of the inputs and sum the results.
+///
+/// Returns an error if a step is inconsistent with the tensors it refers to,
+/// or if the final tensor does not carry the output indices of `notation`
+/// in the right order.
+pub fn interpret_plan<E: HostElement>(
+    notation: &EinsumNotation,
+    plan: &ExecutionPlan,
+    inputs: &[HostTensorRef<'_, E>],
+) -> EinsumResult<HostTensor<E>> {
+    for input in inputs {
+        input.validate()?;
+    }
+
+    let shapes: Vec<&[usize]> = inputs.iter().map(|t| t.shape).collect();
+    let validation = validate_shapes(notation, &shapes)?;
+
+    let batch_indices = generate_batch_indices(validation.ellipsis_dims);
+    let output_indices: Vec<char> = notation
+        .output()
+        .expand_ellipsis(&batch_indices)
+        .named_indices()
+        .collect();
+
+    if plan.sliced_indices().is_empty() {
+        return run_steps(plan, inputs, &validation.output_shape, &output_indices);
+    }
+
+    // Sliced plans run their steps on every slice of the inputs and sum the
+    // results, like the executor
+    plan.validate_slicing(&shapes)?;
+    let sliced = plan.sliced_indices();
+    let mut values = alloc::vec![0usize; sliced.len()];
+    let mut total: Option<HostTensor<E>> = None;
+
+    for slice in 0..plan.num_slices() {
+        let mut remainder = slice;
+        for (value, &(_, size)) in values.iter_mut().zip(sliced).rev() {
+            *value = remainder % size;
+            remainder /= size;
+        }
+
+        let slice_inputs: Vec<HostTensor<E>> = inputs
+            .iter()
+            .enumerate()
+            .map(|(idx, input)| {
+                let indices = plan.input_indices().get(idx).map(Vec::as_slice).unwrap_or(&[]);
+                slice_of(input, indices, sliced, &values)
+            })
+            .collect();
+        let slice_refs: Vec<HostTensorRef<'_, E>> = slice_inputs.iter().map(|t| t.as_ref()).collect();
+        let partial = run_steps(plan, &slice_refs, &validation.output_shape, &output_indices)?;
+
+        total = Some(match total {
+            None => partial,
+            Some(mut total) => {
+                for (acc, value) in total.data.iter_mut().zip(partial.data) {
+                    *acc = *acc + value;
+                }
+                total
+            }
+        });
+    }
+
+    total.ok_or_else(|| EinsumError::launch("sliced plan runs no slice"))
+}
+
+/// Runs the steps of `plan` on one set of inputs.
+fn run_steps<E: HostElement>(
+    plan: &ExecutionPlan,
+    inputs: &[HostTensorRef<'_, E>],
+    output_shape: &[usize],
+    output_indices: &[char],
+) -> EinsumResult<HostTensor<E>> {
+    let plan_indices = plan.input_indices();
+    let mut tracked: Vec<TrackedTensor<E>> = Vec::with_capacity(inputs.len());
+    for (idx, input) in inputs.iter().enumerate() {
+        let indices = plan_indices.get(idx).cloned().unwrap_or_default();
+        tracked.push(TrackedTensor {
+            tensor: input.to_contiguous(),
+            indices,
+        });
+    }
+
+    for (step_idx, step) in plan.steps().iter().enumerate() {
+        match step {
+            ExecutionStep::FastPath(fast_path) => {
+                let result = interpret_fast_path(fast_path, inputs)?;
+                return reshape_to(result, output_shape);
+            }
+            ExecutionStep::Contraction { inputs: (i, j), contracted, result, .. } => {
+                let (i, j) = (*i, *j);
+                if i == j || i >= tracked.len() || j >= tracked.len() {
+                    return Err(EinsumError::launch(alloc::format!(
+                        "contraction step {} references invalid tensor indices ({}, {}), list has {} tensors",
+                        step_idx, i, j, tracked.len()
+                    )));
+                }
+
+                check_contraction_indices(
+                    step_idx,
+                    &tracked[i].indices,
+                    &tracked[j].indices,
+                    contracted,
+                    result,
+                )?;
+
+                // The executor's GEMM writes `[batch, M, N]` and permutes that
+                // into the result order, broadcast multiplies write the result
+                // order directly
+                let layout = GemmLayout::new(&tracked[i].indices, &tracked[j].indices, contracted);
+                let written = if contracted.is_empty() { result.to_vec() } else { layout.output() };
+                let product = evaluate_indexed(
+                    &[
+                        (tracked[i].tensor.as_ref(), tracked[i].indices.as_slice()),
+                        (tracked[j].tensor.as_ref(), tracked[j].indices.as_slice()),
+                    ],
+                    &written,
+                )?;
+                let contracted_tensor = if contracted.is_empty() {
+                    product
+                } else {
+                    let perm = layout.permutation_to(result).ok_or_else(|| {
+                        EinsumError::launch(alloc::format!(
+                            "contraction step {}: GEMM result {:?} cannot be permuted to {:?}",
+                            step_idx, written, result
+                        ))
+                    })?;
+                    permute(&product, &perm)
+                };
+
+                let (min_idx, max_idx) = if i < j { (i, j) } else { (j, i) };
+                tracked.remove(max_idx);
+                tracked.remove(min_idx);
+                tracked.push(TrackedTensor {
+                    tensor: contracted_tensor,
+                    indices: result.to_vec(),
+                });
+            }
+            ExecutionStep::Permutation { input, perm } => {
+                let tracked_tensor = tracked.get_mut(*input).ok_or_else(|| {
+                    EinsumError::launch("permutation references invalid tensor")
+                })?;
+
+                if !is_permutation(perm, tracked_tensor.indices.len()) {
+                    return Err(EinsumError::launch(alloc::format!(
+                        "step {}: {:?} is not a permutation of {} dims",
+                        step_idx, perm, tracked_tensor.indices.len()
+                    )));
+                }
+
+                let indices: Vec<char> = perm.iter().map(|&p| tracked_tensor.indices[p]).collect();
+
+                tracked_tensor.tensor = permute(&tracked_tensor.tensor, perm);
+                tracked_tensor.indices = indices;
+            }
+            ExecutionStep::Reduction { input, axes, op } => {
+                if *op != ReductionOp::Sum {
+                    return Err(EinsumError::unsupported("only sum reduction supported"));
+                }
+
+                let tracked_tensor = tracked.get_mut(*input).ok_or_else(|| {
+                    EinsumError::launch("reduction references invalid tensor")
+                })?;
+
+                if axes.iter().any(|&a| a >= tracked_tensor.indices.len()) {
+                    return Err(EinsumError::launch(alloc::format!(
+                        "step {}: reduction axes {:?} out of range for rank {}",
+                        step_idx, axes, tracked_tensor.indices.len()
+                    )));
+                }
+
+                let kept: Vec<char> = tracked_tensor
+                    .indices
+                    .iter()
+                    .enumerate()
+                    .filter(|(idx, _)| !axes.contains(idx))
+                    .map(|(_, &c)| c)
+                    .collect();
+
+                let reduced = evaluate_indexed(
+                    &[(tracked_tensor.tensor.as_ref(), tracked_tensor.indices.as_slice())],
+                    &kept,
+                )?;
+
+                tracked_tensor.tensor = reduced;
+                tracked_tensor.indices = kept;
+            }
+        }
+    }
+
+    if tracked.len() != 1 {
+        return Err(EinsumError::launch(alloc::format!(
+            "plan leaves {} tensors instead of one",
+            tracked.len()
+        )));
+    }
+
+    let result = tracked.pop().expect("exactly one tensor left");
+    if plan.steps().is_empty() && has_repeated_index(&result.indices) {
+        return Err(EinsumError::unsupported(
+            "repeated indices in a single operand without a fast path",
+        ));
+    }
+    if result.indices != output_indices {
+        return Err(EinsumError::launch(alloc::format!(
+            "plan produces indices {:?} but the output expects {:?}",
+            result.indices, output_indices
+        )));
+    }
+
+    Ok(result.tensor)
+}
+
+/// Copies the slice of `input` selected by the `values` of the `sliced`
+/// indices, keeping sliced dimensions with size 1.
+///
+/// Dimensions of size 1 are broadcast and the same in every slice.
+fn slice_of<E: HostElement>(
+    input: &HostTensorRef<'_, E>,
+    indices: &[char],
+    sliced: &[(char, usize)],
+    values: &[usize],
+) -> HostTensor<E> {
+    let mut shape = input.shape.to_vec();
+    let mut offset = 0usize;
+    for (dim, c) in indices.iter().enumerate() {
+        if let Some(pos) = sliced.iter().position(|&(s, _)| s == *c)
+            && dim < shape.len()
+            && shape[dim] > 1
+        {
+            offset += values[pos] * input.strides[dim];
+            shape[dim] = 1;
+        }
+    }
+
+    HostTensorRef::new(&input.data[offset..], &shape, input.strides).to_contiguous()
+}
+
+/// Checks that a contraction step is consistent with its operands.
+///
+/// The result must hold exactly the operand indices that are not contracted,
+/// and every contracted index must come from one of the operands.
+fn check_contraction_indices(
+    step_idx: usize,
+    lhs_indices: &[char],
+    rhs_indices: &[char],
+    contracted: &[char],
+    result: &[char],
+) -> EinsumResult<()> {
+    let operand_set: BTreeSet<char> = lhs_indices.iter().chain(rhs_indices.iter()).copied().collect();
+    let contracted_set: BTreeSet<char> = contracted.iter().copied().collect();
+    let result_set: BTreeSet<char> = result.iter().copied().collect();
+
+    let expected_result: BTreeSet<char> = operand_set.difference(&contracted_set).copied().collect();
+
+    if !contracted_set.is_subset(&operand_set) || result_set != expected_result || result_set.len() != result.len() {
+        return Err(EinsumError::launch(alloc::format!(
+            "contraction step {}: {:?},{:?} contracting {:?} cannot produce {:?}",
+            step_idx, lhs_indices, rhs_indices, contracted, result
+        )));
+    }
+
+    Ok(())
+}
+
+/// Evaluates a fast path with the semantics of its GPU kernel.
+fn interpret_fast_path<E: HostElement>(
+    fast_path: &FastPath,
+    inputs: &[HostTensorRef<'_, E>],
+) -> EinsumResult<HostTensor<E>> {
+    let required = if fast_path.is_binary() { 2 } else { 1 };
+    if inputs.len() < required {
+        return Err(EinsumError::unsupported(alloc::format!(
+            "{} requires {} inputs",
+            fast_path.name(),
+            required
+        )));
+    }
+
+    match fast_path {
+        FastPath::Matmul { transpose_a, transpose_b } => {
+            interpret_matmul(inputs[0], inputs[1], 0, *transpose_a, *transpose_b)
+        }
+        FastPath::BatchedMatmul { batch_dims, transpose_a, transpose_b } => {
+            interpret_matmul(inputs[0], inputs[1], batch_dims.len(), *transpose_a, *transpose_b)
+        }
+        FastPath::Reduce { axes, .. } => {
+            let labels = positional_labels(inputs[0].rank());
+            if axes.iter().any(|&a| a >= labels.len()) {
+                return Err(EinsumError::launch("reduce axes out of range"));
+            }
+            let kept: Vec<char> = labels
+                .iter()
+                .enumerate()
+                .filter(|(idx, _)| !axes.contains(idx))
+                .map(|(_, &c)| c)
+                .collect();
+            evaluate_indexed(&[(inputs[0], labels.as_slice())], &kept)
+        }
+        FastPath::Transpose { permutation } => {
+            let labels = positional_labels(inputs[0].rank());
+            if !is_permutation(permutation, labels.len()) {
+                return Err(EinsumError::launch(alloc::format!(
+                    "transpose permutation {:?} does not match rank {}",
+                    permutation, labels.len()
+                )));
+            }
+            let permuted: Vec<char> = permutation.iter().map(|&p| labels[p]).collect();
+            evaluate_indexed(&[(inputs[0], labels.as_slice())], &permuted)
+        }
+        FastPath::Hadamard | FastPath::DotProduct => {
+            // Both kernels walk the inputs in flat element order
+            if inputs[0].shape != inputs[1].shape {
+                return Err(EinsumError::launch(alloc::format!(
+                    "{} requires same shape inputs",
+                    fast_path.name()
+                )));
+            }
+            let labels = positional_labels(inputs[0].rank());
+            let output: &[char] = if matches!(fast_path, FastPath::Hadamard) { &labels } else { &[] };
+            evaluate_indexed(
+                &[(inputs[0], labels.as_slice()), (inputs[1], labels.as_slice())],
+                output,
+            )
+        }
+        FastPath::OuterProduct => {
+            let labels = positional_labels(inputs[0].rank() + inputs[1].rank());
+            let (lhs_labels, rhs_labels) = labels.split_at(inputs[0].rank());
+            evaluate_indexed(&[(inputs[0], lhs_labels), (inputs[1], rhs_labels)], &labels)
+        }
+        FastPath::Trace | FastPath::DiagonalExtract => {
+            // Both kernels operate on the last two dimensions, which must be square
+            let rank = inputs[0].rank();
+            if rank < 2 || inputs[0].shape[rank - 2] != inputs[0].shape[rank - 1] {
+                return Err(EinsumError::launch(alloc::format!(
+                    "{} requires square trailing dimensions, got {:?}",
+                    fast_path.name(),
+                    inputs[0].shape
+                )));
+            }
+            let mut labels = positional_labels(rank - 1);
+            let mut output = labels.clone();
+            if matches!(fast_path, FastPath::Trace) {
+                output.pop();
+            }
+            labels.push(labels[rank - 2]);
+            evaluate_indexed(&[(inputs[0], labels.as_slice())], &output)
+        }
+    }
+}
+
+/// Evaluates `[batch..., M, K] @ [batch..., K, N]` with optional transposes
+/// of the two trailing dimensions.
+fn interpret_matmul<E: HostElement>(
+    lhs: HostTensorRef<'_, E>,
+    rhs: HostTensorRef<'_, E>,
+    num_batch: usize,
+    transpose_a: bool,
+    transpose_b: bool,
+) -> EinsumResult<HostTensor<E>> {
+    if lhs.rank() != num_batch + 2 || rhs.rank() != num_batch + 2 {
+        return Err(EinsumError::launch(alloc::format!(
+            "matmul with {} batch dims requires rank {} inputs, got {} and {}",
+            num_batch,
+            num_batch + 2,
+            lhs.rank(),
+            rhs.rank()
+        )));
+    }
+
+    let batch = positional_labels(num_batch);
+    let (m, k, n) = ('m', 'k', 'n');
+
+    let mut lhs_labels = batch.clone();
+    lhs_labels.extend(if transpose_a { [k, m] } else { [m, k] });
+    let mut rhs_labels = batch.clone();
+    rhs_labels.extend(if transpose_b { [n, k] } else { [k, n] });
+    let mut output = batch;
+    output.extend([m, n]);
+
+    evaluate_indexed(&[(lhs, lhs_labels.as_slice()), (rhs, rhs_labels.as_slice())], &output)
+}
+
+/// Labels for positional dimensions, distinct from any notation index.
+fn positional_labels(rank: usize) -> Vec<char> {
+    generate_batch_indices(rank)
+}
+
+/// Copies `tensor` with its dimensions reordered, dimension `d` of the
+/// result being dimension `perm[d]` of `tensor`.
+fn permute<E: HostElement>(tensor: &HostTensor<E>, perm: &[usize]) -> HostTensor<E> {
+    let shape: Vec<usize> = perm.iter().map(|&p| tensor.shape[p]).collect();
+    let strides: Vec<usize> = perm.iter().map(|&p| tensor.strides[p]).collect();
+
+    HostTensorRef::new(&tensor.data, &shape, &strides).to_contiguous()
+}
+
+fn has_repeated_index(indices: &[char]) -> bool {
+    indices.iter().enumerate().any(|(pos, c)| indices[..pos].contains(c))
+}
+
+fn is_permutation(perm: &[usize], rank: usize) -> bool {
+    let unique: BTreeSet<usize> = perm.iter().copied().collect();
+    perm.len() == rank && unique.len() == rank && perm.iter().all(|&p| p < rank)
+}
+
+/// Writes a contiguous result into a buffer of the given shape.
+///
+/// Kernels write their result in flat order into the caller's output, so
+/// only the element count has to agree.
+fn reshape_to<E: HostElement>(tensor: HostTensor<E>, shape: &[usize]) -> EinsumResult<HostTensor<E>> {
+    if tensor.num_elements() != shape.iter().product::<usize>() {
+        return Err(EinsumError::launch(alloc::format!(
+            "fast path produces shape {:?} but the output has shape {:?}",
+            tensor.shape, shape
+        )));
+    }
+    HostTensor::new(tensor.data, shape.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use crate::notation::parse_einsum;
+    use crate::optimization::{
+        ContractionPath, ContractionStep, ContractionStrategy, CostModel, create_plan,
+        create_plan_with_cost_model,
+    };
+    use crate::reference::evaluate_notation;
+
+    fn iota(shape: &[usize]) -> HostTensor<f32> {
+        let n: usize = shape.iter().product();
+        HostTensor::new((0..n).map(|v| (v % 7) as f32).collect(), shape.to_vec()).unwrap()
+    }
+
+    fn check(notation: &str, shapes: &[&[usize]], strategy: ContractionStrategy) {
+        let notation = parse_einsum(notation).unwrap();
+        let tensors: Vec<HostTensor<f32>> = shapes.iter().map(|s| iota(s)).collect();
+        let refs: Vec<HostTensorRef<'_, f32>> = tensors.iter().map(|t| t.as_ref()).collect();
+
+        let plan = create_plan(&notation, shapes, strategy.clone()).unwrap();
+        let expected = evaluate_notation(&notation, &refs).unwrap();
+        let actual = interpret_plan(&notation, &plan, &refs).unwrap();
+
+        assert_eq!(actual, expected, "{:?} {}", strategy, notation);
+    }
+
+    #[test]
+    fn test_interpret_chain_all_strategies() {
+        for strategy in [
+            ContractionStrategy::Greedy,
+            ContractionStrategy::RandomGreedy { trials: 8, temperature: 1.0, seed: 0 },
+            ContractionStrategy::Optimal,
+            ContractionStrategy::branch_bound(),
+            ContractionStrategy::Auto,
+        ] {
+            check("ij,jk,kl->il", &[&[2, 3], &[3, 4], &[4, 5]], strategy.clone());
+            check("ij,jk,kl->li", &[&[2, 3], &[3, 4], &[4, 5]], strategy.clone());
+            check("ik,kj,jl->il", &[&[2, 3], &[3, 4], &[4, 5]], strategy.clone());
+            check("ij,jk,kl,lm->im", &[&[2, 3], &[3, 4], &[4, 2], &[2, 3]], strategy.clone());
+            check("ij,jkm,kl->i", &[&[2, 3], &[3, 4, 2], &[4, 5]], strategy.clone());
+            check("...ij,...jk,...kl->...il", &[&[2, 2, 3], &[2, 3, 4], &[2, 4, 5]], strategy);
+        }
+    }
+
+    #[test]
+    fn test_gemm_layout_permutes_to_result() {
+        // Transposed outputs and batch indices make the GEMM layout differ
+        // from the result order of some steps
+        let mut permuted_steps = 0;
+        for (expr, shapes) in [
+            ("ij,jk,kl->li", &[&[2, 3][..], &[3, 4], &[4, 5]][..]),
+            ("bij,bjk,bkl->lbi", &[&[2, 2, 3][..], &[2, 3, 4], &[2, 4, 5]][..]),
+            ("ijb,bjk,kbl->lib", &[&[2, 3, 2][..], &[2, 3, 4], &[4, 2, 5]][..]),
+        ] {
+            let notation = parse_einsum(expr).unwrap();
+            for strategy in [ContractionStrategy::Greedy, ContractionStrategy::Optimal] {
+                let plan = create_plan(&notation, shapes, strategy.clone()).unwrap();
+                let mut tracked = plan.input_indices().to_vec();
+
+                for step in plan.steps() {
+                    let ExecutionStep::Contraction { inputs: (i, j), contracted, result, .. } = step else {
+                        continue;
+                    };
+                    let layout = GemmLayout::new(&tracked[*i], &tracked[*j], contracted);
+                    let perm = layout.permutation_to(result).unwrap();
+                    let written = layout.output();
+                    let permuted: Vec<char> = perm.iter().map(|&p| written[p]).collect();
+                    assert_eq!(&permuted, result, "{} {:?}", expr, strategy);
+                    if written != *result {
+                        permuted_steps += 1;
+                    }
+
+                    let (min_idx, max_idx) = if i < j { (*i, *j) } else { (*j, *i) };
+                    tracked.remove(max_idx);
+                    tracked.remove(min_idx);
+                    tracked.push(result.clone());
+                }
+
+                check(expr, shapes, strategy);
+            }
+        }
+        assert!(permuted_steps > 0);
+    }
+
+    #[test]
+    fn test_interpret_explicit_paths() {
+        let shapes: &[&[usize]] = &[&[2, 3], &[3, 4], &[4, 2], &[2, 3]];
+        for pairs in [
+            vec![(0, 1), (0, 1), (0, 1)],
+            vec![(2, 3), (1, 2), (0, 1)],
+            vec![(0, 1), (1, 2), (0, 1)],
+            vec![(0, 3), (0, 1), (0, 1)],
+        ] {
+            check("ij,jk,kl,lm->im", shapes, ContractionStrategy::Explicit(pairs));
+        }
+    }
+
+    #[test]
+    fn test_interpret_fast_paths() {
+        check("ij,jk->ik", &[&[2, 3], &[3, 4]], ContractionStrategy::Auto);
+        check("ji,jk->ik", &[&[3, 2], &[3, 4]], ContractionStrategy::Auto);
+        check("ik,jk->ij", &[&[2, 3], &[4, 3]], ContractionStrategy::Auto);
+        check("bij,bjk->bik", &[&[2, 2, 3], &[2, 3, 4]], ContractionStrategy::Auto);
+        check("ij->i", &[&[3, 4]], ContractionStrategy::Auto);
+        check("ijk->j", &[&[2, 3, 4]], ContractionStrategy::Auto);
+        check("ijk->kij", &[&[2, 3, 4]], ContractionStrategy::Auto);
+        check("ij,ij->ij", &[&[2, 3], &[2, 3]], ContractionStrategy::Auto);
+        check("i,j->ij", &[&[3], &[4]], ContractionStrategy::Auto);
+        check("i,i->", &[&[5], &[5]], ContractionStrategy::Auto);
+        check("ii->", &[&[4, 4]], ContractionStrategy::Auto);
+        check("ii->i", &[&[4, 4]], ContractionStrategy::Auto);
+    }
+
+    #[test]
+    fn test_interpret_binary_layouts_without_fast_path() {
+        // Same index sets as hadamard, outer and dot product, but in layouts
+        // their kernels do not compute
+        check("ij,ji->ij", &[&[2, 3], &[3, 2]], ContractionStrategy::Auto);
+        check("i,j->ji", &[&[3], &[4]], ContractionStrategy::Auto);
+        check("ij,ji->", &[&[2, 3], &[3, 2]], ContractionStrategy::Auto);
+    }
+
+    #[test]
+    fn test_interpret_single_input_without_fast_path() {
+        check("ijk->ki", &[&[2, 3, 4]], ContractionStrategy::Auto);
+        check("ijk->kj", &[&[2, 3, 4]], ContractionStrategy::Greedy);
+
+        // A diagonal outside the last two dimensions has no steps
+        let notation = parse_einsum("iij->j").unwrap();
+        let input = iota(&[3, 3, 2]);
+        let plan = create_plan(&notation, &[&[3, 3, 2]], ContractionStrategy::Auto).unwrap();
+        assert!(matches!(
+            interpret_plan(&notation, &plan, &[input.as_ref()]),
+            Err(EinsumError::Unsupported { .. })
+        ));
+    }
+
+    #[test]
+    fn test_interpret_sliced_plans() {
+        let cost_model = CostModel::default().with_memory_limit(10);
+        for (notation, shapes) in [
+            ("ia,ib,ab->", &[&[8, 10][..], &[8, 10], &[10, 10]][..]),
+            ("ia,ib,ab->i", &[&[3, 10][..], &[3, 10], &[10, 10]][..]),
+        ] {
+            let notation = parse_einsum(notation).unwrap();
+            let tensors: Vec<HostTensor<f32>> = shapes.iter().map(|s| iota(s)).collect();
+            let refs: Vec<HostTensorRef<'_, f32>> = tensors.iter().map(|t| t.as_ref()).collect();
+
+            let plan = create_plan_with_cost_model(&notation, shapes, ContractionStrategy::Optimal, &cost_model).unwrap();
+            assert!(plan.num_slices() > 1, "{}", notation);
+
+            let expected = evaluate_notation(&notation, &refs).unwrap();
+            assert_eq!(interpret_plan(&notation, &plan, &refs).unwrap(), expected, "{}", notation);
+        }
+    }
+
+    #[test]
+    fn test_interpret_rejects_invalid_slicing() {
+        let notation = parse_einsum("ia,ib,ab->").unwrap();
+        let shapes: &[&[usize]] = &[&[8, 10], &[8, 10], &[10, 10]];
+        let tensors: Vec<HostTensor<f32>> = shapes.iter().map(|s| iota(s)).collect();
+        let refs: Vec<HostTensorRef<'_, f32>> = tensors.iter().map(|t| t.as_ref()).collect();
+
+        // Slicing `a` with the wrong size, as an edited plan file could
+        let plan = create_plan(&notation, shapes, ContractionStrategy::Optimal).unwrap();
+        let mut json = serde_json::to_value(&plan).unwrap();
+        json["sliced_indices"] = serde_json::json!([["a", 5]]);
+        let plan: ExecutionPlan = serde_json::from_value(json).unwrap();
+
+        assert!(interpret_plan(&notation, &plan, &refs).is_err());
+    }
+
+    #[test]
+    fn test_interpret_rejects_wrong_output_order() {
+        let notation = parse_einsum("ij,jk,kl->il").unwrap();
+        let shapes: &[&[usize]] = &[&[2, 3], &[3, 4], &[4, 5]];
+
+        // Second step yields [l, i] instead of [i, l]
+        let mut path = ContractionPath::new();
+        path.push(ContractionStep::new((0, 1), vec!['j'], vec!['i', 'k'], 0));
+        path.push(ContractionStep::new((0, 1), vec!['k'], vec!['l', 'i'], 0));
+        let input_indices = vec![vec!['i', 'j'], vec!['j', 'k'], vec!['k', 'l']];
+        let plan = ExecutionPlan::from_contraction_path(path, vec![2, 5], input_indices);
+
+        let tensors: Vec<HostTensor<f32>> = shapes.iter().map(|s| iota(s)).collect();
+        let refs: Vec<HostTensorRef<'_, f32>> = tensors.iter().map(|t| t.as_ref()).collect();
+
+        assert!(interpret_plan(&notation, &plan, &refs).is_err());
+    }
+
+    #[test]
+    fn test_interpret_rejects_inconsistent_step() {
+        let notation = parse_einsum("ij,jk,kl->il").unwrap();
+        let shapes: &[&[usize]] = &[&[2, 3], &[3, 4], &[4, 5]];
+
+        // Claims to keep j although it is contracted
+        let mut path = ContractionPath::new();
+        path.push(ContractionStep::new((0, 1), vec!['j'], vec!['i', 'j', 'k'], 0));
+        let input_indices = vec![vec!['i', 'j'], vec!['j', 'k'], vec!['k', 'l']];
+        let plan = ExecutionPlan::from_contraction_path(path, vec![2, 5], input_indices);
+
+        let tensors: Vec<HostTensor<f32>> = shapes.iter().map(|s| iota(s)).collect();
+        let refs: Vec<HostTensorRef<'_, f32>> = tensors.iter().map(|t| t.as_ref()).collect();
+
+        assert!(interpret_plan(&notation, &plan, &refs).is_err());
+    }
+}
@@ -0,0 +1,23 @@
+//! Host reference implementation of einsum.
+//!
+//! Evaluates einsum expressions on host memory with a naive loop nest,
+//! without any GPU runtime. Intended for correctness checking of the
+//! planner and kernels, e.g. on CPU-only CI machines.
+//!
+//! Parsing and validation go through the same `parse_einsum`,
+//! `validate_notation` and `validate_shapes` functions as the GPU path,
+//! so both accept exactly the same expressions.
+//!
+//! [`interpret_plan`] runs an [`ExecutionPlan`](crate::ExecutionPlan) step
+//! by step on the host, which checks the planner independently of kernels.
+
+mod tensor;
+mod naive;
+mod interpreter;
+mod random;
+
+pub use tensor::{HostElement, HostTensor, HostTensorRef};
+pub use naive::{einsum_reference, evaluate_notation};
+pub use interpreter::interpret_plan;
+pub use random::{RandomExpression, RandomExpressionConfig, random_expression, random_tensor};
+pub use crate::rng::SplitMix64;
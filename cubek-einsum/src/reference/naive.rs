@@ -0,0 +1,271 @@
+//! Naive loop-nest einsum evaluation.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::tensor::{HostElement, HostTensor, HostTensorRef, increment_position};
+use crate::error::{EinsumError, EinsumResult};
+use crate::notation::validation::validate_shapes;
+use crate::notation::{EinsumNotation, generate_batch_indices, parse_einsum, validate_notation};
+
+/// Evaluates an einsum expression on host tensors.
+///
+/// Parses and validates the notation exactly like [`einsum`](crate::einsum),
+/// then computes the result with a naive loop over every index combination.
+///
+/// # Example
+///
+/// ```ignore
+/// let a = HostTensor::new(vec![1.0, 2.0, 3.0, 4.0], vec![2, 2])?;
+/// let b = HostTensor::new(vec![5.0, 6.0, 7.0, 8.0], vec![2, 2])?;
+/// let c = einsum_reference("ij,jk->ik", &[a.as_ref(), b.as_ref()])?;
+/// ```
+pub fn einsum_reference<E: HostElement>(
+    notation: &str,
+    inputs: &[HostTensorRef<'_, E>],
+) -> EinsumResult<HostTensor<E>> {
+    let notation = parse_einsum(notation)?;
+    validate_notation(&notation)?;
+    evaluate_notation(&notation, inputs)
+}
+
+/// Evaluates a pre-parsed einsum notation on host tensors.
+///
+/// Supports ellipsis, repeated indices within a subscript (diagonals and
+/// traces) and scalar outputs. The result is always contiguous; a scalar
+/// output has an empty shape and a single element.
+pub fn evaluate_notation<E: HostElement>(
+    notation: &EinsumNotation,
+    inputs: &[HostTensorRef<'_, E>],
+) -> EinsumResult<HostTensor<E>> {
+    for input in inputs {
+        input.validate()?;
+    }
+
+    let shapes: Vec<&[usize]> = inputs.iter().map(|t| t.shape).collect();
+    let validation = validate_shapes(notation, &shapes)?;
+
+    // Expand ellipsis into explicit batch indices
+    let batch_indices = generate_batch_indices(validation.ellipsis_dims);
+    let input_indices: Vec<Vec<char>> = notation
+        .inputs()
+        .iter()
+        .map(|s| s.expand_ellipsis(&batch_indices).named_indices().collect())
+        .collect();
+    let output_indices: Vec<char> = notation
+        .output()
+        .expand_ellipsis(&batch_indices)
+        .named_indices()
+        .collect();
+
+    let operands: Vec<(HostTensorRef<'_, E>, &[char])> = inputs
+        .iter()
+        .copied()
+        .zip(input_indices.iter().map(|v| v.as_slice()))
+        .collect();
+
+    evaluate_indexed(&operands, &output_indices)
+}
+
+/// Multiplies the operands elementwise over their labelled indices and sums
+/// every index that does not appear in `output_indices`.
+///
+/// This is the loop nest shared by the naive evaluator and the plan
+/// interpreter. Each index must have a consistent extent across operands.
+pub(crate) fn evaluate_indexed<E: HostElement>(
+    operands: &[(HostTensorRef<'_, E>, &[char])],
+    output_indices: &[char],
+) -> EinsumResult<HostTensor<E>> {
+    // Every distinct index becomes one loop of the nest
+    let mut loop_indices: Vec<char> = Vec::new();
+    let mut loop_extents: Vec<usize> = Vec::new();
+    for (tensor, indices) in operands {
+        if indices.len() != tensor.rank() {
+            return Err(EinsumError::shape(alloc::format!(
+                "{} indices given for a tensor of rank {}",
+                indices.len(),
+                tensor.rank()
+            )));
+        }
+
+        for (&c, &dim) in indices.iter().zip(tensor.shape.iter()) {
+            match loop_indices.iter().position(|&l| l == c) {
+                Some(pos) if loop_extents[pos] != dim => {
+                    return Err(EinsumError::ShapeMismatch {
+                        index: c,
+                        expected: loop_extents[pos],
+                        got: dim,
+                    });
+                }
+                Some(_) => {}
+                None => {
+                    loop_indices.push(c);
+                    loop_extents.push(dim);
+                }
+            }
+        }
+    }
+
+    let mut output_shape = Vec::with_capacity(output_indices.len());
+    for &c in output_indices {
+        match loop_indices.iter().position(|&l| l == c) {
+            Some(pos) => output_shape.push(loop_extents[pos]),
+            None => return Err(EinsumError::OutputIndexNotInInputs { index: c }),
+        }
+    }
+
+    let mut output = HostTensor::zeros(output_shape);
+    if loop_extents.contains(&0) {
+        return Ok(output);
+    }
+
+    // For each tensor, the stride contributed by each loop index.
+    // Repeated indices within a subscript accumulate their strides.
+    let input_loop_strides: Vec<Vec<usize>> = operands
+        .iter()
+        .map(|(tensor, indices)| loop_strides(&loop_indices, indices, tensor.strides))
+        .collect();
+    let output_loop_strides = loop_strides(&loop_indices, output_indices, &output.strides);
+
+    let mut position = vec![0usize; loop_indices.len()];
+    loop {
+        let mut product: Option<E> = None;
+        for ((tensor, _), strides) in operands.iter().zip(input_loop_strides.iter()) {
+            let value = tensor.data[dot(&position, strides)];
+            product = Some(match product {
+                Some(acc) => acc * value,
+                None => value,
+            });
+        }
+
+        if let Some(value) = product {
+            let offset = dot(&position, &output_loop_strides);
+            output.data[offset] = output.data[offset] + value;
+        }
+
+        if !increment_position(&mut position, &loop_extents) {
+            break;
+        }
+    }
+
+    Ok(output)
+}
+
+/// Maps the strides of a tensor onto the loop indices.
+fn loop_strides(loop_indices: &[char], tensor_indices: &[char], strides: &[usize]) -> Vec<usize> {
+    let mut result = vec![0usize; loop_indices.len()];
+    for (&c, &stride) in tensor_indices.iter().zip(strides.iter()) {
+        let pos = loop_indices
+            .iter()
+            .position(|&l| l == c)
+            .expect("every tensor index is a loop index");
+        result[pos] += stride;
+    }
+    result
+}
+
+#[inline]
+fn dot(position: &[usize], strides: &[usize]) -> usize {
+    position.iter().zip(strides.iter()).map(|(&p, &s)| p * s).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tensor(data: Vec<f32>, shape: Vec<usize>) -> HostTensor<f32> {
+        HostTensor::new(data, shape).unwrap()
+    }
+
+    #[test]
+    fn test_reference_matmul() {
+        let a = tensor(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0], vec![2, 3]);
+        let b = tensor(vec![1.0, 0.0, 0.0, 1.0, 1.0, 1.0], vec![3, 2]);
+
+        let c = einsum_reference("ij,jk->ik", &[a.as_ref(), b.as_ref()]).unwrap();
+
+        assert_eq!(c.shape, vec![2, 2]);
+        assert_eq!(c.data, vec![4.0, 5.0, 10.0, 11.0]);
+    }
+
+    #[test]
+    fn test_reference_transpose() {
+        let a = tensor(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0], vec![2, 3]);
+
+        let t = einsum_reference("ij->ji", &[a.as_ref()]).unwrap();
+
+        assert_eq!(t.shape, vec![3, 2]);
+        assert_eq!(t.data, vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+    }
+
+    #[test]
+    fn test_reference_strided_input() {
+        // Transposed view of a 3x2 matrix, summed over rows
+        let data = [1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let view = HostTensorRef::new(&data, &[2, 3], &[1, 2]);
+
+        let sums = einsum_reference("ij->i", &[view]).unwrap();
+
+        assert_eq!(sums.data, vec![9.0, 12.0]);
+    }
+
+    #[test]
+    fn test_reference_trace() {
+        let a = tensor(vec![1.0, 2.0, 3.0, 4.0], vec![2, 2]);
+
+        let trace = einsum_reference("ii->", &[a.as_ref()]).unwrap();
+
+        assert!(trace.shape.is_empty());
+        assert_eq!(trace.data, vec![5.0]);
+    }
+
+    #[test]
+    fn test_reference_diagonal() {
+        let a = tensor(vec![1.0, 2.0, 3.0, 4.0], vec![2, 2]);
+
+        let diag = einsum_reference("ii->i", &[a.as_ref()]).unwrap();
+
+        assert_eq!(diag.data, vec![1.0, 4.0]);
+    }
+
+    #[test]
+    fn test_reference_dot_product() {
+        let a = tensor(vec![1.0, 2.0, 3.0], vec![3]);
+        let b = tensor(vec![4.0, 5.0, 6.0], vec![3]);
+
+        let dot = einsum_reference("i,i->", &[a.as_ref(), b.as_ref()]).unwrap();
+
+        assert_eq!(dot.data, vec![32.0]);
+    }
+
+    #[test]
+    fn test_reference_ellipsis() {
+        // Two batches of 1x2 @ 2x1
+        let a = tensor(vec![1.0, 2.0, 3.0, 4.0], vec![2, 1, 2]);
+        let b = tensor(vec![1.0, 1.0, 2.0, 2.0], vec![2, 2, 1]);
+
+        let c = einsum_reference("...ij,...jk->...ik", &[a.as_ref(), b.as_ref()]).unwrap();
+
+        assert_eq!(c.shape, vec![2, 1, 1]);
+        assert_eq!(c.data, vec![3.0, 14.0]);
+    }
+
+    #[test]
+    fn test_reference_chain() {
+        let a = tensor(vec![1.0, 2.0], vec![1, 2]);
+        let b = tensor(vec![1.0, 0.0, 0.0, 1.0], vec![2, 2]);
+        let c = tensor(vec![3.0, 4.0], vec![2, 1]);
+
+        let out = einsum_reference("ij,jk,kl->il", &[a.as_ref(), b.as_ref(), c.as_ref()]).unwrap();
+
+        assert_eq!(out.data, vec![11.0]);
+    }
+
+    #[test]
+    fn test_reference_shape_mismatch() {
+        let a = tensor(vec![0.0; 6], vec![2, 3]);
+        let b = tensor(vec![0.0; 8], vec![4, 2]);
+
+        assert!(einsum_reference("ij,jk->ik", &[a.as_ref(), b.as_ref()]).is_err());
+    }
+}
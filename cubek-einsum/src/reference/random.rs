@@ -0,0 +1,199 @@
+//! Random einsum expression generation for differential testing.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use super::tensor::{HostElement, HostTensor};
+use crate::rng::SplitMix64;
+
+/// Limits for [`random_expression`].
+#[derive(Debug, Clone)]
+pub struct RandomExpressionConfig {
+    /// Maximum number of operands.
+    pub max_inputs: usize,
+    /// Maximum number of named indices per operand.
+    pub max_rank: usize,
+    /// Maximum extent of a dimension.
+    pub max_dim: usize,
+    /// Maximum number of dimensions covered by an ellipsis (0 disables it).
+    pub max_ellipsis_dims: usize,
+    /// Whether an operand may repeat an index (diagonals and traces).
+    pub allow_repeated: bool,
+}
+
+impl Default for RandomExpressionConfig {
+    fn default() -> Self {
+        Self {
+            max_inputs: 4,
+            max_rank: 3,
+            max_dim: 4,
+            max_ellipsis_dims: 2,
+            allow_repeated: true,
+        }
+    }
+}
+
+/// A valid einsum expression with matching input shapes.
+#[derive(Debug, Clone)]
+pub struct RandomExpression {
+    /// Einsum notation string, e.g. `"ab,bc->ac"`.
+    pub notation: String,
+    /// Shape of each input.
+    pub shapes: Vec<Vec<usize>>,
+}
+
+/// Index letters used by the generator.
+const LETTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+
+/// Generates a random valid einsum expression with matching shapes.
+///
+/// Operands share indices with earlier operands (contractions and batch
+/// dimensions), may repeat an index within a subscript, and may all carry
+/// an ellipsis. The output keeps a random subset of the indices in random
+/// order. Every index appears at most three times in total, which is the
+/// limit enforced by `validate_notation`.
+pub fn random_expression(rng: &mut SplitMix64, config: &RandomExpressionConfig) -> RandomExpression {
+    let num_inputs = rng.range(1, config.max_inputs.max(1));
+
+    let ellipsis_dims = if config.max_ellipsis_dims > 0 && rng.chance(25) {
+        rng.range(0, config.max_ellipsis_dims)
+    } else {
+        0
+    };
+    let has_ellipsis = ellipsis_dims > 0 || (config.max_ellipsis_dims > 0 && rng.chance(5));
+    let batch_shape: Vec<usize> = (0..ellipsis_dims).map(|_| rng.range(1, config.max_dim)).collect();
+
+    // Extent and total number of occurrences of each letter
+    let extents: Vec<usize> = LETTERS.iter().map(|_| rng.range(1, config.max_dim)).collect();
+    let mut counts = [0usize; 26];
+    let mut used: Vec<usize> = Vec::new();
+
+    let mut subscripts: Vec<Vec<usize>> = Vec::with_capacity(num_inputs);
+    for _ in 0..num_inputs {
+        let rank = rng.range(1, config.max_rank.max(1));
+        let mut subscript: Vec<usize> = Vec::with_capacity(rank + 1);
+
+        while subscript.len() < rank {
+            let reusable: Vec<usize> = used
+                .iter()
+                .copied()
+                .filter(|&l| counts[l] < 2 && !subscript.contains(&l))
+                .collect();
+
+            let letter = if !subscript.is_empty() && config.allow_repeated && rng.chance(10) {
+                // Repeat an index of this operand
+                let candidate = subscript[rng.below(subscript.len())];
+                if counts[candidate] >= 2 {
+                    continue;
+                }
+                candidate
+            } else if !reusable.is_empty() && rng.chance(60) {
+                reusable[rng.below(reusable.len())]
+            } else {
+                match (0..LETTERS.len()).find(|l| counts[*l] == 0) {
+                    Some(fresh) => fresh,
+                    None => break,
+                }
+            };
+
+            if counts[letter] == 0 {
+                used.push(letter);
+            }
+            counts[letter] += 1;
+            subscript.push(letter);
+        }
+
+        subscripts.push(subscript);
+    }
+
+    // Output: a random subset of the indices, each once, in random order
+    let mut output: Vec<usize> = used.iter().copied().filter(|&l| counts[l] < 3 && rng.chance(50)).collect();
+    rng.shuffle(&mut output);
+
+    let ellipsis_positions: Vec<usize> = subscripts
+        .iter()
+        .map(|s| rng.range(0, s.len()))
+        .collect();
+    let output_ellipsis = rng.range(0, output.len());
+
+    let mut notation = String::new();
+    let mut shapes = Vec::with_capacity(num_inputs);
+    for (idx, subscript) in subscripts.iter().enumerate() {
+        if idx > 0 {
+            notation.push(',');
+        }
+
+        let mut shape = Vec::with_capacity(subscript.len() + ellipsis_dims);
+        for (pos, &letter) in subscript.iter().enumerate() {
+            if has_ellipsis && pos == ellipsis_positions[idx] {
+                notation.push_str("...");
+                shape.extend_from_slice(&batch_shape);
+            }
+            notation.push(LETTERS[letter] as char);
+            shape.push(extents[letter]);
+        }
+        if has_ellipsis && ellipsis_positions[idx] == subscript.len() {
+            notation.push_str("...");
+            shape.extend_from_slice(&batch_shape);
+        }
+
+        shapes.push(shape);
+    }
+
+    notation.push_str("->");
+    for (pos, &letter) in output.iter().enumerate() {
+        if has_ellipsis && pos == output_ellipsis {
+            notation.push_str("...");
+        }
+        notation.push(LETTERS[letter] as char);
+    }
+    if has_ellipsis && output_ellipsis == output.len() {
+        notation.push_str("...");
+    }
+
+    RandomExpression { notation, shapes }
+}
+
+/// Creates a tensor of small random integers in `-2..=2`.
+///
+/// Small integers keep every sum exact, so plan results can be compared
+/// with the reference without a tolerance.
+pub fn random_tensor<E: HostElement>(rng: &mut SplitMix64, shape: &[usize]) -> HostTensor<E> {
+    let num_elements: usize = shape.iter().product();
+    let data = (0..num_elements)
+        .map(|_| E::from_f64(rng.range(0, 4) as f64 - 2.0))
+        .collect();
+    HostTensor::new(data, shape.to_vec()).expect("data matches shape")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::validation::validate_shapes;
+    use crate::notation::{parse_einsum, validate_notation};
+
+    #[test]
+    fn test_random_expressions_are_valid() {
+        let mut rng = SplitMix64::new(7);
+        let config = RandomExpressionConfig::default();
+
+        for _ in 0..500 {
+            let expr = random_expression(&mut rng, &config);
+            let notation = parse_einsum(&expr.notation).unwrap();
+            validate_notation(&notation).unwrap();
+
+            let shapes: Vec<&[usize]> = expr.shapes.iter().map(|s| s.as_slice()).collect();
+            validate_shapes(&notation, &shapes).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_random_expression_is_deterministic() {
+        let config = RandomExpressionConfig::default();
+        let a = random_expression(&mut SplitMix64::new(42), &config);
+        let b = random_expression(&mut SplitMix64::new(42), &config);
+
+        assert_eq!(a.notation, b.notation);
+        assert_eq!(a.shapes, b.shapes);
+    }
+}
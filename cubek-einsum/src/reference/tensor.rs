@@ -0,0 +1,272 @@
+//! Host tensor representation for reference evaluation.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::ops::{Add, Mul};
+
+use crate::error::{EinsumError, EinsumResult};
+
+/// Element type supported by the host reference implementation.
+pub trait HostElement: Copy + Debug + PartialEq + Add<Output = Self> + Mul<Output = Self> {
+    /// Additive identity.
+    fn zero() -> Self;
+
+    /// Converts to `f64` for tolerance-based comparisons.
+    fn to_f64(self) -> f64;
+
+    /// Converts from `f64`, rounding to the nearest representable value.
+    fn from_f64(value: f64) -> Self;
+}
+
+macro_rules! impl_host_element {
+    ($($ty:ty),*) => {
+        $(
+            impl HostElement for $ty {
+                #[inline]
+                fn zero() -> Self {
+                    0 as $ty
+                }
+
+                #[inline]
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+
+                #[inline]
+                fn from_f64(value: f64) -> Self {
+                    value as $ty
+                }
+            }
+        )*
+    };
+}
+
+impl_host_element!(f32, f64, i32, i64, u32, u64);
+
+impl HostElement for half::f16 {
+    #[inline]
+    fn zero() -> Self {
+        half::f16::ZERO
+    }
+
+    #[inline]
+    fn to_f64(self) -> f64 {
+        self.to_f64()
+    }
+
+    #[inline]
+    fn from_f64(value: f64) -> Self {
+        half::f16::from_f64(value)
+    }
+}
+
+impl HostElement for half::bf16 {
+    #[inline]
+    fn zero() -> Self {
+        half::bf16::ZERO
+    }
+
+    #[inline]
+    fn to_f64(self) -> f64 {
+        self.to_f64()
+    }
+
+    #[inline]
+    fn from_f64(value: f64) -> Self {
+        half::bf16::from_f64(value)
+    }
+}
+
+/// Borrowed host tensor with explicit shape and strides (in elements).
+#[derive(Debug, Clone, Copy)]
+pub struct HostTensorRef<'a, E> {
+    /// Underlying storage.
+    pub data: &'a [E],
+    /// Dimension sizes.
+    pub shape: &'a [usize],
+    /// Stride of each dimension, in elements.
+    pub strides: &'a [usize],
+}
+
+impl<'a, E: HostElement> HostTensorRef<'a, E> {
+    /// Creates a new tensor view.
+    pub fn new(data: &'a [E], shape: &'a [usize], strides: &'a [usize]) -> Self {
+        Self { data, shape, strides }
+    }
+
+    /// Returns the number of dimensions.
+    #[inline]
+    pub fn rank(&self) -> usize {
+        self.shape.len()
+    }
+
+    /// Returns the number of logical elements.
+    pub fn num_elements(&self) -> usize {
+        self.shape.iter().product()
+    }
+
+    /// Checks that shape and strides agree and every element is in bounds.
+    pub fn validate(&self) -> EinsumResult<()> {
+        if self.shape.len() != self.strides.len() {
+            return Err(EinsumError::shape(alloc::format!(
+                "shape has {} dims but strides has {}",
+                self.shape.len(),
+                self.strides.len()
+            )));
+        }
+
+        if self.num_elements() == 0 {
+            return Ok(());
+        }
+
+        let max_offset: usize = self
+            .shape
+            .iter()
+            .zip(self.strides.iter())
+            .map(|(&d, &s)| (d - 1) * s)
+            .sum();
+
+        if max_offset >= self.data.len() {
+            return Err(EinsumError::shape(alloc::format!(
+                "tensor view reaches offset {} but storage has {} elements",
+                max_offset,
+                self.data.len()
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Reads the element at a multi-dimensional position.
+    #[inline]
+    pub fn get(&self, position: &[usize]) -> E {
+        let offset: usize = position
+            .iter()
+            .zip(self.strides.iter())
+            .map(|(&p, &s)| p * s)
+            .sum();
+        self.data[offset]
+    }
+
+    /// Copies the view into a contiguous owned tensor.
+    pub fn to_contiguous(&self) -> HostTensor<E> {
+        let mut output = HostTensor::zeros(self.shape.to_vec());
+        let mut position = vec![0usize; self.rank()];
+
+        for value in output.data.iter_mut() {
+            *value = self.get(&position);
+            increment_position(&mut position, self.shape);
+        }
+
+        output
+    }
+}
+
+/// Owned host tensor with explicit shape and strides (in elements).
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostTensor<E> {
+    /// Underlying storage.
+    pub data: Vec<E>,
+    /// Dimension sizes.
+    pub shape: Vec<usize>,
+    /// Stride of each dimension, in elements.
+    pub strides: Vec<usize>,
+}
+
+impl<E: HostElement> HostTensor<E> {
+    /// Creates a contiguous (row-major) tensor from data and shape.
+    pub fn new(data: Vec<E>, shape: Vec<usize>) -> EinsumResult<Self> {
+        let num_elements: usize = shape.iter().product();
+        if data.len() != num_elements {
+            return Err(EinsumError::shape(alloc::format!(
+                "shape {:?} needs {} elements, got {}",
+                shape,
+                num_elements,
+                data.len()
+            )));
+        }
+
+        let strides = contiguous_strides(&shape);
+        Ok(Self { data, shape, strides })
+    }
+
+    /// Creates a contiguous tensor filled with zeros.
+    pub fn zeros(shape: Vec<usize>) -> Self {
+        let num_elements: usize = shape.iter().product();
+        let strides = contiguous_strides(&shape);
+        Self {
+            data: vec![E::zero(); num_elements],
+            shape,
+            strides,
+        }
+    }
+
+    /// Returns a borrowed view of this tensor.
+    pub fn as_ref(&self) -> HostTensorRef<'_, E> {
+        HostTensorRef {
+            data: &self.data,
+            shape: &self.shape,
+            strides: &self.strides,
+        }
+    }
+
+    /// Returns the number of logical elements.
+    pub fn num_elements(&self) -> usize {
+        self.shape.iter().product()
+    }
+}
+
+/// Computes row-major strides for a shape.
+pub(crate) fn contiguous_strides(shape: &[usize]) -> Vec<usize> {
+    let mut strides = vec![1usize; shape.len()];
+    for i in (0..shape.len().saturating_sub(1)).rev() {
+        strides[i] = strides[i + 1] * shape[i + 1];
+    }
+    strides
+}
+
+/// Advances a row-major multi-index, wrapping around at the end.
+///
+/// Returns false once every position has been visited.
+pub(crate) fn increment_position(position: &mut [usize], shape: &[usize]) -> bool {
+    for dim in (0..position.len()).rev() {
+        position[dim] += 1;
+        if position[dim] < shape[dim] {
+            return true;
+        }
+        position[dim] = 0;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contiguous_strides() {
+        assert_eq!(contiguous_strides(&[2, 3, 4]), vec![12, 4, 1]);
+        assert_eq!(contiguous_strides(&[]), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_to_contiguous_transposed_view() {
+        // 2x3 matrix viewed as its 3x2 transpose
+        let data = [1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let view = HostTensorRef::new(&data, &[3, 2], &[1, 3]);
+
+        let contiguous = view.to_contiguous();
+
+        assert_eq!(contiguous.data, vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+        assert_eq!(contiguous.strides, vec![2, 1]);
+    }
+
+    #[test]
+    fn test_validate_out_of_bounds() {
+        let data = [0.0f32; 4];
+        let view = HostTensorRef::new(&data, &[2, 3], &[3, 1]);
+
+        assert!(view.validate().is_err());
+    }
+}
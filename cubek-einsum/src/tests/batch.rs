@@ -0,0 +1,21 @@
+//! Batched matrix multiplication fast paths.
+
+use cubecl::Runtime;
+
+use super::test_utils::{TestElement, test_einsum};
+
+pub fn test_batched_matmul<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "bij,bjk->bik", &[&[3, 8, 16], &[3, 16, 12]], None);
+}
+
+pub fn test_batched_matmul_two_batch_dims<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "abij,abjk->abik", &[&[2, 3, 4, 5], &[2, 3, 5, 6]], None);
+}
+
+pub fn test_batched_matmul_ellipsis<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "...ij,...jk->...ik", &[&[2, 3, 4, 5], &[2, 3, 5, 6]], None);
+}
+
+pub fn test_attention_scores<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "bhqd,bhkd->bhqk", &[&[2, 2, 8, 4], &[2, 2, 6, 4]], None);
+}
@@ -0,0 +1,136 @@
+//! General contractions and the remaining fast paths.
+
+use alloc::vec::Vec;
+
+use cubecl::prelude::*;
+use cubecl::Runtime;
+use cubecl::std::tensor::TensorHandle;
+
+use crate::launch::{EinsumConfig, PreparedEinsum};
+use crate::reference::{HostTensor, HostTensorRef, SplitMix64, einsum_reference, random_tensor};
+
+use super::test_utils::{TestElement, assert_close, download, test_einsum, test_random_expressions, upload};
+
+pub fn test_chain<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "ij,jk,kl->il", &[&[4, 5], &[5, 6], &[6, 3]], None);
+}
+
+pub fn test_tensor_contraction<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "ijk,jkl->il", &[&[3, 4, 5], &[4, 5, 6]], None);
+}
+
+pub fn test_bilinear_form<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "i,ij,j->", &[&[5], &[5, 6], &[6]], None);
+}
+
+pub fn test_matrix_vector<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "ij,j->i", &[&[6, 7], &[7]], None);
+}
+
+pub fn test_hadamard<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "ij,ij->ij", &[&[6, 7], &[6, 7]], None);
+}
+
+pub fn test_outer_product<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "i,j->ij", &[&[6], &[7]], None);
+}
+
+pub fn test_dot_product<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "i,i->", &[&[33], &[33]], None);
+}
+
+pub fn test_trace<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "ii->", &[&[7, 7]], None);
+}
+
+pub fn test_batched_diagonal<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "bii->bi", &[&[3, 5, 5]], None);
+}
+
+pub fn test_random<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_random_expressions::<R, E>(device, 0, 32);
+}
+
+pub fn test_prepared<R: Runtime, E: TestElement>(device: &R::Device) {
+    let client = R::client(device);
+    let notation = "bhqd,bhkd->bhqk";
+    let shapes: [&[usize]; 2] = [&[2, 3, 4, 8], &[2, 3, 5, 8]];
+    let prepared = PreparedEinsum::new(notation, &shapes, None).unwrap();
+
+    // The same prepared expression runs on fresh inputs every call
+    let mut rng = SplitMix64::new(11);
+    for _ in 0..3 {
+        let host_inputs: Vec<HostTensor<E>> = shapes.iter().map(|s| random_tensor(&mut rng, s)).collect();
+        let refs: Vec<HostTensorRef<'_, E>> = host_inputs.iter().map(|t| t.as_ref()).collect();
+        let expected = einsum_reference(notation, &refs).unwrap();
+
+        let inputs: Vec<TensorHandle<R>> = host_inputs.iter().map(|t| upload(&client, t)).collect();
+        let input_refs: Vec<&TensorHandle<R>> = inputs.iter().collect();
+        let mut output = TensorHandle::empty(
+            &client,
+            prepared.output_shape().to_vec(),
+            E::as_type_native_unchecked(),
+        );
+
+        prepared.execute::<R, E>(&client, &input_refs, &mut output).unwrap();
+
+        let actual = download::<R, E>(&client, &output);
+        assert_close(&actual.data, &expected.data, E::TOLERANCE).unwrap();
+    }
+}
+
+pub fn test_imported_path<R: Runtime, E: TestElement>(device: &R::Device) {
+    use crate::notation::parse_einsum;
+    use crate::optimization::{plan_from_path, ContractionPath, ContractionTree, CostModel};
+
+    let client = R::client(device);
+    let notation = "ij,jk,kl,lm->im";
+    let shapes: [&[usize]; 4] = [&[3, 4], &[4, 5], &[5, 6], &[6, 2]];
+
+    // ((0, 1), (2, 3)), a path the built-in search would not pick
+    let tree = ContractionTree::node(
+        ContractionTree::node(ContractionTree::Leaf(0), ContractionTree::Leaf(1)),
+        ContractionTree::node(ContractionTree::Leaf(2), ContractionTree::Leaf(3)),
+    );
+    let parsed = parse_einsum(notation).unwrap();
+    let path = ContractionPath::from_tree(&parsed, &shapes, &tree, &CostModel::default()).unwrap();
+    let plan = plan_from_path(&parsed, &shapes, path).unwrap();
+    let prepared = PreparedEinsum::with_plan(notation, &shapes, plan, None).unwrap();
+
+    let mut rng = SplitMix64::new(17);
+    let host_inputs: Vec<HostTensor<E>> = shapes.iter().map(|s| random_tensor(&mut rng, s)).collect();
+    let refs: Vec<HostTensorRef<'_, E>> = host_inputs.iter().map(|t| t.as_ref()).collect();
+    let expected = einsum_reference(notation, &refs).unwrap();
+
+    let inputs: Vec<TensorHandle<R>> = host_inputs.iter().map(|t| upload(&client, t)).collect();
+    let input_refs: Vec<&TensorHandle<R>> = inputs.iter().collect();
+    let mut output = TensorHandle::empty(
+        &client,
+        prepared.output_shape().to_vec(),
+        E::as_type_native_unchecked(),
+    );
+
+    prepared.execute::<R, E>(&client, &input_refs, &mut output).unwrap();
+
+    let actual = download::<R, E>(&client, &output);
+    assert_close(&actual.data, &expected.data, E::TOLERANCE).unwrap();
+}
+
+pub fn test_plan_cache<R: Runtime, E: TestElement>(device: &R::Device) {
+    let config = EinsumConfig::new().with_plan_cache(true);
+    let shapes: [&[usize]; 3] = [&[4, 5], &[5, 6], &[6, 3]];
+
+    // The second notation reuses the plan cached for the first one
+    test_einsum::<R, E>(device, "ij,jk,kl->il", &shapes, Some(config.clone()));
+    test_einsum::<R, E>(device, "ab,bc,cd->ad", &shapes, Some(config));
+}
+
+pub fn test_sliced<R: Runtime, E: TestElement>(device: &R::Device) {
+    // Both limits are below every intermediate the paths can create, so the
+    // plans loop over contracted indices and sum the slices
+    let config = EinsumConfig::new().with_memory_limit(8);
+    test_einsum::<R, E>(device, "ij,jk,kl->il", &[&[4, 5], &[5, 6], &[6, 3]], Some(config));
+
+    let config = EinsumConfig::new().with_memory_limit(4);
+    test_einsum::<R, E>(device, "ab,bc,cd,da->", &[&[3, 4], &[4, 5], &[5, 6], &[6, 3]], Some(config));
+}
@@ -0,0 +1,25 @@
+//! Matrix multiplication fast paths.
+
+use cubecl::Runtime;
+
+use super::test_utils::{TestElement, test_einsum};
+
+pub fn test_matmul_basic<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "ij,jk->ik", &[&[8, 16], &[16, 12]], None);
+}
+
+pub fn test_matmul_transpose_a<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "ji,jk->ik", &[&[16, 8], &[16, 12]], None);
+}
+
+pub fn test_matmul_transpose_b<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "ik,jk->ij", &[&[8, 16], &[12, 16]], None);
+}
+
+pub fn test_matmul_transpose_both<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "ki,jk->ij", &[&[16, 8], &[12, 16]], None);
+}
+
+pub fn test_matmul_non_square<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "nm,md->nd", &[&[33, 7], &[7, 5]], None);
+}
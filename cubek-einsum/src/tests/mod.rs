@@ -0,0 +1,355 @@
+//! Runtime-generic kernel and executor test suite.
+//!
+//! The suite runs every case on a real runtime and compares the result with
+//! the host reference evaluator. Instantiate it in any test crate that has a
+//! `TestRuntime` type alias in scope, e.g. on cubecl's CPU runtime:
+//!
+//! ```ignore
+//! pub type TestRuntime = cubecl::cpu::CpuRuntime;
+//!
+//! cubek_einsum::testgen_einsum!();
+//! ```
+//!
+//! The `einsum_tests_*` features of this crate select which groups
+//! (`matmul`, `batch`, `reduce`, `transpose`, `contraction`) and which float
+//! types (`f32`, `f16`) are generated.
+
+pub mod test_utils;
+
+pub mod batch;
+pub mod contraction;
+pub mod matmul;
+pub mod reduce;
+pub mod transpose;
+
+#[doc(hidden)]
+pub use half::f16;
+
+/// Generates the einsum test suite for `TestRuntime`.
+#[macro_export]
+macro_rules! testgen_einsum {
+    () => {
+        mod einsum {
+            use super::*;
+
+            $crate::testgen_einsum_f32!();
+            $crate::testgen_einsum_f16!();
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! testgen_einsum_groups {
+    () => {
+        $crate::testgen_einsum_matmul!();
+        $crate::testgen_einsum_batch!();
+        $crate::testgen_einsum_reduce!();
+        $crate::testgen_einsum_transpose!();
+        $crate::testgen_einsum_contraction!();
+    };
+}
+
+#[cfg(feature = "einsum_tests_f32")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! testgen_einsum_f32 {
+    () => {
+        mod f32_ty {
+            use super::*;
+
+            type FloatT = f32;
+
+            $crate::testgen_einsum_groups!();
+        }
+    };
+}
+
+#[cfg(not(feature = "einsum_tests_f32"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! testgen_einsum_f32 {
+    () => {};
+}
+
+#[cfg(feature = "einsum_tests_f16")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! testgen_einsum_f16 {
+    () => {
+        mod f16_ty {
+            use super::*;
+
+            type FloatT = $crate::tests::f16;
+
+            $crate::testgen_einsum_groups!();
+        }
+    };
+}
+
+#[cfg(not(feature = "einsum_tests_f16"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! testgen_einsum_f16 {
+    () => {};
+}
+
+#[cfg(feature = "einsum_tests_matmul")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! testgen_einsum_matmul {
+    () => {
+        mod matmul {
+            use super::*;
+
+            #[test]
+            fn test_matmul_basic() {
+                $crate::tests::matmul::test_matmul_basic::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_matmul_transpose_a() {
+                $crate::tests::matmul::test_matmul_transpose_a::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_matmul_transpose_b() {
+                $crate::tests::matmul::test_matmul_transpose_b::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_matmul_transpose_both() {
+                $crate::tests::matmul::test_matmul_transpose_both::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_matmul_non_square() {
+                $crate::tests::matmul::test_matmul_non_square::<TestRuntime, FloatT>(&Default::default());
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "einsum_tests_matmul"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! testgen_einsum_matmul {
+    () => {};
+}
+
+#[cfg(feature = "einsum_tests_batch")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! testgen_einsum_batch {
+    () => {
+        mod batch {
+            use super::*;
+
+            #[test]
+            fn test_batched_matmul() {
+                $crate::tests::batch::test_batched_matmul::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_batched_matmul_two_batch_dims() {
+                $crate::tests::batch::test_batched_matmul_two_batch_dims::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_batched_matmul_ellipsis() {
+                $crate::tests::batch::test_batched_matmul_ellipsis::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_attention_scores() {
+                $crate::tests::batch::test_attention_scores::<TestRuntime, FloatT>(&Default::default());
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "einsum_tests_batch"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! testgen_einsum_batch {
+    () => {};
+}
+
+#[cfg(feature = "einsum_tests_reduce")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! testgen_einsum_reduce {
+    () => {
+        mod reduce {
+            use super::*;
+
+            #[test]
+            fn test_reduce_last_axis() {
+                $crate::tests::reduce::test_reduce_last_axis::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_reduce_first_axis() {
+                $crate::tests::reduce::test_reduce_first_axis::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_reduce_outer_axes() {
+                $crate::tests::reduce::test_reduce_outer_axes::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_reduce_middle_axis() {
+                $crate::tests::reduce::test_reduce_middle_axis::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_reduce_all() {
+                $crate::tests::reduce::test_reduce_all::<TestRuntime, FloatT>(&Default::default());
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "einsum_tests_reduce"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! testgen_einsum_reduce {
+    () => {};
+}
+
+#[cfg(feature = "einsum_tests_transpose")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! testgen_einsum_transpose {
+    () => {
+        mod transpose {
+            use super::*;
+
+            #[test]
+            fn test_transpose_2d() {
+                $crate::tests::transpose::test_transpose_2d::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_transpose_3d() {
+                $crate::tests::transpose::test_transpose_3d::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_transpose_partial() {
+                $crate::tests::transpose::test_transpose_partial::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_transpose_output_view() {
+                $crate::tests::transpose::test_transpose_output_view::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_identity() {
+                $crate::tests::transpose::test_identity::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_reduce_then_transpose() {
+                $crate::tests::transpose::test_reduce_then_transpose::<TestRuntime, FloatT>(&Default::default());
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "einsum_tests_transpose"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! testgen_einsum_transpose {
+    () => {};
+}
+
+#[cfg(feature = "einsum_tests_contraction")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! testgen_einsum_contraction {
+    () => {
+        mod contraction {
+            use super::*;
+
+            #[test]
+            fn test_chain() {
+                $crate::tests::contraction::test_chain::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_tensor_contraction() {
+                $crate::tests::contraction::test_tensor_contraction::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_bilinear_form() {
+                $crate::tests::contraction::test_bilinear_form::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_matrix_vector() {
+                $crate::tests::contraction::test_matrix_vector::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_hadamard() {
+                $crate::tests::contraction::test_hadamard::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_outer_product() {
+                $crate::tests::contraction::test_outer_product::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_dot_product() {
+                $crate::tests::contraction::test_dot_product::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_trace() {
+                $crate::tests::contraction::test_trace::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_batched_diagonal() {
+                $crate::tests::contraction::test_batched_diagonal::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_random() {
+                $crate::tests::contraction::test_random::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_prepared() {
+                $crate::tests::contraction::test_prepared::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_imported_path() {
+                $crate::tests::contraction::test_imported_path::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_plan_cache() {
+                $crate::tests::contraction::test_plan_cache::<TestRuntime, FloatT>(&Default::default());
+            }
+
+            #[test]
+            fn test_sliced() {
+                $crate::tests::contraction::test_sliced::<TestRuntime, FloatT>(&Default::default());
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "einsum_tests_contraction"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! testgen_einsum_contraction {
+    () => {};
+}
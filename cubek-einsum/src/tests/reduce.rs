@@ -0,0 +1,25 @@
+//! Reduction fast paths.
+
+use cubecl::Runtime;
+
+use super::test_utils::{TestElement, test_einsum};
+
+pub fn test_reduce_last_axis<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "ij->i", &[&[8, 16]], None);
+}
+
+pub fn test_reduce_first_axis<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "ij->j", &[&[8, 16]], None);
+}
+
+pub fn test_reduce_outer_axes<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "ijk->j", &[&[4, 5, 6]], None);
+}
+
+pub fn test_reduce_middle_axis<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "ijk->ik", &[&[4, 5, 6]], None);
+}
+
+pub fn test_reduce_all<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "ijk->", &[&[4, 5, 6]], None);
+}
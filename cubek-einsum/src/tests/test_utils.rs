@@ -0,0 +1,161 @@
+//! Helpers shared by the runtime-generic test suite.
+
+use alloc::vec::Vec;
+
+use cubecl::prelude::*;
+use cubecl::CubeElement;
+use cubecl::Runtime;
+use cubecl::client::ComputeClient;
+use cubecl::std::tensor::TensorHandle;
+
+use crate::error::{EinsumError, EinsumResult};
+use crate::launch::{EinsumConfig, einsum, einsum_output_shape};
+use crate::notation::parse_einsum;
+use crate::reference::{
+    HostElement, HostTensor, HostTensorRef, RandomExpressionConfig, SplitMix64, einsum_reference,
+    random_expression, random_tensor,
+};
+
+/// Element types the suite runs on.
+pub trait TestElement: CubePrimitive + Numeric + CubeElement + HostElement {
+    /// Maximum relative error accepted against the reference.
+    const TOLERANCE: f64;
+}
+
+impl TestElement for f32 {
+    const TOLERANCE: f64 = 1e-4;
+}
+
+impl TestElement for half::f16 {
+    const TOLERANCE: f64 = 1e-2;
+}
+
+/// Uploads a host tensor to the device.
+pub fn upload<R: Runtime, E: TestElement>(
+    client: &ComputeClient<R>,
+    tensor: &HostTensor<E>,
+) -> TensorHandle<R> {
+    let handle = client.create_from_slice(E::as_bytes(&tensor.data));
+    TensorHandle::new_contiguous(tensor.shape.clone(), handle, E::as_type_native_unchecked())
+}
+
+/// Reads a device tensor back, honoring its shape and strides.
+pub fn download<R: Runtime, E: TestElement>(
+    client: &ComputeClient<R>,
+    tensor: &TensorHandle<R>,
+) -> HostTensor<E> {
+    let bytes = client.read_one(tensor.handle.clone());
+    let data = E::from_bytes(&bytes);
+    HostTensorRef::new(data, &tensor.shape, &tensor.strides).to_contiguous()
+}
+
+/// Runs `notation` on random inputs and compares with the host reference.
+pub fn test_einsum<R: Runtime, E: TestElement>(
+    device: &R::Device,
+    notation: &str,
+    shapes: &[&[usize]],
+    config: Option<EinsumConfig>,
+) {
+    let client = R::client(device);
+    let mut rng = SplitMix64::new(shapes.iter().flat_map(|s| s.iter()).sum::<usize>() as u64);
+
+    let host_inputs: Vec<HostTensor<E>> = shapes.iter().map(|s| random_tensor(&mut rng, s)).collect();
+    if let Err(err) = run_against_reference::<R, E>(&client, notation, &host_inputs, config) {
+        panic!("{}: {}", notation, err);
+    }
+}
+
+/// Runs `notation` on the given inputs and compares with the host reference.
+///
+/// The result is read from the buffer allocated before the call, so fast
+/// paths that replace the output handle instead of writing to it fail here.
+/// Configs that allow output views are read through the returned handle.
+pub fn run_against_reference<R: Runtime, E: TestElement>(
+    client: &ComputeClient<R>,
+    notation: &str,
+    host_inputs: &[HostTensor<E>],
+    config: Option<EinsumConfig>,
+) -> EinsumResult<()> {
+    let refs: Vec<HostTensorRef<'_, E>> = host_inputs.iter().map(|t| t.as_ref()).collect();
+    let expected = einsum_reference(notation, &refs)?;
+
+    let inputs: Vec<TensorHandle<R>> = host_inputs.iter().map(|t| upload(client, t)).collect();
+    let input_refs: Vec<&TensorHandle<R>> = inputs.iter().collect();
+
+    let shapes: Vec<&[usize]> = host_inputs.iter().map(|t| t.shape.as_slice()).collect();
+    let output_shape = einsum_output_shape(notation, &shapes)?;
+    let allocated = TensorHandle::empty(client, output_shape, E::as_type_native_unchecked());
+    let allows_view = config.as_ref().is_some_and(|c| c.allow_output_view);
+
+    let mut output = allocated.clone();
+    einsum::<R, E>(client, notation, &input_refs, &mut output, config)?;
+
+    let result = if allows_view { &output } else { &allocated };
+    let actual = download::<R, E>(client, result);
+    assert_close(&actual.data, &expected.data, E::TOLERANCE)
+}
+
+/// Checks that two buffers agree within a relative tolerance.
+pub fn assert_close<E: HostElement>(actual: &[E], expected: &[E], tolerance: f64) -> EinsumResult<()> {
+    if actual.len() != expected.len() {
+        return Err(EinsumError::shape(alloc::format!(
+            "got {} elements, expected {}",
+            actual.len(),
+            expected.len()
+        )));
+    }
+
+    for (idx, (&a, &e)) in actual.iter().zip(expected.iter()).enumerate() {
+        let (a, e) = (a.to_f64(), e.to_f64());
+        if (a - e).abs() > tolerance * e.abs().max(1.0) {
+            return Err(EinsumError::launch(alloc::format!(
+                "element {}: got {}, expected {}",
+                idx, a, e
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether the executor is known not to support an expression.
+///
+/// Only diagonals of a single operand that no fast path covers, such as
+/// `aab->b`, are: the plan has no step to take them.
+fn is_known_unsupported(notation: &str) -> bool {
+    let Ok(notation) = parse_einsum(notation) else {
+        return false;
+    };
+    let Some(input) = notation.inputs().first() else {
+        return false;
+    };
+    let indices: Vec<char> = input.named_indices().collect();
+    notation.is_unary() && indices.iter().enumerate().any(|(pos, c)| indices[..pos].contains(c))
+}
+
+/// Runs random expressions against the reference.
+///
+/// Any error fails the case. Unsupported expressions are only skipped when
+/// [`is_known_unsupported`].
+pub fn test_random_expressions<R: Runtime, E: TestElement>(device: &R::Device, seed: u64, count: usize) {
+    let client = R::client(device);
+    let config = RandomExpressionConfig {
+        max_inputs: 3,
+        ..Default::default()
+    };
+
+    for case in 0..count {
+        let mut rng = SplitMix64::new(seed.wrapping_add(case as u64));
+        let expr = random_expression(&mut rng, &config);
+        let inputs: Vec<HostTensor<E>> = expr.shapes.iter().map(|s| random_tensor(&mut rng, s)).collect();
+
+        match run_against_reference::<R, E>(&client, &expr.notation, &inputs, None) {
+            Ok(()) => {}
+            Err(EinsumError::Unsupported { .. }) if is_known_unsupported(&expr.notation) => {}
+            Err(err) => panic!(
+                "case {} (seed {}): {} with shapes {:?}: {}",
+                case, seed, expr.notation, expr.shapes, err
+            ),
+        }
+    }
+}
@@ -0,0 +1,32 @@
+//! Transpose fast paths.
+
+use cubecl::Runtime;
+
+use crate::launch::EinsumConfig;
+
+use super::test_utils::{TestElement, test_einsum};
+
+pub fn test_transpose_2d<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "ij->ji", &[&[8, 16]], None);
+}
+
+pub fn test_transpose_3d<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "ijk->kij", &[&[3, 4, 5]], None);
+}
+
+pub fn test_transpose_partial<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "ijkl->jilk", &[&[2, 3, 4, 5]], None);
+}
+
+pub fn test_transpose_output_view<R: Runtime, E: TestElement>(device: &R::Device) {
+    let config = EinsumConfig::new().with_output_view(true);
+    test_einsum::<R, E>(device, "ijk->kij", &[&[3, 4, 5]], Some(config));
+}
+
+pub fn test_identity<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "ij->ij", &[&[4, 6]], None);
+}
+
+pub fn test_reduce_then_transpose<R: Runtime, E: TestElement>(device: &R::Device) {
+    test_einsum::<R, E>(device, "ijk->ki", &[&[3, 4, 5]], None);
+}
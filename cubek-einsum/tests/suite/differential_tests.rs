@@ -0,0 +1,114 @@
+//! Randomized differential tests.
+//!
+//! Generates random valid expressions, plans them with every strategy and
+//! compares the interpreted plan against the naive reference evaluation.
+
+use cubek_einsum::EinsumError;
+use cubek_einsum::notation::{EinsumNotation, parse_einsum};
+use cubek_einsum::optimization::{ContractionStrategy, create_plan};
+use cubek_einsum::reference::{
+    HostTensor, HostTensorRef, RandomExpression, RandomExpressionConfig, SplitMix64,
+    evaluate_notation, interpret_plan, random_expression, random_tensor,
+};
+
+const NUM_CASES: u64 = 400;
+
+/// Every strategy, the explicit one contracting the operands left to right.
+fn strategies(num_inputs: usize) -> Vec<ContractionStrategy> {
+    vec![
+        ContractionStrategy::Greedy,
+        ContractionStrategy::RandomGreedy { trials: 8, temperature: 1.0, seed: 0 },
+        ContractionStrategy::Partition { trials: 4, seed: 0 },
+        ContractionStrategy::Optimal,
+        ContractionStrategy::ConnectedOptimal { outer_products: false },
+        ContractionStrategy::ConnectedOptimal { outer_products: true },
+        ContractionStrategy::branch_bound(),
+        ContractionStrategy::Auto,
+        ContractionStrategy::Explicit(vec![(0, 1); num_inputs.saturating_sub(1)]),
+    ]
+}
+
+/// Whether the executor is known not to support an expression.
+///
+/// Only diagonals of a single operand that no fast path covers, such as
+/// `aab->b`, are: the plan has no step to take them.
+fn is_known_unsupported(notation: &EinsumNotation) -> bool {
+    let Some(input) = notation.inputs().first() else {
+        return false;
+    };
+    let indices: Vec<char> = input.named_indices().collect();
+    notation.is_unary() && indices.iter().enumerate().any(|(pos, c)| indices[..pos].contains(c))
+}
+
+/// Plans and interprets one expression under every strategy.
+///
+/// Any error fails the case. Unsupported steps are only accepted for
+/// [`is_known_unsupported`] expressions.
+fn check_expression(expr: &RandomExpression, rng: &mut SplitMix64) -> Result<(), String> {
+    let notation = parse_einsum(&expr.notation).map_err(|e| format!("parse: {}", e))?;
+    let shapes: Vec<&[usize]> = expr.shapes.iter().map(|s| s.as_slice()).collect();
+
+    let tensors: Vec<HostTensor<f32>> = shapes.iter().map(|s| random_tensor(rng, s)).collect();
+    let inputs: Vec<HostTensorRef<'_, f32>> = tensors.iter().map(|t| t.as_ref()).collect();
+
+    let expected = evaluate_notation(&notation, &inputs).map_err(|e| format!("reference: {}", e))?;
+
+    for strategy in strategies(notation.num_inputs()) {
+        let plan = create_plan(&notation, &shapes, strategy.clone())
+            .map_err(|e| format!("{:?}: plan: {}", strategy, e))?;
+        let actual = match interpret_plan(&notation, &plan, &inputs) {
+            Ok(actual) => actual,
+            Err(EinsumError::Unsupported { .. }) if is_known_unsupported(&notation) => return Ok(()),
+            Err(e) => return Err(format!("{:?}: {}\n{:#?}", strategy, e, plan)),
+        };
+
+        if actual != expected {
+            return Err(format!(
+                "{:?}: got {:?}, expected {:?}\n{:#?}",
+                strategy, actual, expected, plan
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn run_cases(seed: u64, config: &RandomExpressionConfig) {
+    for case in 0..NUM_CASES {
+        let mut rng = SplitMix64::new(seed.wrapping_mul(1_000_003).wrapping_add(case));
+        let expr = random_expression(&mut rng, config);
+
+        if let Err(message) = check_expression(&expr, &mut rng) {
+            panic!(
+                "case {} (seed {}): {} with shapes {:?}\n{}",
+                case, seed, expr.notation, expr.shapes, message
+            );
+        }
+    }
+}
+
+#[test]
+fn test_differential_default() {
+    run_cases(0, &RandomExpressionConfig::default());
+}
+
+#[test]
+fn test_differential_many_operands() {
+    let config = RandomExpressionConfig {
+        max_inputs: 6,
+        max_rank: 2,
+        max_dim: 3,
+        ..Default::default()
+    };
+    run_cases(1, &config);
+}
+
+#[test]
+fn test_differential_without_ellipsis_or_repeats() {
+    let config = RandomExpressionConfig {
+        max_ellipsis_dims: 0,
+        allow_repeated: false,
+        ..Default::default()
+    };
+    run_cases(2, &config);
+}
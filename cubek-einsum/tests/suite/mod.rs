@@ -3,3 +3,13 @@
 mod parser_tests;
 mod pattern_tests;
 mod optimization_tests;
+mod differential_tests;
+
+#[cfg(any(
+    feature = "einsum_tests_matmul",
+    feature = "einsum_tests_reduce",
+    feature = "einsum_tests_transpose",
+    feature = "einsum_tests_batch",
+    feature = "einsum_tests_contraction",
+))]
+mod integration_tests;